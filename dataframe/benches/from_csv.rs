@@ -0,0 +1,33 @@
+//! Times reading a large CSV file, to compare changes to the parser. Run with
+//! `cargo bench -p dataframe`.
+
+use std::fmt::Write;
+use std::time::Instant;
+
+use dataframe::{CsvOptions, DataFrameView};
+
+const ROWS: usize = 1_000_000;
+const RUNS: usize = 5;
+
+/// A CSV like an exported launch file, with a text column that is sometimes quoted.
+fn csv() -> String {
+    let mut text = String::from("sensor,timestamp,accel,alt,note\n");
+    for row in 0..ROWS {
+        let sensor = ["imu", "gps", "baro"][row % 3];
+        let note = if row % 10 == 0 { "\"fix, 3d\"" } else { "" };
+        writeln!(text, "{},{},{},{},{}", sensor, row * 10, row as f32 * 0.25 - 9.81, 1200.5 + row as f32, note).unwrap();
+    }
+    text
+}
+
+fn main() {
+    let text = csv();
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let (view, _) = DataFrameView::from_csv(&mut text.as_bytes(), Some(text.len() as u64), CsvOptions::default(), |_| {}).unwrap();
+        best = best.min(start.elapsed().as_secs_f64());
+        assert_eq!(view.shape().rows, ROWS);
+    }
+    println!("from_csv: {} rows, {:.1} MB in {:.3} s at best, {:.0} MB/s", ROWS, text.len() as f64 / 1e6, best, text.len() as f64 / 1e6 / best);
+}
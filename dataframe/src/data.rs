@@ -32,7 +32,7 @@ impl Context {
         } else {
             let key = unsafe { NonZeroU32::new_unchecked(self.interner.interned.len() as u32) };
             let storage = String::from(s).into_boxed_str();
-            self.interner.map.insert(unsafe { std::mem::transmute(storage.as_ref()) }, key);
+            self.interner.map.insert(unsafe { std::mem::transmute::<&str, &'static str>(storage.as_ref()) }, key);
             self.interner.interned.push(storage);
             key
         }
@@ -43,6 +43,37 @@ impl Context {
             unsafe { std::mem::transmute(storage.as_ref()) }
         })
     }
+
+    /// Interns every string of `other`, and returns the symbol each of its symbols became here,
+    /// indexed by the raw symbol. Null, 0, stays 0, so raw cells can be translated with a lookup.
+    pub(crate) fn merge(&mut self, other: &Context) -> Vec<u32> {
+        self.reserve(other.distinct_strings());
+        let mut remap = Vec::with_capacity(other.interner.interned.len());
+        remap.push(0);
+        for s in &other.interner.interned[1..] {
+            remap.push(self.get_or_intern(s).get());
+        }
+        remap
+    }
+
+    /// Makes room for `additional` more distinct strings, without changing those already interned.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.interner.map.reserve(additional);
+        self.interner.interned.reserve(additional);
+    }
+
+    /// How many distinct strings have been interned.
+    pub(crate) fn distinct_strings(&self) -> usize {
+        // The first slot holds the empty string standing in for null, which is never looked up.
+        self.interner.interned.len() - 1
+    }
+
+    /// Roughly how many bytes the interned strings take up, including the lookup table.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        let strings: usize = self.interner.interned.iter().map(|s| s.len()).sum();
+        let entry = std::mem::size_of::<Box<str>>() + std::mem::size_of::<(&str, NonZeroU32)>();
+        strings + self.interner.interned.len() * entry
+    }
 }
 
 
@@ -62,6 +93,7 @@ pub enum Data<'a> {
     #[default]
     Null,
     Integer(i32),
+    Unsigned(u32),
     Str(&'a str),
     Float(f32),
 }
@@ -70,6 +102,7 @@ impl<'a> Data<'a> {
     pub fn as_integer(&self) -> Option<i32> {
         match *self {
             Data::Integer(num) => Some(num),
+            Data::Unsigned(num) => i32::try_from(num).ok(),
             Data::Str(s) => s.parse::<i32>().ok(),
             Data::Float(num) => Some(num as i32),
             Data::Null => None
@@ -79,25 +112,29 @@ impl<'a> Data<'a> {
     pub fn as_float(&self) -> Option<f32> {
         match *self {
             Data::Integer(num) => Some(num as f32),
+            Data::Unsigned(num) => Some(num as f32),
             Data::Str(s) => s.parse::<f32>().ok(),
             Data::Float(num) => Some(num),
             Data::Null => None
         }
     }
 
-    pub fn as_str(&self) -> Option<Cow<str>> {
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
         match *self {
             Data::Integer(num) => Some(num.to_string().into()),
+            Data::Unsigned(num) => Some(num.to_string().into()),
             Data::Str(s) => Some(s.into()),
             Data::Float(num) => Some(num.to_string().into()),
             Data::Null => None
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn eq(&self, other: &Data) -> bool {
         match (self, other) {
             (Data::Null, Data::Null) => true,
             (Data::Integer(a), Data::Integer(b)) => a == b,
+            (Data::Unsigned(a), Data::Unsigned(b)) => a == b,
             (Data::Float(a), Data::Float(b)) => a.total_cmp(b).is_eq(),
             (Data::Str(a), Data::Str(b)) => a == b,
             _ => false
@@ -108,6 +145,7 @@ impl<'a> Data<'a> {
         match (self, other) {
             (Data::Null, Data::Null) => Some(Ordering::Equal),
             (Data::Integer(a), Data::Integer(b)) => Some(a.cmp(b)),
+            (Data::Unsigned(a), Data::Unsigned(b)) => Some(a.cmp(b)),
             (Data::Float(a), Data::Float(b)) => Some(a.total_cmp(b)),
             (Data::Str(a), Data::Str(b)) => Some(a.cmp(b)),
             _ => None
@@ -117,12 +155,12 @@ impl<'a> Data<'a> {
     pub fn in_bounds<'b>(&self, range: impl RangeBounds<Data<'b>>) -> bool {
         match range.start_bound() {
             Bound::Included(value) => {
-                if self.compare(value).map_or(false, |ord| ord.is_lt()) {
+                if self.compare(value).is_some_and(|ord| ord.is_lt()) {
                     return false;
                 }
             }
             Bound::Excluded(value) => {
-                if self.compare(value).map_or(false, |ord| ord.is_le()) {
+                if self.compare(value).is_some_and(|ord| ord.is_le()) {
                     return false;
                 }
             }
@@ -131,12 +169,12 @@ impl<'a> Data<'a> {
 
         match range.end_bound() {
             Bound::Included(value) => {
-                if self.compare(value).map_or(false, |ord| ord.is_gt()) {
+                if self.compare(value).is_some_and(|ord| ord.is_gt()) {
                     return false;
                 }
             }
             Bound::Excluded(value) => {
-                if self.compare(value).map_or(false, |ord| ord.is_ge()) {
+                if self.compare(value).is_some_and(|ord| ord.is_ge()) {
                     return false;
                 }
             }
@@ -157,6 +195,9 @@ impl Display for Data<'_> {
             Data::Integer(num) => {
                 write!(f, "{}", num)
             }
+            Data::Unsigned(num) => {
+                write!(f, "{}", num)
+            }
             Data::Str(s) => {
                 write!(f, "{}", s)
             }
@@ -172,22 +213,49 @@ impl Display for Data<'_> {
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum DataType {
+    /// A signed 32 bit integer. `i32::MIN` shares its stored form with an empty cell, so it reads
+    /// back as null.
     Integer,
+    /// An unsigned 32 bit integer. `u32::MAX` reads back as null.
+    Unsigned,
     Float,
     Intern
 }
 
 impl DataType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Integer => "Integer",
+            DataType::Unsigned => "Unsigned",
+            DataType::Float => "Float",
+            DataType::Intern => "String"
+        }
+    }
+
+    /// The type with the given [`name`](Self::name), ignoring case.
+    pub fn from_name(name: &str) -> Option<DataType> {
+        [DataType::Integer, DataType::Unsigned, DataType::Float, DataType::Intern].into_iter()
+            .find(|ty| ty.name().eq_ignore_ascii_case(name))
+    }
+
     pub fn parse_str<'a>(&self, s: &'a str) -> Data<'a> {
         match self {
-            DataType::Integer => s.parse::<i32>().ok().map_or(Data::Null, |num| Data::Integer(num)),
-            DataType::Float => s.parse::<f32>().ok().map_or(Data::Null, |num| Data::Float(num)),
+            DataType::Integer => s.parse::<i32>().ok().map_or(Data::Null, Data::Integer),
+            DataType::Unsigned => s.parse::<u32>().ok().map_or(Data::Null, Data::Unsigned),
+            DataType::Float => s.parse::<f32>().ok().map_or(Data::Null, Data::Float),
             DataType::Intern => Data::Str(s)
         }
     }
 
+    // Integers are stored with the sign bit flipped so that the all-zero null pattern is `i32::MIN`,
+    // which is then the only integer that can't be stored.
     fn convert_integer(bits: NonZeroU32) -> i32 {
-        (!bits.get() as i32).wrapping_add(2)
+        (bits.get() ^ 0x8000_0000) as i32
+    }
+
+    // Unsigned integers are stored one higher, so `u32::MAX` is the only one that can't be stored.
+    fn convert_unsigned(bits: NonZeroU32) -> u32 {
+        bits.get() - 1
     }
 
     fn convert_float(bits: NonZeroU32) -> f32 {
@@ -199,7 +267,11 @@ impl DataType {
     }
 
     fn unconvert_integer(num: i32) -> u32 {
-        (!num as u32).wrapping_add(2)
+        num as u32 ^ 0x8000_0000
+    }
+
+    fn unconvert_unsigned(num: u32) -> u32 {
+        num.wrapping_add(1)
     }
 
     fn unconvert_float(num: f32) -> u32 {
@@ -210,12 +282,15 @@ impl DataType {
         ctx.get_or_intern(s).get()
     }
 
-    pub(crate) fn to_data<'df>(&self, bits: u32, ctx: &'df Context) -> Data<'df> {
+    pub(crate) fn to_data<'df>(self, bits: u32, ctx: &'df Context) -> Data<'df> {
         if let Some(bits) = NonZeroU32::new(bits) {
             match self {
                 DataType::Integer => {
                     Data::Integer(Self::convert_integer(bits))
                 },
+                DataType::Unsigned => {
+                    Data::Unsigned(Self::convert_unsigned(bits))
+                },
                 DataType::Float => {
                     Data::Float(Self::convert_float(bits))
                 },
@@ -237,11 +312,22 @@ impl DataType {
                     0u32
                 }
             }
+            DataType::Unsigned => {
+                if let Data::Unsigned(num) = data {
+                    Self::unconvert_unsigned(num)
+                } else if let Data::Integer(num) = data {
+                    u32::try_from(num).map_or(0u32, Self::unconvert_unsigned)
+                } else {
+                    0u32
+                }
+            }
             DataType::Float => {
                 if let Data::Float(num) = data {
                     Self::unconvert_float(num)
                 } else if let Data::Integer(num) = data {
                     Self::unconvert_float(num as f32)
+                } else if let Data::Unsigned(num) = data {
+                    Self::unconvert_float(num as f32)
                 } else {
                     0u32
                 }
@@ -256,6 +342,41 @@ impl DataType {
         }
     }
 
+    /// Reads `bits` of this type as type `to`, such as text as a number or a decimal rounded to a
+    /// whole number. Values that `to` can't hold, such as text that isn't a number, become null.
+    pub(crate) fn convert_to(self, bits: u32, to: DataType, ctx: &mut Context) -> u32 {
+        let data = self.to_data(bits, ctx);
+        if to == DataType::Intern {
+            return match data {
+                Data::Null => 0,
+                data => Self::unconvert_intern(&data.to_string(), ctx)
+            };
+        }
+
+        let data = match data {
+            Data::Str(s) => to.parse_str(s.trim()),
+            data => data
+        };
+        match (to, data) {
+            (DataType::Integer, Data::Integer(num)) => Self::unconvert_integer(num),
+            (DataType::Integer, Data::Unsigned(num)) => i32::try_from(num).map_or(0, Self::unconvert_integer),
+            (DataType::Integer, Data::Float(num)) => {
+                let num = num.round();
+                if num >= i32::MIN as f32 && num < -(i32::MIN as f32) { Self::unconvert_integer(num as i32) } else { 0 }
+            }
+            (DataType::Unsigned, Data::Integer(num)) => u32::try_from(num).map_or(0, Self::unconvert_unsigned),
+            (DataType::Unsigned, Data::Unsigned(num)) => Self::unconvert_unsigned(num),
+            (DataType::Unsigned, Data::Float(num)) => {
+                let num = num.round();
+                if (0.0..u32::MAX as f32).contains(&num) { Self::unconvert_unsigned(num as u32) } else { 0 }
+            }
+            (DataType::Float, Data::Integer(num)) => Self::unconvert_float(num as f32),
+            (DataType::Float, Data::Unsigned(num)) => Self::unconvert_float(num as f32),
+            (DataType::Float, Data::Float(num)) => Self::unconvert_float(num),
+            _ => 0
+        }
+    }
+
     pub(crate) fn compare(&self, a: u32, b: u32, ctx: &Context) -> Ordering {
         match (NonZeroU32::new(a), NonZeroU32::new(b)) {
             (None, None) => Ordering::Equal,
@@ -264,10 +385,66 @@ impl DataType {
             (Some(a), Some(b)) => {
                 match self {
                     DataType::Integer => Self::convert_integer(a).cmp(&Self::convert_integer(b)),
+                    DataType::Unsigned => Self::convert_unsigned(a).cmp(&Self::convert_unsigned(b)),
                     DataType::Float => Self::convert_float(a).total_cmp(&Self::convert_float(b)),
-                    DataType::Intern => Self::convert_intern(a, ctx).cmp(&Self::convert_intern(b, ctx)),
+                    DataType::Intern => Self::convert_intern(a, ctx).cmp(Self::convert_intern(b, ctx)),
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, Data, DataType};
+
+    #[test]
+    fn merges_strings_into_existing_symbols_or_new_ones() {
+        let mut ctx = Context::new();
+        let imu = ctx.get_or_intern("imu");
+        let gps = ctx.get_or_intern("gps");
+
+        let mut other = Context::new();
+        let other_gps = other.get_or_intern("gps");
+        let other_baro = other.get_or_intern("baro");
+        let other_imu = other.get_or_intern("imu");
+
+        let remap = ctx.merge(&other);
+        assert_eq!(remap.len(), 4);
+        assert_eq!(remap[0], 0);
+        assert_eq!(remap[other_gps.get() as usize], gps.get());
+        assert_eq!(remap[other_imu.get() as usize], imu.get());
+        let baro = remap[other_baro.get() as usize];
+        assert!(baro != imu.get() && baro != gps.get());
+        assert_eq!(ctx.resolve(baro.try_into().unwrap()), Some("baro"));
+        assert_eq!(ctx.distinct_strings(), 3);
+    }
+
+    #[test]
+    fn keeps_symbols_when_reserving_room() {
+        let mut ctx = Context::new();
+        let symbols: Vec<_> = ["imu", "gps", "baro"].iter().map(|s| ctx.get_or_intern(s)).collect();
+        assert_eq!(ctx.distinct_strings(), 3);
+
+        ctx.reserve(10_000);
+        assert_eq!(ctx.distinct_strings(), 3);
+        assert_eq!(symbols.iter().map(|&sym| ctx.resolve(sym).unwrap()).collect::<Vec<_>>(), ["imu", "gps", "baro"]);
+        assert_eq!(ctx.get_or_intern("gps"), symbols[1]);
+
+        ctx.get_or_intern("mag");
+        ctx.get_or_intern("imu");
+        assert_eq!(ctx.distinct_strings(), 4);
+    }
+
+    #[test]
+    fn reads_back_every_integer_but_i32_min_which_is_null() {
+        let mut ctx = Context::new();
+        for num in [0, -1, 1, i32::MAX] {
+            let bits = DataType::Integer.as_data(Data::Integer(num), &mut ctx);
+            assert!(matches!(DataType::Integer.to_data(bits, &ctx), Data::Integer(read) if read == num), "{} didn't read back", num);
+        }
+
+        let bits = DataType::Integer.as_data(Data::Integer(i32::MIN), &mut ctx);
+        assert!(matches!(DataType::Integer.to_data(bits, &ctx), Data::Null));
+    }
+}
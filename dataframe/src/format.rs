@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::data::Data;
+
+/// A unit, notation and number of decimal places to show a column with.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ColumnFormat {
+    pub unit: String,
+    /// How many decimal places float values are shown with, or `None` for all of them. In
+    /// scientific notation this applies to every number.
+    pub precision: Option<usize>,
+    #[serde(default)]
+    pub notation: Notation
+}
+
+/// How the numbers of a column are written out.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub enum Notation {
+    /// As they are stored, such as `1234567.5`.
+    #[default]
+    Plain,
+    /// With commas between every three digits, such as `1,234,567.5`.
+    Thousands,
+    /// As a power of ten, such as `1.2345675e6`.
+    Scientific
+}
+
+impl ColumnFormat {
+    /// `item` written out in this format, followed by the unit if it isn't empty.
+    pub fn value(&self, item: &Data) -> String {
+        let text = match (item, self.notation, self.precision) {
+            (Data::Float(num), Notation::Scientific, Some(precision)) => format!("{:.*e}", precision, num),
+            (Data::Float(num), Notation::Scientific, None) => format!("{:e}", num),
+            (Data::Integer(num), Notation::Scientific, precision) => scientific(*num as f64, precision),
+            (Data::Unsigned(num), Notation::Scientific, precision) => scientific(*num as f64, precision),
+            (Data::Float(num), _, Some(precision)) => format!("{:.*}", precision, num),
+            _ => item.to_string()
+        };
+        let text = match item {
+            Data::Integer(_) | Data::Unsigned(_) | Data::Float(_) if self.notation == Notation::Thousands => group_thousands(&text),
+            _ => text
+        };
+        if self.unit.is_empty() || matches!(item, Data::Null) {
+            text
+        } else {
+            format!("{} {}", text, self.unit)
+        }
+    }
+
+    /// The column name `name` followed by the unit, such as `pressure (Pa)`.
+    pub fn label(&self, name: &str) -> String {
+        if self.unit.is_empty() {
+            name.to_string()
+        } else {
+            format!("{} ({})", name, self.unit)
+        }
+    }
+}
+
+fn scientific(num: f64, precision: Option<usize>) -> String {
+    precision.map_or(format!("{:e}", num), |precision| format!("{:.*e}", precision, num))
+}
+
+/// Puts a comma between every three digits of the whole part of `number`, such as `-1,234.5`.
+fn group_thousands(number: &str) -> String {
+    let (sign, digits) = number.strip_prefix('-').map_or(("", number), |digits| ("-", digits));
+    let (whole, fraction) = digits.split_at(digits.find('.').unwrap_or(digits.len()));
+
+    let mut grouped = String::with_capacity(number.len() + whole.len() / 3);
+    grouped.push_str(sign);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(fraction);
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Data, DataFrameBuilder, DataType};
+
+    use super::{ColumnFormat, Notation};
+
+    #[test]
+    fn shows_a_float_column_to_two_decimals_with_its_unit() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("pressure", DataType::Float);
+        let mut df = builder.build();
+        df.add_row(&[Data::Float(101325.01)]);
+        df.add_row(&[Data::Float(1.23456)]);
+        df.add_row(&[Data::Null]);
+        df.set_col_format(0, ColumnFormat { unit: "Pa".to_string(), precision: Some(2), notation: Notation::Plain });
+
+        let col = df.col(0);
+        let format = col.format();
+        assert_eq!(format.label(col.name()), "pressure (Pa)");
+        assert_eq!(format.value(&col.get_row(0)), "101325.01 Pa");
+        assert_eq!(format.value(&col.get_row(1)), "1.23 Pa");
+        assert_eq!(format.value(&col.get_row(2)), "");
+    }
+
+    #[test]
+    fn groups_thousands_and_writes_powers_of_ten() {
+        let thousands = ColumnFormat { notation: Notation::Thousands, ..ColumnFormat::default() };
+        assert_eq!(thousands.value(&Data::Integer(-1234567)), "-1,234,567");
+        assert_eq!(thousands.value(&Data::Float(1234.5)), "1,234.5");
+
+        let scientific = ColumnFormat { precision: Some(1), notation: Notation::Scientific, ..ColumnFormat::default() };
+        assert_eq!(scientific.value(&Data::Unsigned(1500)), "1.5e3");
+    }
+}
@@ -1,7 +1,13 @@
 use std::cmp::Ordering;
 use std::num::NonZeroU32;
+use std::sync::{Arc, RwLock};
 
 use crate::{data, data::{Data, DataType}};
+use crate::format::ColumnFormat;
+
+/// The fewest rows a frame makes room for when it runs out. Past that, its memory doubles each
+/// time, so adding `n` rows one at a time only moves it about `log2(n / MIN_GROWTH_ROWS)` times.
+const MIN_GROWTH_ROWS: usize = 1024;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Shape {
@@ -14,6 +20,19 @@ pub struct ColumnInfo {
     pub offset: usize,
     pub name: String,
     pub ty: DataType,
+    /// How the column is shown. Frames copied from this one share it for the columns they keep, so
+    /// that a format set on data derived from this frame carries back to it.
+    format: Arc<RwLock<ColumnFormat>>
+}
+
+/// `name`, or if one of `columns` already has it, `name` followed by the first free `.1`, `.2`, ...
+/// Columns are chosen by name in the app, so two columns sharing a name couldn't be told apart.
+fn unique_name(columns: &[ColumnInfo], name: String) -> String {
+    let is_taken = |candidate: &str| columns.iter().any(|col| col.name == candidate);
+    if !is_taken(&name) {
+        return name;
+    }
+    (1..).map(|n| format!("{}.{}", name, n)).find(|candidate| !is_taken(candidate)).unwrap()
 }
 
 #[derive(Clone)]
@@ -43,6 +62,12 @@ pub struct DataFrameBuilder {
     context: data::Context,
 }
 
+impl Default for DataFrameBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DataFrameBuilder {
     pub fn new() -> Self {
         DataFrameBuilder {
@@ -56,13 +81,20 @@ impl DataFrameBuilder {
         let offset = self.offset;
         self.columns.push(ColumnInfo {
             offset,
-            name: name.into(),
-            ty
+            name: unique_name(&self.columns, name.into()),
+            ty,
+            format: Arc::default()
         });
         self.offset += 1;
         offset
     }
 
+    /// Makes room for `additional` more distinct strings, such as when the number of packet types is
+    /// known up front.
+    pub fn reserve_strings(&mut self, additional: usize) {
+        self.context.reserve(additional);
+    }
+
     pub fn add_interned_string(&mut self, s: impl AsRef<str>) -> NonZeroU32 {
         self.context.get_or_intern(s)
     }
@@ -140,16 +172,16 @@ impl<'df> RowMut<'df> {
         self.mem[idx] = unsafe { std::mem::transmute::<Option<NonZeroU32>, u32>(value) };
     }
 
-    pub fn get_col(&self, idx: usize) -> Data {
+    pub fn get_col(&self, idx: usize) -> Data<'_> {
         let value = self.mem[idx];
         self.header.col_info(idx).ty.to_data(value, self.ctx)
     }
 
-    pub fn set_col(&mut self, idx: usize, value: Data<'df>) {
+    pub fn set_col(&mut self, idx: usize, value: Data) {
         self.mem[idx] = self.header.col_info(idx).ty.as_data(value, self.ctx);
     }
 
-    pub fn set_col_with_ty(&mut self, idx: usize, ty: DataType, value: Data<'df>) {
+    pub fn set_col_with_ty(&mut self, idx: usize, ty: DataType, value: Data) {
         self.mem[idx] = ty.as_data(value, self.ctx);
     }
 }
@@ -161,6 +193,7 @@ pub struct Column<'df> {
     stride: usize,
     name: &'df str,
     ty: DataType,
+    format: &'df RwLock<ColumnFormat>,
     ctx: &'df data::Context
 }
 
@@ -169,6 +202,10 @@ impl<'df> Column<'df> {
         self.name
     }
 
+    pub fn format(&self) -> ColumnFormat {
+        self.format.read().unwrap().clone()
+    }
+
     pub fn data_type(&self) -> DataType {
         self.ty
     }
@@ -207,11 +244,41 @@ impl DataFrame {
         }
     }
 
+    /// Roughly how many bytes the frame takes up: 4 for every cell, plus its interned strings.
+    pub fn memory_bytes(&self) -> usize {
+        self.mem.len() * std::mem::size_of::<u32>() + self.context.memory_bytes()
+    }
+
+    /// How many distinct strings the text columns of the frame hold between them.
+    pub fn distinct_strings(&self) -> usize {
+        self.context.distinct_strings()
+    }
+
     pub fn hint_complete(&mut self) {
         self.mem.truncate(self.rows * self.header.size());
         self.mem.shrink_to_fit();
     }
 
+    /// Changes the type of column `col` to `ty`, reading each value as the new type. Returns how many
+    /// values came out different, such as text that isn't a number, which is left empty.
+    pub fn convert_column(&mut self, col: usize, ty: DataType, mut on_row_callback: impl FnMut(usize)) -> usize {
+        let from = self.header.columns[col].ty;
+        self.header.columns[col].ty = ty;
+
+        let stride = self.header.size();
+        let mut changed = 0;
+        for row in 0..self.rows {
+            let bits = self.mem[row * stride + col];
+            let converted = from.convert_to(bits, ty, &mut self.context);
+            if from.to_data(bits, &self.context).to_string() != ty.to_data(converted, &self.context).to_string() {
+                changed += 1;
+            }
+            self.mem[row * stride + col] = converted;
+            on_row_callback(row);
+        }
+        changed
+    }
+
     pub fn col_names(&self) -> impl Iterator<Item=&str> {
         self.header.columns.iter().map(|col| col.name.as_str())
     }
@@ -236,17 +303,35 @@ impl DataFrame {
         }
     }
 
+    /// The row at `index`, or `None` if there aren't that many rows.
+    pub fn try_row(&self, index: usize) -> Option<Row<'_>> {
+        (index < self.rows).then(|| self.row(index))
+    }
+
+    /// The row at `index` to change, or `None` if there aren't that many rows.
+    pub fn try_row_mut(&mut self, index: usize) -> Option<RowMut<'_>> {
+        (index < self.rows).then(|| self.row_mut(index))
+    }
+
     pub fn col(&self, index: usize) -> Column<'_> {
         Column {
-            mem: &self.mem[index] as *const u32,
+            // A frame without rows has no memory to point into, but nothing is read from it either.
+            mem: self.mem.as_ptr().wrapping_add(index),
             len: self.rows,
             stride: self.header.size(),
             ty: self.header.columns[index].ty,
+            format: &self.header.columns[index].format,
             ctx: &self.context,
             name: &self.header.columns[index].name
         }
     }
 
+    /// Shows column `col` in `format`, in this frame and every frame sharing the column's format.
+    /// Only how the column is shown changes, so this can be done to a frame that is shared.
+    pub fn set_col_format(&self, col: usize, format: ColumnFormat) {
+        *self.header.columns[col].format.write().unwrap() = format;
+    }
+
     // pub fn col_mut(&mut self, index: usize) -> ColumnStepsMut<'_> {
     //     ColumnStepsMut {
     //         mem: &mut self.mem[index] as *mut u32,
@@ -259,15 +344,149 @@ impl DataFrame {
     // }
 
     pub fn add_null_row(&mut self) -> usize {
-        if self.rows * self.header.size() < self.mem.len() {
-            let idx = self.rows;
-            self.rows += 1;
-            idx
-        } else {
-            let idx = self.rows;
-            self.mem.extend((0..self.header.columns.len()).map(|_| 0u32));
-            self.rows += 1;
-            idx
+        let size = self.header.size();
+        if self.rows * size >= self.mem.len() {
+            // Growing explicitly, rather than leaving it to `Vec`, keeps small frames from moving
+            // many times over their first rows.
+            if self.mem.len() + size > self.mem.capacity() {
+                self.mem.reserve(self.mem.len().max(MIN_GROWTH_ROWS * size));
+            }
+            self.mem.resize(self.mem.len() + size, 0);
+        }
+        let idx = self.rows;
+        self.rows += 1;
+        idx
+    }
+
+    /// An empty frame with the same columns, whose interned strings keep their ids.
+    pub fn empty_like(&self, capacity: usize) -> DataFrame {
+        DataFrame {
+            mem: Vec::with_capacity(capacity * self.header.size()),
+            rows: 0,
+            context: self.context.clone(),
+            header: self.header.clone()
+        }
+    }
+
+    /// A frame holding `rows` of only the columns `cols`, both in the given order. Interned strings
+    /// keep their ids.
+    pub(crate) fn select(&self, cols: &[usize], rows: &[usize]) -> DataFrame {
+        self.select_and_add(cols, rows, &[])
+    }
+
+    /// Like [`select`](Self::select), followed by a column of empty cells for each of `added`, which
+    /// unlike adding them one at a time afterwards copies the rows only once.
+    pub(crate) fn select_and_add(&self, cols: &[usize], rows: &[usize], added: &[(String, DataType)]) -> DataFrame {
+        let mut columns = Vec::with_capacity(cols.len() + added.len());
+        for (offset, &col) in cols.iter().enumerate() {
+            let info = &self.header.columns[col];
+            // The same column may be chosen more than once.
+            let name = unique_name(&columns, info.name.clone());
+            columns.push(ColumnInfo { offset, name, ty: info.ty, format: info.format.clone() });
+        }
+        for (name, ty) in added {
+            let name = unique_name(&columns, name.clone());
+            columns.push(ColumnInfo { offset: columns.len(), name, ty: *ty, format: Arc::default() });
+        }
+
+        let size = self.header.size();
+        let mut mem = Vec::with_capacity(rows.len() * columns.len());
+        for &row_idx in rows {
+            let src = &self.mem[row_idx * size..(row_idx + 1) * size];
+            mem.extend(cols.iter().map(|&col| src[col]));
+            mem.resize(mem.len() + added.len(), 0);
+        }
+
+        DataFrame {
+            mem,
+            rows: rows.len(),
+            context: self.context.clone(),
+            header: Header { columns }
+        }
+    }
+
+    /// A new frame with the columns of this frame followed by `other_cols` of `other`, named by
+    /// `other_names` where those are free. Row `i` holds `rows[i]` of this frame next to `other_rows[i]` of `other`, or
+    /// empty cells where that is `None`.
+    pub(crate) fn join(&self, rows: &[usize], other: &DataFrame, other_cols: &[usize], other_names: Vec<String>, other_rows: &[Option<usize>]) -> DataFrame {
+        let size = self.header.size();
+        let other_size = other.header.size();
+
+        let mut columns = self.header.columns.clone();
+        for (idx, (&col, name)) in other_cols.iter().zip(other_names).enumerate() {
+            let name = unique_name(&columns, name);
+            let info = &other.header.columns[col];
+            columns.push(ColumnInfo { offset: size + idx, name, ty: info.ty, format: info.format.clone() });
+        }
+
+        let mut context = self.context.clone();
+        let mut mem = Vec::with_capacity(rows.len() * (size + other_cols.len()));
+        for (&row_idx, other_row) in rows.iter().zip(other_rows) {
+            mem.extend_from_slice(&self.mem[row_idx * size..(row_idx + 1) * size]);
+            match *other_row {
+                Some(other_idx) => {
+                    let src = &other.mem[other_idx * other_size..(other_idx + 1) * other_size];
+                    for &col in other_cols {
+                        match NonZeroU32::new(src[col]) {
+                            Some(sym) if other.header.columns[col].ty == DataType::Intern => {
+                                let s = other.context.resolve(sym).unwrap_or("<unknown>");
+                                mem.push(context.get_or_intern(s).get());
+                            }
+                            _ => mem.push(src[col])
+                        }
+                    }
+                }
+                None => mem.resize(mem.len() + other_cols.len(), 0)
+            }
+        }
+
+        DataFrame {
+            mem,
+            rows: rows.len(),
+            context,
+            header: Header { columns }
+        }
+    }
+
+    /// Describes each column that differs in name or type between the two frames.
+    pub(crate) fn mismatched_columns(&self, other: &DataFrame) -> Vec<String> {
+        let mut mismatched = vec![];
+        for idx in 0..self.header.num_cols().max(other.header.num_cols()) {
+            match (self.header.columns.get(idx), other.header.columns.get(idx)) {
+                (Some(a), Some(b)) if a.name == b.name && a.ty == b.ty => { }
+                (Some(a), Some(b)) => {
+                    mismatched.push(format!("'{}' ({}) vs '{}' ({})", a.name, a.ty.name(), b.name, b.ty.name()));
+                }
+                (Some(a), None) => mismatched.push(format!("'{}' is missing", a.name)),
+                (None, Some(b)) => mismatched.push(format!("'{}' is extra", b.name)),
+                (None, None) => unreachable!()
+            }
+        }
+        mismatched
+    }
+
+    /// Appends copies of `rows` from a frame with the same columns. Its strings are interned once
+    /// up front, so that string cells are translated by lookup rather than interned one by one.
+    pub(crate) fn extend_from(&mut self, other: &DataFrame, rows: &[usize]) {
+        let size = self.header.size();
+        let intern_cols: Vec<usize> = (0..self.header.num_cols())
+            .filter(|&idx| self.header.columns[idx].ty == DataType::Intern)
+            .collect();
+        let remap = if intern_cols.is_empty() { vec![] } else { self.context.merge(&other.context) };
+        // Interned only if a cell holds a symbol `other` doesn't know, which reads as `<unknown>` there.
+        let mut unknown = None;
+
+        for &row_idx in rows {
+            let src = &other.mem[row_idx * size..(row_idx + 1) * size];
+            let start = self.add_null_row() * size;
+            let dst = &mut self.mem[start..start + size];
+            dst.copy_from_slice(src);
+            for &col in &intern_cols {
+                dst[col] = match remap.get(src[col] as usize) {
+                    Some(&sym) => sym,
+                    None => *unknown.get_or_insert_with(|| self.context.get_or_intern("<unknown>").get())
+                };
+            }
         }
     }
 
@@ -275,10 +494,64 @@ impl DataFrame {
         assert_eq!(datas.len(), self.header.num_cols());
         let idx = self.add_null_row();
         let mut row = self.row_mut(idx);
-        for (col_idx, data) in datas.into_iter().enumerate() {
+        for (col_idx, data) in datas.iter().enumerate() {
             row.set_col(col_idx, *data);
         }
         idx
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use crate::data::{Data, DataType};
+    use crate::view::DataFrameView;
+    use super::{DataFrameBuilder, MIN_GROWTH_ROWS};
+
+    #[test]
+    fn counts_the_memory_of_cells_strings_and_view_rows() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("time", DataType::Integer);
+        builder.add_column("sensor", DataType::Intern);
+        let mut df = builder.build();
+        for (time, sensor) in [(1, "imu"), (2, "gps"), (3, "imu")] {
+            df.add_row(&[Data::Integer(time), Data::Str(sensor)]);
+        }
+
+        let cells = 3 * 2 * 4;
+        // The strings, plus a boxed string and a lookup entry for each, including the empty string
+        // standing in for null.
+        let entry = std::mem::size_of::<Box<str>>() + std::mem::size_of::<(&str, std::num::NonZeroU32)>();
+        let strings = "imugps".len() + 3 * entry;
+        assert_eq!(df.memory_bytes(), cells + strings);
+
+        let view = DataFrameView::from_dataframe(df);
+        assert_eq!(view.memory_bytes(), cells + strings + 3 * std::mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn grows_a_bounded_number_of_times_while_adding_rows() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("time", DataType::Integer);
+        builder.add_column("value", DataType::Float);
+        let mut df = builder.build();
+
+        let rows = 100 * MIN_GROWTH_ROWS;
+        let mut capacities = vec![];
+        for _ in 0..rows {
+            let capacity = df.mem.capacity();
+            df.add_null_row();
+            if df.mem.capacity() != capacity {
+                capacities.push(df.mem.capacity());
+            }
+        }
+
+        assert_eq!(df.shape().rows, rows);
+        assert!(capacities[0] >= MIN_GROWTH_ROWS * 2);
+        assert!(capacities.windows(2).all(|pair| pair[1] >= pair[0] * 2), "grew by less than double: {:?}", capacities);
+        // 100 times the first growth is reached after 7 doublings.
+        assert!(capacities.len() <= 8, "grew {} times", capacities.len());
+    }
+}
+
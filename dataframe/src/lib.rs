@@ -1,7 +1,9 @@
 mod view;
 mod data;
 mod frame;
+mod format;
 
-pub use view::{DataFrameView, ColumnView};
+pub use view::{DataFrameView, ColumnView, CsvOptions, AsofDir};
 pub use data::{Data, DataType};
+pub use format::{ColumnFormat, Notation};
 pub use frame::{Shape, DataFrame, DataFrameBuilder, Row, RowMut, ColumnInfo};
@@ -1,7 +1,13 @@
-use std::{io, io::BufRead};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::{io, io::{BufRead, Write}};
+use std::ops::Range;
 use std::sync::Arc;
 
+use ahash::{AHashMap, AHashSet};
+
 use crate::data::{Data, DataType};
+use crate::format::ColumnFormat;
 use crate::frame::{DataFrame, DataFrameBuilder, Row, RowMut, Shape, Column};
 
 
@@ -15,9 +21,261 @@ impl<'v> ColumnView<'v> {
         self.col.name()
     }
 
+    pub fn format(&self) -> ColumnFormat {
+        self.col.format()
+    }
+
     pub fn get_row(&self, idx: usize) -> Data<'v> {
         self.col.get_row(self.rows[idx])
     }
+
+    /// The values of the column, in the order of the view's rows.
+    pub fn iter(&self) -> impl Iterator<Item=Data<'v>> + '_ {
+        self.rows.iter().map(|&row_idx| self.col.get_row(row_idx))
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub decimal_separator: char,
+    /// How many rows at the start of the file are used to infer the column types.
+    pub infer_rows: usize,
+    /// Instead of failing on rows with the wrong number of fields, pads short rows with nulls and drops
+    /// extra fields.
+    pub lenient: bool,
+    /// Whether each column name in the header is followed by its type, such as `timestamp:unsigned`
+    /// as written by [`DataFrameView::to_csv_typed`]. The type follows the last `:`, so names may
+    /// hold a `:` too.
+    pub typed_header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            decimal_separator: '.',
+            infer_rows: 100,
+            lenient: false,
+            typed_header: false
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Calls `on_field` with the index and trimmed text of each field in a line, returning how many
+    /// there were. Fields may be quoted to contain the delimiter, with `""` standing for a quote inside
+    /// a quoted field. `scratch` holds the text of quoted fields, so it can be reused between lines.
+    fn for_each_field(&self, line: &str, scratch: &mut String, mut on_field: impl FnMut(usize, &str)) -> usize {
+        let mut rest = line;
+        let mut field_idx = 0;
+        loop {
+            let remaining = if let Some(mut quoted) = rest.trim_start().strip_prefix('"') {
+                scratch.clear();
+                loop {
+                    match quoted.find('"') {
+                        Some(idx) if quoted[idx + 1..].starts_with('"') => {
+                            scratch.push_str(&quoted[..idx + 1]);
+                            quoted = &quoted[idx + 2..];
+                        }
+                        Some(idx) => {
+                            scratch.push_str(&quoted[..idx]);
+                            quoted = &quoted[idx + 1..];
+                            break;
+                        }
+                        None => {
+                            scratch.push_str(quoted);
+                            quoted = "";
+                            break;
+                        }
+                    }
+                }
+                on_field(field_idx, scratch);
+                quoted.find(self.delimiter).map(|idx| &quoted[idx..])
+            } else {
+                let end = rest.find(self.delimiter).unwrap_or(rest.len());
+                on_field(field_idx, rest[..end].trim());
+                Some(&rest[end..]).filter(|remaining| !remaining.is_empty())
+            };
+            field_idx += 1;
+
+            match remaining {
+                Some(remaining) => rest = &remaining[self.delimiter.len_utf8()..],
+                None => return field_idx
+            }
+        }
+    }
+
+    /// Whether `record` ends partway through a quoted field, in which case the line break at its end
+    /// is part of the field and the record carries on into the next line.
+    fn is_unterminated(&self, record: &str) -> bool {
+        let mut in_quotes = false;
+        let mut at_field_start = true;
+        let mut chars = record.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c == '"' && chars.next_if_eq(&'"').is_none() {
+                    in_quotes = false;
+                }
+            } else if c == self.delimiter {
+                at_field_start = true;
+            } else if at_field_start && c == '"' {
+                in_quotes = true;
+                at_field_start = false;
+            } else if !c.is_whitespace() {
+                at_field_start = false;
+            }
+        }
+        in_quotes
+    }
+
+    /// Reads the next record into `record`, which is one line unless a quoted field holds line breaks,
+    /// returning how many bytes were read.
+    fn read_record(&self, file: &mut impl BufRead, record: &mut String) -> io::Result<usize> {
+        let mut amount = file.read_line(record)?;
+        while amount > 0 && self.is_unterminated(record) {
+            let more = file.read_line(record)?;
+            if more == 0 {
+                break;
+            }
+            amount += more;
+        }
+        Ok(amount)
+    }
+
+    fn split_fields(&self, line: &str) -> Vec<String> {
+        let mut fields = vec![];
+        self.for_each_field(line, &mut String::new(), |_, field| fields.push(field.to_string()));
+        fields
+    }
+
+    fn normalize_decimal<'a>(&self, item: &'a str) -> Cow<'a, str> {
+        if self.decimal_separator == '.' {
+            Cow::Borrowed(item)
+        } else {
+            Cow::Owned(item.replace(self.decimal_separator, "."))
+        }
+    }
+
+    fn parse_item<'a>(&self, ty: DataType, item: &'a str) -> Data<'a> {
+        match ty {
+            DataType::Float => self.normalize_decimal(item).parse::<f32>().map_or(Data::Null, Data::Float),
+            _ => ty.parse_str(item)
+        }
+    }
+
+    /// Widens `ty` so that it can also hold `item`, going from Integer to Float to Intern.
+    fn infer_type(&self, ty: Option<DataType>, item: &str) -> DataType {
+        let item_ty = if item.parse::<i32>().is_ok() {
+            DataType::Integer
+        } else if self.normalize_decimal(item).parse::<f32>().is_ok() {
+            DataType::Float
+        } else {
+            DataType::Intern
+        };
+        // The variants of DataType are declared from narrowest to widest.
+        ty.map_or(item_ty, |ty| ty.max(item_ty))
+    }
+
+    /// Parses a line straight into a new row, which starts out null so that missing fields stay empty.
+    fn add_row(&self, df: &mut DataFrame, data_types: &[DataType], line: &str, scratch: &mut String, malformed_rows: &mut usize) -> io::Result<usize> {
+        let row_idx = df.add_null_row();
+        let mut row = df.row_mut(row_idx);
+        let fields = self.for_each_field(trim_line(line), scratch, |col_idx, item| {
+            if let Some(&ty) = data_types.get(col_idx) {
+                row.set_col(col_idx, self.parse_item(ty, item));
+            }
+        });
+
+        if fields != data_types.len() {
+            if !self.lenient {
+                return Err(io::Error::other("Malformed CSV file."));
+            }
+            *malformed_rows += 1;
+        }
+        Ok(row_idx)
+    }
+}
+
+fn trim_line(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+const MERGE_SORT_RUN: usize = 4096;
+
+/// A stable bottom-up merge sort, which unlike `sort_by` can report how far along it is. Runs of
+/// `MERGE_SORT_RUN` rows are sorted first, then merged in passes of doubling width.
+fn merge_sort_by(rows: &mut Vec<usize>, mut compare: impl FnMut(usize, usize) -> Ordering, mut on_progress: impl FnMut(f32)) {
+    let len = rows.len();
+    let runs = len.div_ceil(MERGE_SORT_RUN).max(1);
+    let passes = (runs.next_power_of_two().trailing_zeros() + 1) as f32;
+
+    for (run_idx, run) in rows.chunks_mut(MERGE_SORT_RUN).enumerate() {
+        run.sort_by(|&a, &b| compare(a, b));
+        on_progress((run_idx + 1) as f32 / runs as f32 / passes);
+    }
+
+    let mut merged = vec![0; len];
+    let mut width = MERGE_SORT_RUN;
+    let mut pass = 1.0;
+    while width < len {
+        for start in (0..len).step_by(2 * width) {
+            let mid = (start + width).min(len);
+            let end = (start + 2 * width).min(len);
+
+            let (mut left, mut right) = (start, mid);
+            for slot in &mut merged[start..end] {
+                // Taking from the left on ties keeps the sort stable.
+                if right == end || (left < mid && compare(rows[left], rows[right]).is_le()) {
+                    *slot = rows[left];
+                    left += 1;
+                } else {
+                    *slot = rows[right];
+                    right += 1;
+                }
+            }
+
+            on_progress((pass + end as f32 / len as f32) / passes);
+        }
+        std::mem::swap(rows, &mut merged);
+        width *= 2;
+        pass += 1.0;
+    }
+
+    on_progress(1.0);
+}
+
+/// Writes a text field as in RFC 4180, quoting it if it contains a comma, quote or line break and
+/// doubling any quotes inside it.
+fn write_csv_field(writer: &mut impl Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\r', '\n']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        writer.write_all(field.as_bytes())
+    }
+}
+
+
+/// Which row of the other frame [`DataFrameView::asof_join`] matches to each row.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AsofDir {
+    /// The last row at or before the time.
+    Backward,
+    /// The first row at or after the time.
+    Forward,
+    /// Whichever of those is closer in time, preferring the earlier one on a tie.
+    Nearest
+}
+
+/// A time as a double, since timestamps in milliseconds don't fit exactly in an `f32`.
+fn time_value(data: Data) -> Option<f64> {
+    match data {
+        Data::Integer(num) => Some(num as f64),
+        Data::Unsigned(num) => Some(num as f64),
+        Data::Float(num) => Some(num as f64),
+        Data::Str(_) | Data::Null => None
+    }
 }
 
 
@@ -36,74 +294,384 @@ impl DataFrameView {
         }
     }
 
-    pub fn from_csv(file: &mut impl BufRead, mut on_row_callback: impl FnMut(usize)) -> io::Result<Self> {
+    /// Reads a CSV file with a header row. Also returns how many rows had the wrong number of fields,
+    /// which is always 0 unless `options.lenient` is set. If the size of the file is given, space
+    /// for the rows is reserved up front. Quoted fields may hold line breaks.
+    ///
+    /// With `options.typed_header`, the columns get the types named in the header, and reading fails
+    /// if a column has none. Otherwise the header is taken as plain names and the types are inferred
+    /// from the first rows.
+    pub fn from_csv(file: &mut impl BufRead, file_size: Option<u64>, options: CsvOptions, mut on_row_callback: impl FnMut(usize)) -> io::Result<(Self, usize)> {
         let mut offset = 0;
         let mut header = String::new();
         let mut row_numbers = Vec::new();
-        offset += file.read_line(&mut header)?;
+        let mut malformed_rows = 0;
+        offset += options.read_record(file, &mut header)?;
         if header.is_empty() {
             return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
         }
+        let col_names = options.split_fields(trim_line(&header));
+        let typed_header: Option<Vec<(&str, DataType)>> = if options.typed_header {
+            let typed = col_names.iter()
+                .map(|name| name.rsplit_once(':').and_then(|(name, ty)| Some((name, DataType::from_name(ty)?)))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Column '{}' has no type in the header, such as '{}:float'.", name, name))))
+                .collect::<io::Result<_>>()?;
+            Some(typed)
+        } else {
+            None
+        };
 
-        let mut dataframe_builder = DataFrameBuilder::new();
-        let mut data_types = Vec::new();
-
-        let mut row_buf = String::new();
-        offset += file.read_line(&mut row_buf)?;
-        if row_buf.is_empty() {
-            for col_name in header.trim().split(',') {
-                dataframe_builder.add_column(col_name.trim(), DataType::Intern);
-            }
-            let df = dataframe_builder.build();
-            return Ok(DataFrameView {
-                rows: row_numbers,
-                df: Arc::new(df)
-            });
+        // The first rows are held back to infer the column types from, along with the offset after each.
+        let mut sample = Vec::new();
+        while sample.len() < options.infer_rows.max(1) {
+            let mut row_buf = String::new();
+            let amount = options.read_record(file, &mut row_buf)?;
+            if row_buf.is_empty() {
+                break;
+            }
+            offset += amount;
+            sample.push((row_buf, offset));
         }
 
-        for (col_name, item) in header.trim().split(',').zip(row_buf.trim().split(',')) {
-            let item = item.trim();
-            let col_name = col_name.trim();
-
-            if let Ok(_) = item.parse::<f32>() {
-                dataframe_builder.add_column(col_name, DataType::Float);
-                data_types.push(DataType::Float);
-            } else {
-                dataframe_builder.add_column(col_name, DataType::Intern);
-                data_types.push(DataType::Intern);
+        let mut data_types: Vec<Option<DataType>> = vec![None; col_names.len()];
+        for (row_buf, _) in &sample {
+            for (ty, item) in data_types.iter_mut().zip(options.split_fields(trim_line(row_buf))) {
+                if !item.is_empty() {
+                    *ty = Some(options.infer_type(*ty, &item));
+                }
             }
         }
+        let data_types: Vec<DataType> = match &typed_header {
+            Some(typed_header) => typed_header.iter().map(|&(_, ty)| ty).collect(),
+            None => data_types.into_iter().map(|ty| ty.unwrap_or(DataType::Intern)).collect()
+        };
+
+        let mut dataframe_builder = DataFrameBuilder::new();
+        for (i, (col_name, ty)) in col_names.iter().zip(&data_types).enumerate() {
+            let col_name = typed_header.as_ref().map_or(col_name.as_str(), |typed_header| typed_header[i].0);
+            dataframe_builder.add_column(col_name, *ty);
+        }
         let mut df = dataframe_builder.build();
-        let mut row_data = vec![];
-        for (ty, item) in data_types.iter().zip(row_buf.trim().split(',')) {
-            row_data.push(ty.parse_str(item.trim()));
+
+        // Guess the number of rows from the average length of the sampled rows.
+        if let (Some(file_size), Some((_, sample_end))) = (file_size, sample.last()) {
+            let sample_start = header.len();
+            let row_len = ((sample_end - sample_start) / sample.len()).max(1);
+            let rows = (file_size as usize).saturating_sub(sample_start) / row_len;
+            df.hint_rows(rows);
+            row_numbers.reserve(rows);
         }
-        if row_data.len() != df.shape().cols {
-            return Err(io::Error::other("Malformed CSV file."));
+
+        let mut scratch = String::new();
+        for (row_buf, row_offset) in &sample {
+            row_numbers.push(options.add_row(&mut df, &data_types, row_buf, &mut scratch, &mut malformed_rows)?);
+            on_row_callback(*row_offset);
         }
-        row_numbers.push(df.add_row(&row_data));
-        on_row_callback(offset);
 
+        let mut row_buf = String::new();
         loop {
-            let mut row_data = Vec::new();
             row_buf.clear();
-            let amount = file.read_line(&mut row_buf)?;
+            let amount = options.read_record(file, &mut row_buf)?;
             if row_buf.is_empty() {
-                return Ok(DataFrameView {
+                df.hint_complete();
+                return Ok((DataFrameView {
                     rows: row_numbers,
                     df: Arc::new(df)
-                })
+                }, malformed_rows))
             }
             offset += amount;
-            for (dtype, item) in data_types.iter().zip(row_buf.trim_end_matches('\n').split(',')) {
-                row_data.push(dtype.parse_str(item.trim()));
+            row_numbers.push(options.add_row(&mut df, &data_types, &row_buf, &mut scratch, &mut malformed_rows)?);
+            on_row_callback(offset);
+        }
+    }
+
+    /// Writes the rows as comma-separated values, preceded by the column names if `header` is set.
+    /// `on_row_callback` is called with the index of each row after it is written.
+    pub fn to_csv(&self, writer: &mut impl Write, header: bool, on_row_callback: impl FnMut(usize)) -> io::Result<()> {
+        let cols: Vec<usize> = (0..self.df.shape().cols).collect();
+        self.to_csv_columns(writer, header, &cols, on_row_callback)
+    }
+
+    /// Like [`to_csv`](Self::to_csv), but only writes the columns `cols`, in that order.
+    pub fn to_csv_columns(&self, writer: &mut impl Write, header: bool, cols: &[usize], on_row_callback: impl FnMut(usize)) -> io::Result<()> {
+        if header {
+            self.write_csv_header(writer, cols, false)?;
+        }
+        self.write_csv_rows(writer, cols, on_row_callback)
+    }
+
+    /// Like [`to_csv_columns`](Self::to_csv_columns) with a header, but each column name is followed
+    /// by its type, such as `timestamp:unsigned`, so that [`from_csv`](Self::from_csv) reads the
+    /// columns back with the same types instead of inferring them.
+    pub fn to_csv_typed(&self, writer: &mut impl Write, cols: &[usize], on_row_callback: impl FnMut(usize)) -> io::Result<()> {
+        self.write_csv_header(writer, cols, true)?;
+        self.write_csv_rows(writer, cols, on_row_callback)
+    }
+
+    fn write_csv_header(&self, writer: &mut impl Write, cols: &[usize], typed: bool) -> io::Result<()> {
+        for (i, &col_idx) in cols.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
             }
-            if row_data.len() != df.shape().cols {
-                return Err(io::Error::other("Malformed CSV file."));
+            if typed {
+                let ty = self.df.col(col_idx).data_type().name().to_lowercase();
+                write_csv_field(writer, &format!("{}:{}", self.col_name(col_idx), ty))?;
+            } else {
+                write_csv_field(writer, self.col_name(col_idx))?;
             }
-            row_numbers.push(df.add_row(&row_data));
-            on_row_callback(offset);
         }
+        writer.write_all(b"\n")
+    }
+
+    fn write_csv_rows(&self, writer: &mut impl Write, cols: &[usize], mut on_row_callback: impl FnMut(usize)) -> io::Result<()> {
+        for (row_idx, row) in self.iter_rows().enumerate() {
+            for (i, &col_idx) in cols.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                match row.get_col(col_idx) {
+                    Data::Str(s) => write_csv_field(writer, s)?,
+                    data => write!(writer, "{}", data)?
+                }
+            }
+            writer.write_all(b"\n")?;
+
+            on_row_callback(row_idx);
+        }
+
+        Ok(())
+    }
+
+    /// The distinct non-empty strings in a column, sorted. Empty for columns that don't hold strings.
+    pub fn distinct_strs(&self, col: usize) -> Vec<&str> {
+        let column = self.df.col(col);
+        let mut seen = AHashSet::new();
+        let mut values: Vec<&str> = self.rows.iter()
+            .filter(|&&row_idx| seen.insert(column.get_row_raw(row_idx)))
+            .filter_map(|&row_idx| match column.get_row(row_idx) {
+                Data::Str(s) if !s.is_empty() => Some(s),
+                _ => None
+            })
+            .collect();
+        values.sort_unstable();
+        values
+    }
+
+    /// A new frame with the rows of this view plus a column holding `values`, in row order.
+    pub fn with_column<'a>(&self, name: impl Into<String>, ty: DataType, values: impl IntoIterator<Item=Data<'a>>) -> DataFrameView {
+        self.with_columns(vec![(name.into(), ty, values)])
+    }
+
+    /// Like [`with_column`](Self::with_column) for each of `columns`, in order, but copying the
+    /// frame only once.
+    pub fn with_columns<'a, I: IntoIterator<Item=Data<'a>>>(&self, columns: Vec<(String, DataType, I)>) -> DataFrameView {
+        let cols: Vec<usize> = (0..self.df.shape().cols).collect();
+        let added: Vec<(String, DataType)> = columns.iter().map(|(name, ty, _)| (name.clone(), *ty)).collect();
+        let mut df = self.df.select_and_add(&cols, &self.rows, &added);
+        for (col_idx, (_, _, values)) in (cols.len()..).zip(columns) {
+            for (row_idx, value) in values.into_iter().enumerate().take(self.rows.len()) {
+                df.row_mut(row_idx).set_col(col_idx, value);
+            }
+        }
+        DataFrameView::from_dataframe(df)
+    }
+
+    /// A new frame with the rows of this view and column `col` changed to type `ty`, along with how
+    /// many of its values came out different. See [`DataFrame::convert_column`].
+    pub fn with_column_type(&self, col: usize, ty: DataType, on_row_callback: impl FnMut(usize)) -> (DataFrameView, usize) {
+        let cols: Vec<usize> = (0..self.df.shape().cols).collect();
+        let mut df = self.df.select(&cols, &self.rows);
+        let changed = df.convert_column(col, ty, on_row_callback);
+        (DataFrameView::from_dataframe(df), changed)
+    }
+
+    /// A new frame with only the columns `cols`, in that order.
+    pub fn select_cols(&self, cols: &[usize]) -> DataFrameView {
+        DataFrameView::from_dataframe(self.df.select(cols, &self.rows))
+    }
+
+    /// A new frame with the rows of this view, each followed by the columns of the row of `other`
+    /// matched to it by time in `direction`. `other` must be sorted by `other_time`, ascending.
+    /// Rows without a match, or with an empty time, get empty cells. Columns of `other` whose names
+    /// are already taken get a `_right` suffix.
+    pub fn asof_join(&self, other: &DataFrameView, self_time: usize, other_time: usize, direction: AsofDir) -> DataFrameView {
+        let other_times: Vec<(f64, usize)> = {
+            let col = other.col(other_time);
+            (0..other.rows.len())
+                .filter_map(|row_idx| time_value(col.get_row(row_idx)).map(|time| (time, other.rows[row_idx])))
+                .collect()
+        };
+
+        let other_rows: Vec<Option<usize>> = {
+            let col = self.col(self_time);
+            (0..self.rows.len()).map(|row_idx| {
+                let time = time_value(col.get_row(row_idx))?;
+                // The first row after `time`, so the one before it is the last at or before `time`.
+                let after = other_times.partition_point(|&(other_time, _)| other_time <= time);
+                let before = after.checked_sub(1).map(|idx| other_times[idx]);
+                let at_or_after = if before.is_some_and(|(other_time, _)| other_time == time) {
+                    before
+                } else {
+                    other_times.get(after).copied()
+                };
+
+                match direction {
+                    AsofDir::Backward => before,
+                    AsofDir::Forward => at_or_after,
+                    AsofDir::Nearest => match (before, at_or_after) {
+                        (Some(b), Some(a)) => if a.0 - time < time - b.0 { Some(a) } else { Some(b) },
+                        (b, a) => b.or(a)
+                    }
+                }.map(|(_, other_idx)| other_idx)
+            }).collect()
+        };
+
+        let other_cols: Vec<usize> = (0..other.df.shape().cols).collect();
+        let other_names = other.col_names().map(|name| {
+            if self.col_names().any(|taken| taken == name) {
+                format!("{}_right", name)
+            } else {
+                name.to_string()
+            }
+        }).collect();
+
+        DataFrameView::from_dataframe(self.df.join(&self.rows, &other.df, &other_cols, other_names, &other_rows))
+    }
+
+    /// A new frame of how this view differs from `other`, such as a run of new firmware from one
+    /// of the old. Rows are matched by position, leaving out the extra rows of the longer view, or
+    /// if `key` is given, by the value of that column and the column of `other` with the same name.
+    /// Rows without a match are left out. The first column is the key, or the row number, followed
+    /// by each other column found in both: for numbers, this value minus that of `other`, and for
+    /// text, 1 if the values differ and 0 otherwise. Deltas next to an empty cell are empty.
+    pub fn diff(&self, other: &DataFrameView, key: Option<usize>) -> Result<DataFrameView, String> {
+        let other_key = match key {
+            Some(key) => {
+                let name = self.col_name(key);
+                Some(other.col_names().position(|other_name| other_name == name)
+                    .ok_or_else(|| format!("The other data has no '{}' column to match rows by.", name))?)
+            }
+            None => None
+        };
+
+        let matched: Vec<(usize, usize)> = match (key, other_key) {
+            (Some(key), Some(other_key)) => {
+                // The first row of `other` with each key.
+                let mut other_rows: AHashMap<String, usize> = AHashMap::new();
+                let other_col = other.col(other_key);
+                for row_idx in 0..other.rows.len() {
+                    if let Some(value) = other_col.get_row(row_idx).as_str() {
+                        other_rows.entry(value.into_owned()).or_insert(row_idx);
+                    }
+                }
+                let col = self.col(key);
+                (0..self.rows.len())
+                    .filter_map(|row_idx| {
+                        let value = col.get_row(row_idx);
+                        other_rows.get(value.as_str()?.as_ref()).map(|&other_idx| (row_idx, other_idx))
+                    })
+                    .collect()
+            }
+            _ => (0..self.rows.len().min(other.rows.len())).map(|row_idx| (row_idx, row_idx)).collect()
+        };
+
+        // Each column compared, with the matching column of `other` and whether it holds numbers.
+        let compared: Vec<(usize, usize, bool)> = (0..self.shape().cols)
+            .filter(|&col_idx| Some(col_idx) != key)
+            .filter_map(|col_idx| {
+                let name = self.col_name(col_idx);
+                let other_idx = other.col_names().position(|other_name| other_name == name)?;
+                let is_numeric = self.df.col(col_idx).data_type() != DataType::Intern && other.df.col(other_idx).data_type() != DataType::Intern;
+                Some((col_idx, other_idx, is_numeric))
+            })
+            .collect();
+        if compared.is_empty() {
+            return Err("The data have no columns in common to compare.".to_string());
+        }
+
+        let mut builder = DataFrameBuilder::new();
+        match key {
+            Some(key) => builder.add_column(self.col_name(key), self.df.col(key).data_type()),
+            None => builder.add_column("row", DataType::Integer)
+        };
+        for &(col_idx, _, is_numeric) in &compared {
+            builder.add_column(self.col_name(col_idx), if is_numeric { DataType::Float } else { DataType::Integer });
+        }
+        let mut diff = builder.build_with_capacity(matched.len());
+
+        let mut row_data = Vec::with_capacity(compared.len() + 1);
+        for (row_idx, other_idx) in matched {
+            row_data.clear();
+            row_data.push(match key {
+                Some(key) => self.get_by_index(key, row_idx),
+                None => Data::Integer(row_idx as i32)
+            });
+            for &(col_idx, other_col_idx, is_numeric) in &compared {
+                let (value, other_value) = (self.get_by_index(col_idx, row_idx), other.get_by_index(other_col_idx, other_idx));
+                row_data.push(if is_numeric {
+                    match (time_value(value), time_value(other_value)) {
+                        (Some(value), Some(other_value)) => Data::Float((value - other_value) as f32),
+                        _ => Data::Null
+                    }
+                } else {
+                    Data::Integer(!value.eq(&other_value) as i32)
+                });
+            }
+            diff.add_row(&row_data);
+        }
+
+        Ok(DataFrameView::from_dataframe(diff))
+    }
+
+    /// Concatenates the rows of each view, in order, into a new frame. All of the frames must have
+    /// the same column names and types.
+    pub fn concat(frames: &[DataFrameView]) -> Result<DataFrameView, String> {
+        let Some((first, rest)) = frames.split_first() else {
+            return Err("No data to concatenate.".to_string());
+        };
+
+        for (idx, frame) in rest.iter().enumerate() {
+            let mismatched = first.df.mismatched_columns(&frame.df);
+            if !mismatched.is_empty() {
+                return Err(format!("Columns of data {} do not match: {}", idx + 2, mismatched.join(", ")));
+            }
+        }
+
+        let total_rows = frames.iter().map(|frame| frame.rows.len()).sum();
+        let mut df = first.df.empty_like(total_rows);
+        for frame in frames {
+            df.extend_from(&frame.df, &frame.rows);
+        }
+
+        Ok(DataFrameView::from_dataframe(df))
+    }
+
+    /// Appends the rows of `other`, which must have the same column names and types, to the end of
+    /// this view. They are added to its frame in place, unless another view shares the frame, in
+    /// which case it is copied first and the other view keeps the rows it had.
+    pub fn extend(&mut self, other: &DataFrameView) -> Result<(), String> {
+        let mismatched = self.df.mismatched_columns(&other.df);
+        if !mismatched.is_empty() {
+            return Err(format!("Columns do not match: {}", mismatched.join(", ")));
+        }
+
+        let df = Arc::make_mut(&mut self.df);
+        let start = df.shape().rows;
+        df.extend_from(&other.df, &other.rows);
+        self.rows.extend(start..start + other.rows.len());
+        Ok(())
+    }
+
+    /// Roughly how many bytes the frame and the row order of this view take up. Views sharing a
+    /// frame each count all of it.
+    pub fn memory_bytes(&self) -> usize {
+        self.df.memory_bytes() + self.rows.len() * std::mem::size_of::<usize>()
+    }
+
+    /// How many distinct strings the text columns of the frame hold between them, including those
+    /// only in rows outside this view.
+    pub fn distinct_strings(&self) -> usize {
+        self.df.distinct_strings()
     }
 
     pub fn shape(&self) -> Shape {
@@ -121,18 +689,29 @@ impl DataFrameView {
         self.df.col(idx).name()
     }
 
-    pub fn col(&self, idx: usize) -> ColumnView {
+    pub fn col(&self, idx: usize) -> ColumnView<'_> {
         ColumnView {
             rows: &self.rows,
             col: self.df.col(idx)
         }
     }
 
-    pub fn row(&self, idx: usize) -> Row {
+    pub fn row(&self, idx: usize) -> Row<'_> {
         self.df.row(self.rows[idx])
     }
 
-    pub fn row_mut(&mut self, idx: usize) -> RowMut {
+    /// The row at `idx`, or `None` if the view doesn't have that many rows, such as when an index
+    /// was kept from before the view was filtered.
+    pub fn try_row(&self, idx: usize) -> Option<Row<'_>> {
+        self.rows.get(idx).map(|&row_idx| self.df.row(row_idx))
+    }
+
+    /// The rows of the view, in order.
+    pub fn iter_rows(&self) -> impl Iterator<Item=Row<'_>> {
+        self.rows.iter().map(|&row_idx| self.df.row(row_idx))
+    }
+
+    pub fn row_mut(&mut self, idx: usize) -> RowMut<'_> {
         Arc::make_mut(&mut self.df).row_mut(self.rows[idx])
     }
 
@@ -145,14 +724,60 @@ impl DataFrameView {
     //     // self.df.row_iter(self.rows[index])
     // }
 
-    pub fn get_by_index(&self, col: usize, row: usize) -> Data {
+    pub fn get_by_index(&self, col: usize, row: usize) -> Data<'_> {
         self.df.row(self.rows[row]).get_col(col)
     }
 
+    /// How many cells of each column are empty.
+    pub fn null_counts(&self) -> Vec<usize> {
+        self.null_counts_in(0..self.rows.len())
+    }
+
+    /// How many cells of each column are empty within `rows` of the view. The counts of ranges that
+    /// cover the view add up to [`null_counts`](Self::null_counts), so large views can be counted a
+    /// piece at a time.
+    pub fn null_counts_in(&self, rows: Range<usize>) -> Vec<usize> {
+        let mut counts = vec![0; self.shape().cols];
+        for &row_idx in &self.rows[rows] {
+            for (count, cell) in counts.iter_mut().zip(self.df.row(row_idx).raw_slice()) {
+                *count += cell.is_none() as usize;
+            }
+        }
+        counts
+    }
+
+    /// The value at column `col` of row `row`, or `None` if either is out of range.
+    pub fn get(&self, col: usize, row: usize) -> Option<Data<'_>> {
+        if col >= self.shape().cols {
+            return None;
+        }
+        Some(self.try_row(row)?.get_col(col))
+    }
+
     pub fn set_by_index(&mut self, col: usize, row: usize, data: Data) {
         Arc::make_mut(&mut self.df).row_mut(self.rows[row]).set_col(col, data)
     }
 
+    /// Replaces each value of column `col` in the view with `f` of it, such as to convert units. The
+    /// column keeps its type, so values of another type are stored as that type if they fit and left
+    /// empty if not. Empty cells stay empty without calling `f`. Rows outside the view are unchanged.
+    pub fn map_column(&mut self, col: usize, mut f: impl FnMut(Data) -> Data<'static>) {
+        // A row of the frame may be in the view more than once, but must only be mapped once.
+        let mut rows = self.rows.clone();
+        rows.sort_unstable();
+        rows.dedup();
+
+        let df = Arc::make_mut(&mut self.df);
+        for row_idx in rows {
+            let value = df.row(row_idx).get_col(col);
+            if value.is_null() {
+                continue;
+            }
+            let mapped = f(value);
+            df.row_mut(row_idx).set_col(col, mapped);
+        }
+    }
+
     pub fn filter_by(&mut self, col: usize, mut f: impl FnMut(usize, &Data) -> bool) {
         let indices = {
             let col = self.col(col);
@@ -169,6 +794,21 @@ impl DataFrameView {
         self.rows = indices;
     }
 
+    pub fn filter_by_row(&mut self, mut f: impl FnMut(usize, &Row) -> bool) {
+        let indices = {
+            let mut indices = vec![];
+
+            for row_idx in 0..self.rows.len() {
+                let row = self.row(row_idx);
+                if f(row_idx, &row) {
+                    indices.push(self.rows[row_idx]);
+                }
+            }
+            indices
+        };
+        self.rows = indices;
+    }
+
     pub fn sort_by_asc(&mut self, col: usize) {
         let mut rows_sorted = self.rows.clone();
         let col = &self.df.col(col);
@@ -182,4 +822,451 @@ impl DataFrameView {
         rows_sorted.sort_by(|a_idx, b_idx| col.compare(*a_idx, *b_idx).reverse());
         self.rows = rows_sorted;
     }
+
+    /// Sorts by each `(column, ascending)` key in turn, with later keys breaking ties in earlier ones.
+    /// Rows that tie on every key keep their order. `on_progress` is called with the fraction done.
+    pub fn sort_by_columns(&mut self, keys: &[(usize, bool)], on_progress: impl FnMut(f32)) {
+        let mut rows_sorted = self.rows.clone();
+        let cols: Vec<(Column, bool)> = keys.iter().map(|&(col, ascending)| (self.df.col(col), ascending)).collect();
+        merge_sort_by(&mut rows_sorted, |a_idx, b_idx| {
+            cols.iter()
+                .map(|(col, ascending)| if *ascending { col.compare(a_idx, b_idx) } else { col.compare(a_idx, b_idx).reverse() })
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }, on_progress);
+        self.rows = rows_sorted;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::num::NonZeroU32;
+
+    use crate::data::{Data, DataType};
+    use crate::frame::DataFrameBuilder;
+    use super::{AsofDir, CsvOptions, DataFrameView, MERGE_SORT_RUN, merge_sort_by, trim_line};
+
+    fn read_csv(text: &str, options: CsvOptions) -> DataFrameView {
+        DataFrameView::from_csv(&mut text.as_bytes(), Some(text.len() as u64), options, |_| {}).unwrap().0
+    }
+
+    /// Each cell of column `col`, in the form `Integer(1)`, so that types are compared too.
+    fn cells(view: &DataFrameView, col: usize) -> Vec<String> {
+        view.col(col).iter().map(|data| format!("{:?}", data)).collect()
+    }
+
+    fn write_csv(view: &DataFrameView) -> String {
+        let mut out = vec![];
+        view.to_csv(&mut out, true, |_| {}).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn extends_with_a_string_its_frame_never_interned() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("sensor", DataType::Intern);
+        let mut other = builder.build();
+        other.add_row(&[Data::Str("Imu")]);
+        let row_idx = other.add_null_row();
+        other.row_mut(row_idx).set_col_raw(0, NonZeroU32::new(99));
+        let other = DataFrameView::from_dataframe(other);
+
+        let mut view = DataFrameView::from_dataframe(other.df.empty_like(0));
+        view.extend(&other).unwrap();
+        assert_eq!(cells(&view, 0), ["Str(\"Imu\")", "Str(\"<unknown>\")"]);
+    }
+
+    #[test]
+    fn reads_semicolons_and_decimal_commas() {
+        let options = CsvOptions { delimiter: ';', decimal_separator: ',', ..CsvOptions::default() };
+        let view = read_csv("time;value;name\n1;2,5;a\n2;-0,25;b\n", options);
+
+        assert_eq!(view.col_names().collect::<Vec<_>>(), ["time", "value", "name"]);
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(2)"]);
+        assert_eq!(cells(&view, 1), ["Float(2.5)", "Float(-0.25)"]);
+        assert_eq!(cells(&view, 2), ["Str(\"a\")", "Str(\"b\")"]);
+    }
+
+    #[test]
+    fn reads_quoted_delimiters_and_quotes() {
+        let view = read_csv("a,b\n\"x, y\",\"say \"\"hi\"\"\"\n", CsvOptions::default());
+        assert_eq!(cells(&view, 0), ["Str(\"x, y\")"]);
+        assert_eq!(cells(&view, 1), ["Str(\"say \\\"hi\\\"\")"]);
+    }
+
+    #[test]
+    fn quotes_fields_with_line_breaks_commas_and_quotes() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("note, text", DataType::Intern);
+        builder.add_column("n", DataType::Integer);
+        let mut df = builder.build();
+        df.add_row(&[Data::Str("two\nlines"), Data::Integer(1)]);
+        df.add_row(&[Data::Str("cr\r\nlf"), Data::Integer(2)]);
+        df.add_row(&[Data::Str("a \"quote\""), Data::Integer(3)]);
+        df.add_row(&[Data::Str("plain"), Data::Null]);
+
+        assert_eq!(
+            write_csv(&DataFrameView::from_dataframe(df)),
+            "\"note, text\",n\n\"two\nlines\",1\n\"cr\r\nlf\",2\n\"a \"\"quote\"\"\",3\nplain,\n"
+        );
+    }
+
+    #[test]
+    fn reads_quoted_fields_across_lines() {
+        let view = read_csv("note,n\n\"first\nsecond\",1\n\"a, \"\"b\"\"\r\n\nc\",2\nplain,3\n", CsvOptions::default());
+        assert_eq!(cells(&view, 0), ["Str(\"first\\nsecond\")", "Str(\"a, \\\"b\\\"\\r\\n\\nc\")", "Str(\"plain\")"]);
+        assert_eq!(cells(&view, 1), ["Integer(1)", "Integer(2)", "Integer(3)"]);
+    }
+
+    /// How a line was split before rows were parsed in place, copying each quoted field.
+    fn split_fields_by_copying<'a>(options: &CsvOptions, line: &'a str) -> Vec<Cow<'a, str>> {
+        let mut fields = vec![];
+        let mut rest = line;
+        loop {
+            let remaining = if let Some(mut quoted) = rest.trim_start().strip_prefix('"') {
+                let mut value = String::new();
+                loop {
+                    match quoted.find('"') {
+                        Some(idx) if quoted[idx + 1..].starts_with('"') => {
+                            value.push_str(&quoted[..idx + 1]);
+                            quoted = &quoted[idx + 2..];
+                        }
+                        Some(idx) => {
+                            value.push_str(&quoted[..idx]);
+                            quoted = &quoted[idx + 1..];
+                            break;
+                        }
+                        None => {
+                            value.push_str(quoted);
+                            quoted = "";
+                            break;
+                        }
+                    }
+                }
+                fields.push(Cow::Owned(value));
+                quoted.find(options.delimiter).map(|idx| &quoted[idx..])
+            } else {
+                let end = rest.find(options.delimiter).unwrap_or(rest.len());
+                fields.push(Cow::Borrowed(rest[..end].trim()));
+                Some(&rest[end..]).filter(|remaining| !remaining.is_empty())
+            };
+
+            match remaining {
+                Some(remaining) => rest = &remaining[options.delimiter.len_utf8()..],
+                None => return fields
+            }
+        }
+    }
+
+    #[test]
+    fn parses_rows_in_place_the_same_as_by_splitting_them() {
+        let comma = "1,imu,0.5\n\"2\", \"a, \"\"b\"\"\" ,-1\n3,\"two\nlines\",\n,,\n4\n5,gps,1.5,extra\n 6 , baro ,  \n\"7\",\"\",\"\"\r\n";
+        let semicolon = "1;imu;0,5\n\"2\";\"a; \"\"b\"\"\";-1,25\n3;\"two\r\nlines\"\n;;\n";
+        let cases = [
+            (CsvOptions { lenient: true, ..CsvOptions::default() }, comma, 8, 2),
+            (CsvOptions { lenient: true, delimiter: ';', decimal_separator: ',', ..CsvOptions::default() }, semicolon, 4, 1),
+        ];
+        let data_types = [DataType::Integer, DataType::Intern, DataType::Float];
+
+        for (options, text, rows, expected_malformed) in cases {
+            let frame = || {
+                let mut builder = DataFrameBuilder::new();
+                for (name, ty) in ["time", "name", "value"].into_iter().zip(data_types) {
+                    builder.add_column(name, ty);
+                }
+                builder.build()
+            };
+            let mut by_splitting = frame();
+            let mut in_place = frame();
+
+            let mut file = text.as_bytes();
+            let mut record = String::new();
+            let mut scratch = String::new();
+            let mut malformed_rows = 0;
+            while options.read_record(&mut file, &mut record).unwrap() > 0 {
+                let fields = split_fields_by_copying(&options, trim_line(&record));
+                let row: Vec<Data> = data_types.iter().enumerate()
+                    .map(|(idx, &ty)| fields.get(idx).map_or(Data::Null, |item| options.parse_item(ty, item)))
+                    .collect();
+                by_splitting.add_row(&row);
+                options.add_row(&mut in_place, &data_types, &record, &mut scratch, &mut malformed_rows).unwrap();
+                record.clear();
+            }
+
+            let by_splitting = DataFrameView::from_dataframe(by_splitting);
+            let in_place = DataFrameView::from_dataframe(in_place);
+            assert_eq!(in_place.shape().rows, rows);
+            for col in 0..data_types.len() {
+                assert_eq!(cells(&in_place, col), cells(&by_splitting, col));
+            }
+            assert_eq!(malformed_rows, expected_malformed);
+        }
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("text", DataType::Intern);
+        builder.add_column("value", DataType::Float);
+        let mut df = builder.build();
+        df.add_row(&[Data::Str("line one\nline two"), Data::Float(1.5)]);
+        df.add_row(&[Data::Str("\"quoted\", with comma"), Data::Float(-2.0)]);
+        let written = write_csv(&DataFrameView::from_dataframe(df));
+
+        let view = read_csv(&written, CsvOptions::default());
+        assert_eq!(view.shape().rows, 2);
+        assert_eq!(cells(&view, 0), ["Str(\"line one\\nline two\")", "Str(\"\\\"quoted\\\", with comma\")"]);
+        assert_eq!(cells(&view, 1), ["Float(1.5)", "Float(-2.0)"]);
+    }
+
+    #[test]
+    fn lists_distinct_strings_of_the_rows_in_view() {
+        let mut view = read_csv("sensor,n\nimu,1\ngps,2\n,3\nimu,4\nbaro,5\n", CsvOptions::default());
+        assert_eq!(view.distinct_strs(0), ["baro", "gps", "imu"]);
+        assert!(view.distinct_strs(1).is_empty());
+
+        view.filter_by(1, |_, data| data.as_integer().is_some_and(|n| n < 3));
+        assert_eq!(view.distinct_strs(0), ["gps", "imu"]);
+    }
+
+    #[test]
+    fn extends_an_unshared_frame_in_place() {
+        let mut view = read_csv("time,name\n1,a\n", CsvOptions::default());
+        let more = read_csv("time,name\n2,b\n3,a\n", CsvOptions::default());
+
+        let before = std::sync::Arc::as_ptr(&view.df);
+        view.extend(&more).unwrap();
+        assert_eq!(std::sync::Arc::as_ptr(&view.df), before);
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(2)", "Integer(3)"]);
+        assert_eq!(cells(&view, 1), ["Str(\"a\")", "Str(\"b\")", "Str(\"a\")"]);
+
+        let shared = view.clone();
+        view.extend(&more).unwrap();
+        assert_eq!(shared.shape().rows, 3);
+        assert_eq!(view.shape().rows, 5);
+
+        let other = read_csv("time\n4\n", CsvOptions::default());
+        assert_eq!(view.extend(&other).unwrap_err(), "Columns do not match: 'name' is missing");
+    }
+
+    #[test]
+    fn reads_back_typed_columns_written_with_their_types() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("sensor:name", DataType::Intern);
+        builder.add_column("timestamp", DataType::Unsigned);
+        builder.add_column("offset", DataType::Integer);
+        builder.add_column("pressure", DataType::Float);
+        let mut df = builder.build();
+        df.add_row(&[Data::Str("1"), Data::Unsigned(4000000000), Data::Integer(-3), Data::Float(1.5)]);
+        df.add_row(&[Data::Str("IMU"), Data::Null, Data::Integer(2), Data::Float(2.0)]);
+        let view = DataFrameView::from_dataframe(df);
+
+        let mut out = vec![];
+        view.to_csv_typed(&mut out, &[0, 1, 2, 3], |_| {}).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("sensor:name:string,timestamp:unsigned,offset:integer,pressure:float\n"));
+
+        let read = read_csv(&text, CsvOptions { typed_header: true, ..CsvOptions::default() });
+        assert_eq!(read.col_names().collect::<Vec<_>>(), ["sensor:name", "timestamp", "offset", "pressure"]);
+        for col in 0..4 {
+            assert_eq!(read.df.col(col).data_type().name(), view.df.col(col).data_type().name());
+            assert_eq!(cells(&read, col), cells(&view, col));
+        }
+
+        // Without the option, the header is only names, and the types are guessed.
+        let untyped = read_csv(&text, CsvOptions::default());
+        assert_eq!(untyped.col_names().collect::<Vec<_>>(), ["sensor:name:string", "timestamp:unsigned", "offset:integer", "pressure:float"]);
+    }
+
+    #[test]
+    fn fails_on_a_typed_header_missing_a_type() {
+        let text = "time:integer,value\n1,2\n";
+        let options = CsvOptions { typed_header: true, ..CsvOptions::default() };
+        let error = DataFrameView::from_csv(&mut text.as_bytes(), None, options, |_| {}).err().unwrap();
+        assert_eq!(error.to_string(), "Column 'value' has no type in the header, such as 'value:float'.");
+    }
+
+    #[test]
+    fn concatenates_frames_with_their_strings_interned_once() {
+        let first = read_csv("time,sensor\n1,imu\n2,gps\n", CsvOptions::default());
+        // `gps` is interned first here, so its symbol differs from the one in `first`.
+        let mut second = read_csv("time,sensor\n3,gps\n4,imu\n5,baro\n", CsvOptions::default());
+        second.filter_by(0, |_, data| data.as_integer() != Some(4));
+        let third = read_csv("time,sensor\n6,mag\n7,\n", CsvOptions::default());
+
+        let view = DataFrameView::concat(&[first, second, third]).unwrap();
+        assert_eq!(view.col_names().collect::<Vec<_>>(), ["time", "sensor"]);
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(2)", "Integer(3)", "Integer(5)", "Integer(6)", "Integer(7)"]);
+        assert_eq!(cells(&view, 1), ["Str(\"imu\")", "Str(\"gps\")", "Str(\"gps\")", "Str(\"baro\")", "Str(\"mag\")", "Str(\"\")"]);
+        assert_eq!(view.distinct_strs(1), ["baro", "gps", "imu", "mag"]);
+    }
+
+    #[test]
+    fn fails_to_concatenate_frames_with_different_columns() {
+        let first = read_csv("time,sensor\n1,imu\n", CsvOptions::default());
+        let renamed = read_csv("time,name\n2,gps\n", CsvOptions::default());
+        let retyped = read_csv("time,sensor\n2.5,gps\n", CsvOptions::default());
+
+        assert_eq!(DataFrameView::concat(&[first.clone(), first.clone(), renamed]).err().unwrap(), "Columns of data 3 do not match: 'sensor' (String) vs 'name' (String)");
+        assert_eq!(DataFrameView::concat(&[first, retyped]).err().unwrap(), "Columns of data 2 do not match: 'time' (Integer) vs 'time' (Float)");
+        assert_eq!(DataFrameView::concat(&[]).err().unwrap(), "No data to concatenate.");
+    }
+
+    #[test]
+    fn infers_types_from_as_many_rows_as_asked() {
+        let text = "value,name\n1,a\n2,b\n1.5,c\n";
+        let view = read_csv(text, CsvOptions { infer_rows: 3, ..CsvOptions::default() });
+        assert_eq!(cells(&view, 0), ["Float(1.0)", "Float(2.0)", "Float(1.5)"]);
+
+        // Only the first row is looked at, so the column holds integers and `1.5` doesn't fit.
+        let view = read_csv(text, CsvOptions { infer_rows: 1, ..CsvOptions::default() });
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(2)", "Null"]);
+    }
+
+    #[test]
+    fn pads_or_cuts_malformed_rows_only_when_lenient() {
+        let text = "time,value,name\n1,2.5,a\n2,3.5,b,extra\n3,4.5,c\n4,5";
+        let error = DataFrameView::from_csv(&mut text.as_bytes(), None, CsvOptions::default(), |_| {}).err().unwrap();
+        assert_eq!(error.to_string(), "Malformed CSV file.");
+
+        let options = CsvOptions { lenient: true, ..CsvOptions::default() };
+        let (view, malformed_rows) = DataFrameView::from_csv(&mut text.as_bytes(), None, options, |_| {}).unwrap();
+        assert_eq!(malformed_rows, 2);
+        assert_eq!(view.col_names().collect::<Vec<_>>(), ["time", "value", "name"]);
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(2)", "Integer(3)", "Integer(4)"]);
+        assert_eq!(cells(&view, 1), ["Float(2.5)", "Float(3.5)", "Float(4.5)", "Float(5.0)"]);
+        assert_eq!(cells(&view, 2), ["Str(\"a\")", "Str(\"b\")", "Str(\"c\")", "Null"]);
+    }
+
+    #[test]
+    fn joins_the_row_before_or_nearest_in_time() {
+        let left = read_csv("time,value\n0,a\n5,b\n6,c\n10,d\n,e\n20,f\n", CsvOptions::default());
+        let right = read_csv("time,value\n2,1.5\n10,2.5\n13,3.5\n", CsvOptions::default());
+
+        let backward = left.asof_join(&right, 0, 0, AsofDir::Backward);
+        assert_eq!(backward.col_names().collect::<Vec<_>>(), ["time", "value", "time_right", "value_right"]);
+        assert_eq!(cells(&backward, 1), cells(&left, 1));
+        assert_eq!(cells(&backward, 2), ["Null", "Integer(2)", "Integer(2)", "Integer(10)", "Null", "Integer(13)"]);
+        assert_eq!(cells(&backward, 3), ["Null", "Float(1.5)", "Float(1.5)", "Float(2.5)", "Null", "Float(3.5)"]);
+
+        // 6 is as far from 2 as from 10, so the earlier row is taken.
+        let nearest = left.asof_join(&right, 0, 0, AsofDir::Nearest);
+        assert_eq!(cells(&nearest, 2), ["Integer(2)", "Integer(2)", "Integer(2)", "Integer(10)", "Null", "Integer(13)"]);
+        assert_eq!(cells(&nearest, 3), ["Float(1.5)", "Float(1.5)", "Float(1.5)", "Float(2.5)", "Null", "Float(3.5)"]);
+    }
+
+    #[test]
+    fn diffs_frames_differing_in_one_column() {
+        let old = read_csv("time,altitude,state\n1,100,idle\n2,110,boost\n3,125,coast\n", CsvOptions::default());
+        let new = read_csv("time,altitude,state\n1,100,idle\n2,110,coast\n3,125,coast\n4,140,coast\n", CsvOptions::default());
+
+        let by_row = new.diff(&old, None).unwrap();
+        assert_eq!(by_row.col_names().collect::<Vec<_>>(), ["row", "time", "altitude", "state"]);
+        assert_eq!(cells(&by_row, 0), ["Integer(0)", "Integer(1)", "Integer(2)"]);
+        assert_eq!(cells(&by_row, 1), ["Float(0.0)", "Float(0.0)", "Float(0.0)"]);
+        assert_eq!(cells(&by_row, 2), ["Float(0.0)", "Float(0.0)", "Float(0.0)"]);
+        assert_eq!(cells(&by_row, 3), ["Integer(0)", "Integer(1)", "Integer(0)"]);
+
+        let by_time = new.diff(&old, Some(0)).unwrap();
+        assert_eq!(by_time.col_names().collect::<Vec<_>>(), ["time", "altitude", "state"]);
+        assert_eq!(cells(&by_time, 0), ["Integer(1)", "Integer(2)", "Integer(3)"]);
+        assert_eq!(cells(&by_time, 2), ["Integer(0)", "Integer(1)", "Integer(0)"]);
+    }
+
+    #[test]
+    fn converts_a_column_to_text_and_back_counting_what_changed() {
+        let view = read_csv("id,n\n1,10\n-2,20\n,30\n3,40\n", CsvOptions::default());
+
+        let (text, changed) = view.with_column_type(0, DataType::Intern, |_| {});
+        assert_eq!(changed, 0);
+        assert_eq!(cells(&text, 0), ["Str(\"1\")", "Str(\"-2\")", "Null", "Str(\"3\")"]);
+        assert_eq!(cells(&text, 1), cells(&view, 1));
+        assert_eq!(cells(&view, 0), ["Integer(1)", "Integer(-2)", "Null", "Integer(3)"]);
+
+        let mut text = text;
+        text.set_by_index(0, 1, Data::Str("two"));
+        text.set_by_index(0, 3, Data::Str(" 3 "));
+        let mut rows = vec![];
+        let (back, changed) = text.with_column_type(0, DataType::Integer, |row_idx| rows.push(row_idx));
+        // Text that isn't a number is left empty. Spaces around a number are trimmed, which also counts
+        // as a change.
+        assert_eq!(changed, 2);
+        assert_eq!(cells(&back, 0), ["Integer(1)", "Null", "Null", "Integer(3)"]);
+        assert_eq!(rows, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn sorts_by_later_columns_within_ties_and_keeps_full_ties_in_order() {
+        let mut view = read_csv("timestamp,sensor,n\n20,imu,0\n10,imu,1\n20,baro,2\n10,gps,3\n20,imu,4\n10,baro,5\n", CsvOptions::default());
+
+        view.sort_by_columns(&[(0, true), (1, true)], |_| {});
+        assert_eq!(cells(&view, 0), ["Integer(10)", "Integer(10)", "Integer(10)", "Integer(20)", "Integer(20)", "Integer(20)"]);
+        assert_eq!(cells(&view, 1), ["Str(\"baro\")", "Str(\"gps\")", "Str(\"imu\")", "Str(\"baro\")", "Str(\"imu\")", "Str(\"imu\")"]);
+        // Rows 0 and 4 tie on both keys, so they stay in the order they were in.
+        assert_eq!(cells(&view, 2), ["Integer(5)", "Integer(3)", "Integer(1)", "Integer(2)", "Integer(0)", "Integer(4)"]);
+
+        view.sort_by_columns(&[(1, false)], |_| {});
+        assert_eq!(cells(&view, 2), ["Integer(1)", "Integer(0)", "Integer(4)", "Integer(3)", "Integer(5)", "Integer(2)"]);
+    }
+
+    #[test]
+    fn merge_sorts_many_runs_stably_with_rising_progress() {
+        let len = 100_000;
+        assert!(len > 16 * MERGE_SORT_RUN);
+        // Few distinct keys, so that most rows tie with many others, some in other runs.
+        let key = |row: usize| (row * 7919) % 1000;
+        let mut rows: Vec<usize> = (0..len).collect();
+        let mut progress = vec![];
+        merge_sort_by(&mut rows, |a, b| key(a).cmp(&key(b)), |amount| progress.push(amount));
+
+        let mut expected: Vec<usize> = (0..len).collect();
+        expected.sort_by_key(|&row| key(row));
+        assert_eq!(rows, expected);
+        assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]), "progress went backwards");
+        assert!(progress[0] > 0.0);
+        assert_eq!(progress.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn iterates_rows_in_the_order_of_a_filtered_and_sorted_view() {
+        let mut view = read_csv("n,name\n3,c\n1,a\n4,d\n1,e\n5,f\n", CsvOptions::default());
+        view.filter_by(0, |_, data| data.as_integer() != Some(4));
+        view.sort_by_desc(0);
+
+        let iterated: Vec<String> = view.iter_rows().map(|row| format!("{:?} {:?}", row.get_col(0), row.get_col(1))).collect();
+        let indexed: Vec<String> = (0..view.shape().rows).map(|idx| format!("{:?} {:?}", view.get_by_index(0, idx), view.get_by_index(1, idx))).collect();
+        assert_eq!(iterated, indexed);
+        assert_eq!(iterated, ["Integer(5) Str(\"f\")", "Integer(3) Str(\"c\")", "Integer(1) Str(\"a\")", "Integer(1) Str(\"e\")"]);
+    }
+
+    #[test]
+    fn gets_nothing_outside_the_view() {
+        let mut view = read_csv("n,name\n1,a\n2,b\n3,c\n", CsvOptions::default());
+        view.filter_by(0, |_, data| data.as_integer() != Some(2));
+
+        assert_eq!(view.get(1, 1).map(|data| format!("{:?}", data)).as_deref(), Some("Str(\"c\")"));
+        // The frame still holds a third row, but the view doesn't.
+        assert!(view.try_row(2).is_none());
+        assert!(view.get(0, 2).is_none());
+        assert!(view.get(2, 0).is_none());
+        assert!(view.df.try_row(3).is_none());
+        assert!(view.df.try_row(2).is_some());
+    }
+
+    #[test]
+    fn counts_empty_cells_of_each_column() {
+        let mut view = read_csv("a,b,c\n1,,x\n,,y\n3,2.5,\n,,\n5,3.5,z\n", CsvOptions::default());
+        // Empty fields of a text column are read as empty strings rather than empty cells.
+        view.set_by_index(2, 2, Data::Null);
+        view.set_by_index(2, 3, Data::Null);
+
+        assert_eq!(view.null_counts(), [2, 3, 2]);
+        let halves = [view.null_counts_in(0..2), view.null_counts_in(2..5)];
+        assert_eq!(halves, [vec![1, 2, 0], vec![1, 1, 2]]);
+
+        view.filter_by(0, |_, data| !data.is_null());
+        assert_eq!(view.null_counts(), [0, 1, 1]);
+    }
 }
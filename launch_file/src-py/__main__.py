@@ -30,10 +30,10 @@ def construct_values_mapping(ctxt: Context) -> dict[int, tuple[str, Type]]:
 
     timestamp_type = format_struct.members["timestamp_ms"]
     if not isinstance(timestamp_type, Integer):
-        raise Exception("'LoggedReading.timestamp_ms' is not a uint32_t")
+        raise Exception("'LoggedReading.timestamp_ms' is not a uint32_t or uint64_t")
 
-    if timestamp_type.signed or timestamp_type.size != 4:
-        raise Exception("'LoggedReading.timestamp_ms' is not a uint32_t")
+    if timestamp_type.signed or timestamp_type.size not in (4, 8):
+        raise Exception("'LoggedReading.timestamp_ms' is not a uint32_t or uint64_t")
 
     variants_type = format_struct.members["data"]
     if not isinstance(variants_type, Union):
@@ -56,6 +56,11 @@ def construct_values_mapping(ctxt: Context) -> dict[int, tuple[str, Type]]:
     return variants
 
 
+def packet_header(ctxt: Context) -> dict[str, int]:
+    format_struct = ctxt.types["LoggedReading"]
+    return {"discriminant": format_struct.members["discriminant"].size, "timestamp": format_struct.members["timestamp_ms"].size}
+
+
 def flatten_headers(prev: str, this: Type) -> list[str]:
     items = []
     if isinstance(this, types.Union):
@@ -188,7 +193,7 @@ def main():
         with open(args.out, "w") as out_file:
             print(f"Writing schema to '{args.out}'")
 
-            schema = {"<checksum>": expected_checksum}
+            schema = {"<checksum>": expected_checksum, "<header>": packet_header(ctxt)}
             for num, (name, ty) in mapping.items():
                 schema[name] = [num, ty.get_schema()]
             json.dump(schema, out_file, indent=4)
@@ -206,14 +211,15 @@ def main():
             total_size = args.raw.stat().st_size
             processed = 0
 
+            header = packet_header(ctxt)
             headers = args.data.write_header(out_file, mapping)
             index = 0
             while True:
-                disc_bytes = data_file.read(4)
+                disc_bytes = data_file.read(header["discriminant"])
                 if len(disc_bytes) == 0:
                     break
                 discriminant = int.from_bytes(disc_bytes, byteorder='little')
-                timestamp_ms = int.from_bytes(data_file.read(4), byteorder="little")
+                timestamp_ms = int.from_bytes(data_file.read(header["timestamp"]), byteorder="little")
                 data_name, data_type = mapping[discriminant]
                 try:
                     data = data_type.parse(data_file.read(data_type.size))
@@ -221,7 +227,7 @@ def main():
                     break
                 args.data.write(out_file, {"type": data_name, "timestamp": timestamp_ms, "data": data}, index, headers)
                 index += 1
-                processed += 4 + 4 + data_type.size
+                processed += header["discriminant"] + header["timestamp"] + data_type.size
                 if index % 100 == 0 or processed == total_size:
                     diff = time.time() - start_time
                     remaining = diff / (processed / total_size) - diff
@@ -52,7 +52,8 @@ initializer: IDENTIFIER parenthesized
 
 ?const_expr: const_expr_1
 
-enum: "enum" "class"? IDENTIFIER "{" _enum_variants "}" ";"
+enum: "enum" "class"? IDENTIFIER enum_base? "{" _enum_variants "}" ";"
+enum_base: ":" IDENTIFIER
 _enum_variants: (enum_variant ("," enum_variant)* ","?)?
 enum_variant: IDENTIFIER ("=" number)?
 
@@ -234,26 +235,29 @@ class Array(Type):
 
 
 class Enum(Type):
-    def __init__(self, variants: dict[int, str]):
+    def __init__(self, variants: dict[int, str], size: int = 4, signed: bool = True):
         self.variants = variants
         # unless otherwise specified, pretty much every compiler makes enums backed by a uint32_t by default
-        # so we use the characteristics of a uint32_t
-        super().__init__(4, 4)
+        # so we use the characteristics of a uint32_t, unless the enum names its own underlying type
+        super().__init__(size, size)
+        # an enum backed by an unsigned type can have values past the largest of the signed type of its size
+        self.signed = signed
 
     def parse(self, data: bytes):
-        as_int = struct.unpack("i", data)[0]
+        codes = {1: "b", 2: "h", 4: "i"} if self.signed else {1: "B", 2: "H", 4: "I"}
+        as_int = struct.unpack(codes[self.size], data)[0]
         # having accessed the integer value, we map it to its name, which is stored in the `variants` field
         # return as_int
         return self.variants[as_int]
 
     def get_schema(self) -> Any:
-        return {"type": "enum", "variants": {name: value for value, name in self.variants.items()}}
+        return {"type": "enum", "variants": {name: value for value, name in self.variants.items()}, "size": self.size}
 
     def __eq__(self, other):
         return self is other
 
     def __repr__(self):
-        return f"Enum(variants={self.variants})"
+        return f"Enum(variants={self.variants}, size={self.size}, signed={self.signed})"
 
 
 class Boolean(Type):
@@ -452,6 +456,12 @@ class Calculate(Interpreter):
         # the @v_args(inline=True) means that the children of the `enum` node will be provided as arguments, instead of
         # the `enum` node itself being provided as the argument as would happen normally
 
+        # an `enum class Name : uint16_t` names the integer type holding it, which comes before the variants
+        size, signed = 4, True
+        if variants and variants[0].data == "enum_base":
+            size, signed = self.visit(variants[0])
+            variants = variants[1:]
+
         enum: dict[int, str] = {}  # stores the variants of the enum as a mapping from value to the name of the variant
         recent = 0
         for variant in variants:
@@ -464,7 +474,13 @@ class Calculate(Interpreter):
 
         # having collected a map from the number which represents each variant to the variant's name, make it into an
         # Enum type and put it in this context's type map
-        self.types[str(name)] = Enum(enum)
+        self.types[str(name)] = Enum(enum, size, signed)
+
+    @v_args(inline=True)
+    def enum_base(self, name: lark.Token) -> tuple[int, bool]:
+        if str(name) not in ENUM_BASES:
+            raise Exception(f"Enums backed by '{name}' are not supported")
+        return ENUM_BASES[str(name)]
 
     @v_args(inline=True)
     def enum_variant(self, name: lark.Token, value: lark.Tree = None) -> tuple[str, int | None]:
@@ -666,6 +682,16 @@ BASE_TYPES: dict[str, Type] = {
     # 'systime_t': Integer(4)
 }
 BASE_TEMPLATES: dict[str, Template] = {}
+# the size in bytes of an enum backed by each integer type, and whether that type is signed
+ENUM_BASES: dict[str, tuple[int, bool]] = {
+    'uint8_t': (1, False),
+    'int8_t': (1, True),
+    'uint16_t': (2, False),
+    'int16_t': (2, True),
+    'uint32_t': (4, False),
+    'int32_t': (4, True),
+    'int': (4, True),
+}
 BASE_CTXT = Context(BASE_NAMES, BASE_TYPES, BASE_TEMPLATES)
 
 STD_HEADERS = {
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+/// A checksum that firmware writes after each packet, computed over the packet's header and body.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Crc {
+    /// CRC-16/CCITT-FALSE: polynomial 0x1021, starting from 0xFFFF, unreflected.
+    #[serde(rename = "crc16")]
+    Crc16,
+    /// The CRC-32 of zlib and Ethernet: polynomial 0x04C11DB7 reflected, starting from and finished
+    /// with 0xFFFFFFFF.
+    #[serde(rename = "crc32")]
+    Crc32,
+}
+
+const CRC16_TABLE: [u16; 256] = crc16_table();
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc16_table() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { crc >> 1 ^ 0xEDB88320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Crc {
+    /// The usual name of the checksum, such as `CRC-16`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crc::Crc16 => "CRC-16",
+            Crc::Crc32 => "CRC-32",
+        }
+    }
+
+    /// The size of the checksum in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Crc::Crc16 => 2,
+            Crc::Crc32 => 4,
+        }
+    }
+
+    /// The checksum of `parts` one after another, as if they were one slice.
+    pub fn checksum(&self, parts: &[&[u8]]) -> u32 {
+        let bytes = parts.iter().flat_map(|part| part.iter());
+        match self {
+            Crc::Crc16 => bytes.fold(0xFFFFu16, |crc, &byte| crc << 8 ^ CRC16_TABLE[((crc >> 8) as u8 ^ byte) as usize]) as u32,
+            Crc::Crc32 => !bytes.fold(0xFFFFFFFFu32, |crc, &byte| crc >> 8 ^ CRC32_TABLE[(crc as u8 ^ byte) as usize]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Crc;
+
+    #[test]
+    fn matches_the_standard_check_values() {
+        assert_eq!(Crc::Crc16.checksum(&[b"123456789"]), 0x29B1);
+        assert_eq!(Crc::Crc32.checksum(&[b"123456789"]), 0xCBF43926);
+    }
+
+    #[test]
+    fn checksums_parts_as_one_slice() {
+        for crc in [Crc::Crc16, Crc::Crc32] {
+            assert_eq!(crc.checksum(&[b"1234", b"", b"56789"]), crc.checksum(&[b"123456789"]));
+        }
+    }
+}
@@ -2,12 +2,14 @@ use std::collections::HashMap;
 use std::num::NonZeroU32;
 
 use ahash::AHashMap;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{ByteOrder, ReadBytesExt};
 use serde::Deserialize;
 use indexmap::IndexMap;
 
 use dataframe::{Data, DataFrameBuilder, DataType, RowMut};
 
+use crate::{LargeUnsigned, LogFormatError, NonFinite};
+
 #[derive(Deserialize, Clone)]
 #[serde(tag = "type")]
 pub enum SerializedCpp {
@@ -24,7 +26,11 @@ pub enum SerializedCpp {
     },
     #[serde(rename = "enum")]
     Enum {
-        variants: IndexMap<String, u32>
+        variants: IndexMap<String, u32>,
+        /// The size of the integer holding the enum, which schemas from before it was written
+        /// leave out.
+        #[serde(default = "default_enum_size")]
+        size: u8
     },
     #[serde(rename = "array")]
     Array {
@@ -38,9 +44,18 @@ pub enum SerializedCpp {
     #[serde(rename = "union")]
     Union {
         variants: Vec<(String, SerializedCpp)>
+    },
+    /// A `uint32_t` register where each named bit is a flag.
+    #[serde(rename = "bitfield")]
+    Bitfield {
+        bits: IndexMap<String, u8>
     }
 }
 
+fn default_enum_size() -> u8 {
+    4
+}
+
 pub enum ReadType {
     Bool,
     I8,
@@ -49,22 +64,45 @@ pub enum ReadType {
     // I64,
     U8,
     // U16,
-    U32,
+    U32(LargeUnsigned),
     // U64,
     F32,
     F64,
     Discriminant(u8),
+    Bitfield(u8),
+    Str(u32),
     Padding(u8)
 }
 
+/// Where a field sits in the body of a packet, for describing a format.
+#[derive(Clone, Debug)]
+pub struct FieldLayout {
+    /// The name of its column, which includes the structs and arrays it is part of.
+    pub name: String,
+    /// Its C++ type, such as `uint32_t` or `char[16]`, along with the names of its values for enums
+    /// and bitfields.
+    pub ty: String,
+    /// Its offset in bytes from the start of the packet body.
+    pub offset: usize,
+    pub size: usize
+}
+
 pub struct Deserializer {
     items: Vec<(ReadType, usize)>,
     enums: Vec<AHashMap<u32, NonZeroU32>>,
+    /// The bit and column of each flag in a bitfield.
+    bitfields: Vec<Vec<(u8, usize)>>,
+    pub fields: Vec<FieldLayout>,
     pub size: usize
 }
 
 impl Deserializer {
-    pub fn parse<'a, 'b>(&'a self, mut buf: &[u8], row: &mut RowMut<'b>) where 'a: 'b {
+    /// Reads nothing from a body of `size` bytes, for walking packets without reading them.
+    pub fn skip(size: usize) -> Deserializer {
+        Deserializer { items: vec![], enums: vec![], bitfields: vec![], fields: vec![], size }
+    }
+
+    pub fn parse<'a, 'b, B: ByteOrder>(&'a self, mut buf: &[u8], row: &mut RowMut<'b>, non_finite: NonFinite) where 'a: 'b {
         debug_assert_eq!(buf.len(), self.size);
         // let mut padding_buf = [0; 256];
         for (ty, offset) in &self.items {
@@ -77,25 +115,38 @@ impl Deserializer {
                     row.set_col_with_ty(offset, DataType::Integer, Data::Integer(buf.read_i8().unwrap() as i32));
                 }
                 ReadType::I32 => {
-                    row.set_col_with_ty(offset, DataType::Integer, Data::Integer(buf.read_i32::<LittleEndian>().unwrap()));
+                    row.set_col_with_ty(offset, DataType::Integer, Data::Integer(buf.read_i32::<B>().unwrap()));
                 }
                 ReadType::U8 => {
                     row.set_col_with_ty(offset, DataType::Integer, Data::Integer(buf.read_u8().unwrap() as i32));
                 }
-                ReadType::U32 => {
-                    row.set_col_with_ty(offset, DataType::Integer, Data::Integer(buf.read_u32::<LittleEndian>().unwrap() as i32));
+                &ReadType::U32(large_unsigned) => {
+                    row.set_col_with_ty(offset, large_unsigned.data_type(), large_unsigned.apply(buf.read_u32::<B>().unwrap()));
                 }
                 ReadType::F32 => {
-                    row.set_col_with_ty(offset, DataType::Float, Data::Float(buf.read_f32::<LittleEndian>().unwrap()));
+                    row.set_col_with_ty(offset, DataType::Float, non_finite.apply(buf.read_f32::<B>().unwrap()));
                 }
                 ReadType::F64 => {
-                    row.set_col_with_ty(offset, DataType::Float, Data::Float(buf.read_f64::<LittleEndian>().unwrap() as f32));
+                    // Doubles too large for a float become infinite, and are treated as such.
+                    row.set_col_with_ty(offset, DataType::Float, non_finite.apply(buf.read_f64::<B>().unwrap() as f32));
                 }
                 ReadType::Discriminant(idx) => {
-                    let disc = buf.read_u32::<LittleEndian>().unwrap();
+                    let disc = buf.read_u32::<B>().unwrap();
                     let value = self.enums[*idx as usize].get(&disc).cloned();
                     row.set_col_raw(offset, value);
                 }
+                ReadType::Bitfield(idx) => {
+                    let register = buf.read_u32::<B>().unwrap();
+                    for &(bit, col) in &self.bitfields[*idx as usize] {
+                        row.set_col_with_ty(col, DataType::Integer, Data::Integer((register >> bit & 1) as i32));
+                    }
+                }
+                &ReadType::Str(len) => {
+                    let (bytes, rest) = buf.split_at(len as usize);
+                    buf = rest;
+                    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                    row.set_col_with_ty(offset, DataType::Intern, Data::Str(&String::from_utf8_lossy(&bytes[..end])));
+                }
                 &ReadType::Padding(amount) => {
                     buf = &buf[amount as usize..];
                 }
@@ -109,66 +160,89 @@ pub struct DeserializerBuilder<'a> {
 
     items: Vec<(ReadType, usize)>,
     offset: usize,
-    enums: Vec<AHashMap<u32, NonZeroU32>>
+    enums: Vec<AHashMap<u32, NonZeroU32>>,
+    bitfields: Vec<Vec<(u8, usize)>>,
+    fields: Vec<FieldLayout>,
+    large_unsigned: LargeUnsigned
 }
 
 impl<'a> DeserializerBuilder<'a> {
-    pub fn new(builder: &'a mut DataFrameBuilder) -> DeserializerBuilder<'a> {
+    pub fn new(builder: &'a mut DataFrameBuilder, large_unsigned: LargeUnsigned) -> DeserializerBuilder<'a> {
         DeserializerBuilder {
             builder,
             items: vec![],
             offset: 0,
-            enums: vec![]
+            enums: vec![],
+            bitfields: vec![],
+            fields: vec![],
+            large_unsigned
         }
     }
 
     pub fn finish(self) -> Deserializer {
-        Deserializer { items: self.items, enums: self.enums, size: self.offset }
+        Deserializer { items: self.items, enums: self.enums, bitfields: self.bitfields, fields: self.fields, size: self.offset }
+    }
+
+    /// Notes a field of `size` bytes at the current offset, before the offset moves past it.
+    fn add_field(&mut self, name: &str, ty: impl Into<String>, size: usize) {
+        self.fields.push(FieldLayout { name: name.to_string(), ty: ty.into(), offset: self.offset, size });
     }
 
-    fn read_bool(&mut self, name: impl Into<String>) {
+    fn read_bool(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Integer);
         self.items.push((ReadType::Bool, offset));
+        self.add_field(name, "bool", 1);
         self.offset += 1;
     }
 
-    fn read_i8(&mut self, name: impl Into<String>) {
+    fn read_i8(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Integer);
         self.items.push((ReadType::I8, offset));
+        self.add_field(name, "int8_t", 1);
         self.offset += 1;
     }
 
-    fn read_i32(&mut self, name: impl Into<String>) {
+    fn read_i32(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Integer);
         self.items.push((ReadType::I32, offset));
+        self.add_field(name, "int32_t", 4);
         self.offset += 4;
     }
 
-    fn read_u8(&mut self, name: impl Into<String>) {
+    fn read_u8(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Integer);
         self.items.push((ReadType::U8, offset));
+        self.add_field(name, "uint8_t", 1);
         self.offset += 1;
     }
 
-    fn read_u32(&mut self, name: impl Into<String>) {
-        let offset = self.builder.add_column(name, DataType::Integer);
-        self.items.push((ReadType::U32, offset));
+    fn read_u32(&mut self, name: &str) {
+        let offset = self.builder.add_column(name, self.large_unsigned.data_type());
+        self.items.push((ReadType::U32(self.large_unsigned), offset));
+        self.add_field(name, "uint32_t", 4);
         self.offset += 4;
     }
 
-    fn read_f32(&mut self, name: impl Into<String>) {
+    fn read_f32(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Float);
         self.items.push((ReadType::F32, offset));
+        self.add_field(name, "float", 4);
         self.offset += 4;
     }
 
-    fn read_f64(&mut self, name: impl Into<String>) {
+    fn read_f64(&mut self, name: &str) {
         let offset = self.builder.add_column(name, DataType::Float);
         self.items.push((ReadType::F64, offset));
+        self.add_field(name, "double", 8);
         self.offset += 8;
     }
 
-    fn read_enum(&mut self, name: impl Into<String>, variants: HashMap<u32, String>) {
+    fn read_enum(&mut self, name: &str, variants: HashMap<u32, String>) {
+        let mut values = variants.iter().collect::<Vec<_>>();
+        values.sort();
+        let values = values.iter().map(|(disc, variant)| format!("{} = {}", variant, disc)).collect::<Vec<_>>();
+        self.add_field(name, format!("enum ({})", values.join(", ")), 4);
+
         let offset = self.builder.add_column(name, DataType::Intern);
         let idx = self.enums.len() as u8;
         let mut variant_to_intern = AHashMap::new();
@@ -180,6 +254,26 @@ impl<'a> DeserializerBuilder<'a> {
         self.offset += 4;
     }
 
+    fn read_bitfield(&mut self, name: &str, bits: &IndexMap<String, u8>) {
+        let flags = bits.iter().map(|(flag, bit)| format!("{}: bit {}", flag, bit)).collect::<Vec<_>>();
+        self.add_field(name, format!("bitfield ({})", flags.join(", ")), 4);
+
+        let idx = self.bitfields.len() as u8;
+        let columns = bits.iter().map(|(flag, bit)| {
+            (*bit, self.builder.add_column(format!("{}.{}", name, flag), DataType::Integer))
+        }).collect();
+        self.bitfields.push(columns);
+        self.items.push((ReadType::Bitfield(idx), 0));
+        self.offset += 4;
+    }
+
+    fn read_str(&mut self, name: &str, len: u32) {
+        let offset = self.builder.add_column(name, DataType::Intern);
+        self.items.push((ReadType::Str(len), offset));
+        self.add_field(name, format!("char[{}]", len), len as usize);
+        self.offset += len as usize;
+    }
+
     fn align_to(&mut self, align: u8) {
         let amount = self.offset.next_multiple_of(align as usize) - self.offset;
         if amount != 0 {
@@ -190,19 +284,43 @@ impl<'a> DeserializerBuilder<'a> {
 }
 
 impl SerializedCpp {
-    fn align(&self) -> u8 {
+    pub(crate) fn align(&self) -> u8 {
         match self {
             SerializedCpp::Boolean => 1,
             SerializedCpp::Integer { size, .. } => *size,
             SerializedCpp::Float { size, .. } => *size,
-            SerializedCpp::Enum { .. } => 4,
+            SerializedCpp::Enum { size, .. } => *size,
             SerializedCpp::Array { item, .. } => item.align(),
             SerializedCpp::Struct { members } => members.values().map(|ty| ty.align()).max().unwrap_or(1),
-            SerializedCpp::Union { .. } => todo!(),
+            SerializedCpp::Union { variants } => variants.iter().map(|(_, ty)| ty.align()).max().unwrap_or(1),
+            SerializedCpp::Bitfield { .. } => 4,
+        }
+    }
+
+    /// How many bytes a value of this type takes up, laid out the way [`Self::to_fast`] reads it,
+    /// including the padding at the end of a struct.
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            SerializedCpp::Boolean => 1,
+            SerializedCpp::Integer { size, .. } | SerializedCpp::Float { size } | SerializedCpp::Enum { size, .. } => *size as usize,
+            SerializedCpp::Array { item, count } => item.size() * *count as usize,
+            SerializedCpp::Struct { members } => {
+                let size = members.values().fold(0, |offset: usize, ty| offset.next_multiple_of(ty.align() as usize) + ty.size());
+                size.next_multiple_of(self.align() as usize)
+            }
+            SerializedCpp::Union { variants } => {
+                let size = variants.iter().map(|(_, ty)| ty.size()).max().unwrap_or(0);
+                size.next_multiple_of(self.align() as usize)
+            }
+            SerializedCpp::Bitfield { .. } => 4,
         }
     }
 
-    pub fn to_fast(&self, file: &mut DeserializerBuilder, name: &str) -> u8 {
+    /// Adds the reads for a value of this type named `name` to `file`, returning its alignment.
+    /// Padding is counted from the start of the packet body, which holds the packet's struct as it
+    /// was laid out in memory, so it lines up with the C++ layout whatever the size of the header.
+    /// Fails on types that can't be read, such as unions and 2 byte integers.
+    pub fn to_fast(&self, file: &mut DeserializerBuilder, name: &str) -> Result<u8, LogFormatError> {
         let value = match self {
             SerializedCpp::Boolean => {
                 file.read_bool(name);
@@ -216,7 +334,7 @@ impl SerializedCpp {
                     file.read_i32(name);
                     4
                 } else {
-                    panic!("{}", *size);
+                    return Err(unsupported(name, format!("{} byte signed integers can't be read", size)));
                 }
             }
             SerializedCpp::Integer { signed: false, size } => {
@@ -227,7 +345,7 @@ impl SerializedCpp {
                     file.read_u32(name);
                     4
                 } else {
-                    panic!("{}", *size);
+                    return Err(unsupported(name, format!("{} byte unsigned integers can't be read", size)));
                 }
             }
             SerializedCpp::Float { size } => {
@@ -238,10 +356,13 @@ impl SerializedCpp {
                     file.read_f64(name);
                     8
                 } else {
-                    panic!("{}", *size);
+                    return Err(unsupported(name, format!("{} byte floats can't be read", size)));
                 }
             }
-            SerializedCpp::Enum { variants } => {
+            SerializedCpp::Enum { size, .. } if *size != 4 => {
+                return Err(unsupported(name, format!("{} byte enums can't be read", size)));
+            }
+            SerializedCpp::Enum { variants, .. } => {
                 let mut new_variants = HashMap::new();
                 for (name, disc) in variants {
                     new_variants.insert(*disc, name.clone());
@@ -249,10 +370,15 @@ impl SerializedCpp {
                 file.read_enum(name, new_variants);
                 4
             }
+            // The format parser only produces signed 1 byte integers for `char`, so these are strings.
+            SerializedCpp::Array { item, count } if matches!(**item, SerializedCpp::Integer { signed: true, size: 1 }) => {
+                file.read_str(name, *count);
+                1
+            }
             SerializedCpp::Array { item, count } => {
                 let mut align = 1;
                 for i in 0..*count {
-                    align = item.to_fast(file, &format!("{}[{}]", name, i));
+                    align = item.to_fast(file, &format!("{}[{}]", name, i))?;
                     file.align_to(align);
                 }
                 align
@@ -262,7 +388,7 @@ impl SerializedCpp {
                 for (field_name, format) in members {
                     file.align_to(format.align());
 
-                    let align = format.to_fast(file, &format!("{}.{}", name, field_name));
+                    let align = format.to_fast(file, &format!("{}.{}", name, field_name))?;
                     if align > max_align {
                         max_align = align;
                     }
@@ -271,9 +397,20 @@ impl SerializedCpp {
                 max_align
             }
             SerializedCpp::Union { .. } => {
-                todo!()
+                return Err(unsupported(name, "unions can't be read".to_string()));
+            }
+            SerializedCpp::Bitfield { bits } => {
+                if let Some((flag, bit)) = bits.iter().find(|(_, bit)| **bit >= 32) {
+                    return Err(unsupported(&format!("{}.{}", name, flag), format!("bit {} is outside the 32 bit register", bit)));
+                }
+                file.read_bitfield(name, bits);
+                4
             }
         };
-        value
+        Ok(value)
     }
 }
+
+fn unsupported(name: &str, reason: String) -> LogFormatError {
+    LogFormatError::UnsupportedType(format!("{}: {}", name, reason))
+}
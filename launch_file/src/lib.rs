@@ -1,172 +1,1027 @@
+mod crc;
 mod deserialize;
+#[cfg(test)]
+mod test_file;
+#[cfg(all(test, unix))]
+mod test_parser;
+#[cfg(test)]
+mod tests;
 
+use std::cmp::Reverse;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::ffi::OsStr;
 use std::{fs, fs::File};
-use std::{io, io::{Read, Write}};
+use std::{io, io::{BufRead, BufReader, Read, Write}};
 use std::num::NonZeroU32;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use ahash::AHashMap;
 use indexmap::IndexMap;
 use serde::Deserialize;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
 use directories::ProjectDirs;
+use thiserror::Error;
 
-use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+use dataframe::{Data, DataFrame, DataFrameBuilder, DataFrameView, DataType};
 
 use crate::deserialize::{SerializedCpp, Deserializer, DeserializerBuilder};
 
-const MAIN_SRC: &'static [u8] = include_bytes!("../src-py/__main__.py");
-const PARSER_SRC: &'static [u8] = include_bytes!("../src-py/cpp_parser.py");
+pub use crate::crc::Crc;
 
 macro_rules! try_catch {
     ($b:block) => { (|| -> Result<_, _> { $b })() };
 }
 
+const MAIN_SRC: &[u8] = include_bytes!("../src-py/__main__.py");
+const PARSER_SRC: &[u8] = include_bytes!("../src-py/cpp_parser.py");
+
+/// Part of the key of cached schemas, so that schemas written by an older parser aren't used once it
+/// changes what it writes. Bump it along with any such change to the parser scripts.
+const PARSER_VERSION: u32 = 2;
+
+/// While resynchronizing, a candidate packet is only accepted if its timestamp is within this many
+/// milliseconds of the last good packet.
+const RESYNC_MAX_TIMESTAMP_JUMP_MS: u64 = 500;
+
+/// Resynchronizing gives up after this many bytes without finding a packet, and counts the rest of
+/// the file as part of the gap, since that much is more likely not to be packets at all than to be
+/// a long run of corruption.
+const RESYNC_MAX_BYTES: u64 = 4 << 20;
+
+/// How long the Python parser is given to compile a format by default.
+pub const DEFAULT_PYTHON_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often the Python parser is checked on while it runs.
+const PYTHON_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the progress of files read on their own threads is reported.
+const READ_FILES_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Why a format couldn't be loaded.
+#[derive(Debug, Error)]
+pub enum LogFormatError {
+    /// A file couldn't be read or written, with what was being done at the time.
+    #[error("{0}: {1}")]
+    Io(&'static str, #[source] io::Error),
+    #[error("Could not run python: {0}")]
+    PythonSpawn(#[source] io::Error),
+    /// Python ran for longer than the timeout, with what it wrote to stderr.
+    #[error("Python did not finish within {} seconds, so it was stopped.\n{}", .0.as_secs_f32(), .1)]
+    Timeout(Duration, String),
+    #[error("Loading the format was cancelled.")]
+    Cancelled,
+    /// The parser exited with an error, with what it wrote to stderr.
+    #[error("Script Error: {0}")]
+    ScriptFailed(String),
+    #[error("Could not read schema: {0}")]
+    Schema(#[from] serde_json::Error),
+    #[error("{0}")]
+    MalformedHeader(String),
+    #[error("{0}")]
+    MalformedTrailer(String),
+    /// A field of a type that can't be read, such as a union, with the field and why.
+    #[error("{0}")]
+    UnsupportedType(String),
+}
+
+fn preamble_too_long(preamble: u64) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("The {} byte preamble is longer than the file.", preamble))
+}
+
+
+/// A run of bytes that had to be skipped because it did not start a valid packet.
+#[derive(Copy, Clone, Debug)]
+pub struct ResyncEvent {
+    /// Offset in the file of the first skipped byte.
+    pub offset: u64,
+    pub bytes_skipped: u64,
+    /// Timestamp of the last good packet before the gap, or 0 if there was none.
+    pub timestamp: u64,
+    /// The discriminant read where the gap starts, which either isn't one of the format's or came
+    /// with a timestamp too far from the last good packet's.
+    pub discriminant: u32,
+    /// The timestamp read where the gap starts.
+    pub found_timestamp: u64,
+}
+
+/// A packet type of the format, and how much of the file it made up.
+#[derive(Clone, Debug)]
+pub struct VariantSummary {
+    pub name: String,
+    pub discriminant: u32,
+    /// The size of the packet body in bytes, after the header.
+    pub size: usize,
+    /// How many rows were added from packets of this type.
+    pub rows: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReadSummary {
+    /// The checksum of the format the file was written with, from the start of the file.
+    pub checksum: u32,
+    pub resync_events: Vec<ResyncEvent>,
+    /// Each packet type of the format, in the order of the format.
+    pub variants: Vec<VariantSummary>,
+    /// How many packets were left out because the CRC in their trailer didn't match.
+    pub crc_failures: usize,
+    /// How many rows have an empty timestamp because theirs was too large for the column, such as
+    /// milliseconds since the Unix epoch in an 8 byte header.
+    pub large_timestamps: usize,
+}
+
+impl ReadSummary {
+    pub fn bytes_skipped(&self) -> u64 {
+        self.resync_events.iter().map(|event| event.bytes_skipped).sum()
+    }
+}
+
+/// What [`LogFormat::validate_file`] found in a file, without reading its packets into a frame.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// The checksum, gaps and packets of each type, as they would be after reading the file.
+    pub summary: ReadSummary,
+    /// How many bytes at the end of the file start a packet that was cut off, such as when the
+    /// logger lost power mid-write.
+    pub trailing_bytes: u64,
+}
+
+impl ValidationReport {
+    /// How many good packets the file holds.
+    pub fn packets(&self) -> usize {
+        self.summary.variants.iter().map(|variant| variant.rows).sum()
+    }
+}
+
+/// How many bytes at the start of a file [`LogFormat::guess_layouts`] reads.
+pub const GUESS_LAYOUT_BYTES: u64 = 1 << 20;
+
+/// How well a byte order and header layout fit the start of a file, from [`LogFormat::guess_layouts`].
+#[derive(Copy, Clone, Debug)]
+pub struct LayoutGuess {
+    pub header: PacketHeader,
+    pub big_endian: bool,
+    /// Whether the checksum at the start of the file is the format's when read in this byte order.
+    pub checksum_matches: bool,
+    pub packets: usize,
+    pub bytes_skipped: u64,
+}
+
+/// What the memory taken up by reading a format depends on, from [`LogFormat::size`]. Working it out
+/// sets up the columns of every variant, so it is done once rather than for each file.
+#[derive(Copy, Clone, Debug)]
+pub struct FormatSize {
+    /// The size of the smallest packet body, in bytes.
+    pub smallest_body: usize,
+    /// How many columns the fields of every variant are read into.
+    pub columns: usize,
+}
+
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// The Unix time in seconds at which the log started. If given, a `datetime` column is added with
+    /// the Unix time of each packet.
+    pub start_epoch: Option<i32>,
+    /// How many bytes of metadata, such as a flight information block, sit between the checksum and
+    /// the first packet.
+    pub preamble: u64,
+    /// How many packets at the start of the file to leave out.
+    pub skip_rows: usize,
+    /// Stop reading after this many packets have been added.
+    pub max_rows: Option<usize>,
+    /// Adds a `discriminant` column with the raw number that picked each packet's type, which helps
+    /// when debugging malformed logs.
+    pub discriminant: bool,
+    /// What to do with floats that aren't finite.
+    pub non_finite: NonFinite,
+    /// How to store `uint32_t` fields, whose values may not fit in an integer column.
+    pub large_unsigned: LargeUnsigned,
+    /// Reads the checksum, headers and fields as big endian, for logs written by big endian
+    /// processors.
+    pub big_endian: bool,
+}
+
+/// What to do with float fields that are NaN or infinite, such as those read from uninitialized
+/// firmware memory.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Keep them as they are. When sorting, they come after every other number, or before if their
+    /// sign is negative.
+    #[default]
+    Keep,
+    /// Leave the cells empty.
+    Null,
+    /// Replace infinities with the largest or smallest float, and leave NaN empty.
+    Clamp,
+}
+
+impl NonFinite {
+    fn apply(self, num: f32) -> Data<'static> {
+        match self {
+            _ if num.is_finite() => Data::Float(num),
+            NonFinite::Keep => Data::Float(num),
+            NonFinite::Null => Data::Null,
+            NonFinite::Clamp if num.is_nan() => Data::Null,
+            NonFinite::Clamp => Data::Float(if num > 0.0 { f32::MAX } else { f32::MIN })
+        }
+    }
+}
+
+/// How `uint32_t` fields are stored, since integer columns only hold values up to `i32::MAX`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LargeUnsigned {
+    /// Keep them in an integer column, replacing values above `i32::MAX` with `i32::MAX`. This keeps
+    /// counters that stay small exact, while larger ones stop at the limit rather than turning negative.
+    #[default]
+    Saturate,
+    /// Store them in a float column, which keeps the size of every value but rounds those above 2^24.
+    Float,
+    /// Store them in an unsigned column, which keeps every value exactly except `u32::MAX`. Unsigned
+    /// columns can't hold that, so it is left empty, as it often means the field wasn't set.
+    Unsigned,
+}
+
+impl LargeUnsigned {
+    fn data_type(self) -> DataType {
+        match self {
+            LargeUnsigned::Saturate => DataType::Integer,
+            LargeUnsigned::Float => DataType::Float,
+            LargeUnsigned::Unsigned => DataType::Unsigned,
+        }
+    }
+
+    fn apply(self, num: u32) -> Data<'static> {
+        match self {
+            LargeUnsigned::Saturate => Data::Integer(num.min(i32::MAX as u32) as i32),
+            LargeUnsigned::Float => Data::Float(num as f32),
+            // Stored as is, `u32::MAX` would read back as empty anyway, so it is left empty on purpose.
+            LargeUnsigned::Unsigned if num == u32::MAX => Data::Null,
+            LargeUnsigned::Unsigned => Data::Unsigned(num),
+        }
+    }
+}
+
+
+/// The layout of the discriminant and timestamp that start each packet.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PacketHeader {
+    /// The size of the discriminant in bytes, either 2 or 4.
+    pub discriminant: u8,
+    /// The size of the unsigned timestamp in bytes, either 4 or 8.
+    pub timestamp: u8
+}
+
+impl Default for PacketHeader {
+    fn default() -> Self {
+        PacketHeader { discriminant: 4, timestamp: 4 }
+    }
+}
+
+impl PacketHeader {
+    /// The largest header, which fits any layout.
+    const MAX_SIZE: usize = 12;
+
+    pub fn size(&self) -> usize {
+        self.discriminant as usize + self.timestamp as usize
+    }
+
+    fn validate(&self) -> Result<(), LogFormatError> {
+        if !matches!(self.discriminant, 2 | 4) {
+            return Err(LogFormatError::MalformedHeader(format!("Unsupported discriminant size {} in packet header", self.discriminant)));
+        }
+        if !matches!(self.timestamp, 4 | 8) {
+            return Err(LogFormatError::MalformedHeader(format!("Unsupported timestamp size {} in packet header", self.timestamp)));
+        }
+        Ok(())
+    }
+
+    /// Splits a header into its discriminant and timestamp.
+    fn parse<B: ByteOrder>(&self, header: &[u8]) -> (u32, u64) {
+        let (mut discriminant, mut timestamp) = header.split_at(self.discriminant as usize);
+        let discriminant = discriminant.read_uint::<B>(self.discriminant as usize).unwrap() as u32;
+        let timestamp = timestamp.read_uint::<B>(self.timestamp as usize).unwrap();
+        (discriminant, timestamp)
+    }
+}
+
+/// Bytes that follow the body of each packet, such as a CRC, in schemas of firmware that writes one.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PacketTrailer {
+    /// The size of the trailer in bytes.
+    pub size: u8,
+    /// The CRC of the header and body that starts the trailer, if any, in the byte order of the file.
+    #[serde(default)]
+    pub crc: Option<Crc>,
+}
+
+impl PacketTrailer {
+    /// The largest trailer, which fits any CRC along with padding.
+    const MAX_SIZE: usize = 16;
+
+    fn validate(&self) -> Result<(), LogFormatError> {
+        if self.size as usize > Self::MAX_SIZE {
+            return Err(LogFormatError::MalformedTrailer(format!("Packet trailer of {} bytes is longer than the most supported, {}", self.size, Self::MAX_SIZE)));
+        }
+        if let Some(crc) = self.crc.filter(|crc| crc.size() > self.size as usize) {
+            return Err(LogFormatError::MalformedTrailer(format!("Packet trailer of {} bytes can't hold a {} byte CRC", self.size, crc.size())));
+        }
+        Ok(())
+    }
+
+    /// Whether the CRC in `trailer` matches the header and body it follows. Trailers without a CRC
+    /// always match.
+    fn matches(&self, header: &[u8], body: &[u8], trailer: &[u8], big_endian: bool) -> bool {
+        let Some(crc) = self.crc else {
+            return true;
+        };
+        let expected = &trailer[..crc.size()];
+        let expected = if big_endian { BigEndian::read_uint(expected, crc.size()) } else { LittleEndian::read_uint(expected, crc.size()) };
+        crc.checksum(&[header, body]) as u64 == expected
+    }
+}
+
 
 #[derive(Deserialize, Clone)]
 pub struct LogFormat {
     #[serde(rename = "<checksum>")]
     pub checksum: u32,
+    /// Schemas from before the header layout was configurable don't have one, and use the default.
+    #[serde(rename = "<header>", default)]
+    pub header: PacketHeader,
+    /// What follows the body of each packet. Most firmware writes nothing there.
+    #[serde(rename = "<trailer>", default)]
+    pub trailer: Option<PacketTrailer>,
     #[serde(flatten)]
     pub variants: IndexMap<String, (u32, SerializedCpp)>,
 }
 
 impl LogFormat {
+    /// Removes the extracted parser scripts, so they are rewritten on the next load, along with every
+    /// cached schema.
     pub fn clear_scripts() {
-        let script_dir = ProjectDirs::from("", "", "MIDAS Launch").unwrap();
+        let script_dir = ProjectDirs::from("", "", "MIDAS-Launch").unwrap();
         fs::create_dir_all(script_dir.data_dir()).unwrap();
         let _ = fs::remove_file(script_dir.data_dir().join("__main__.py"));
         let _ = fs::remove_file(script_dir.data_dir().join("cpp_parser.py"));
+        let _ = fs::remove_dir_all(script_dir.cache_dir().join("schemas"));
     }
 
-    pub fn from_file(format_file_name: &Path, python: impl AsRef<OsStr>) -> Result<Self, String> {
+    /// Compiles a format from a C++ header with the Python parser. The schema is cached by the path and
+    /// contents of the header, so loading an unchanged header again doesn't run Python. Headers it
+    /// includes aren't part of the key, so the cache has to be cleared after changing those.
+    ///
+    /// Python is stopped if it runs for longer than `timeout`. While it runs, `on_poll` is called
+    /// regularly with everything it has written to stderr so far, and stops it by returning `false`.
+    pub fn from_file(format_file_name: &Path, python: impl AsRef<OsStr>, timeout: Duration, on_poll: impl FnMut(&str) -> bool) -> Result<Self, LogFormatError> {
         let script_dir = ProjectDirs::from("", "", "MIDAS-Launch")
-            .ok_or("Could not find script.".to_string())?;
+            .ok_or(LogFormatError::Io("Could not find script", io::ErrorKind::NotFound.into()))?;
+        Self::from_file_in(script_dir.data_dir(), script_dir.cache_dir(), format_file_name, python, timeout, on_poll)
+    }
 
-        fs::create_dir_all(script_dir.data_dir()).map_err(|e| format!("Could not create script: {}", e))?;
-        fs::create_dir_all(script_dir.cache_dir()).map_err(|e| format!("Could not create script: {}", e))?;
-        let main_path = script_dir.data_dir().join("__main__.py");
-        let parser_path = script_dir.data_dir().join("cpp_parser.py");
+    /// [`from_file`](Self::from_file), with the parser scripts in `data_dir` and the cached schemas
+    /// in `cache_dir`.
+    fn from_file_in(data_dir: &Path, cache_dir: &Path, format_file_name: &Path, python: impl AsRef<OsStr>, timeout: Duration, on_poll: impl FnMut(&str) -> bool) -> Result<Self, LogFormatError> {
+        fs::create_dir_all(data_dir).map_err(|e| LogFormatError::Io("Could not create script", e))?;
+        fs::create_dir_all(cache_dir.join("schemas")).map_err(|e| LogFormatError::Io("Could not create script", e))?;
+        let main_path = data_dir.join("__main__.py");
+        let parser_path = data_dir.join("cpp_parser.py");
         let main_res = File::create_new(&main_path);
         match main_res {
             Ok(mut file) => {
-                file.write_all(MAIN_SRC).map_err(|e| format!("Could not create script: {}", e))?;
+                file.write_all(MAIN_SRC).map_err(|e| LogFormatError::Io("Could not create script", e))?;
             }
-            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
-                ()
-            }
-            Err(e) => { return Err(format!("Could not find script: {}", e)); }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => { }
+            Err(e) => { return Err(LogFormatError::Io("Could not find script", e)); }
         }
 
         let parser_res = File::create_new(&parser_path);
         match parser_res {
             Ok(mut file) => {
-                file.write_all(PARSER_SRC).map_err(|e| format!("Could not create script: {}", e))?;
-            }
-            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
-                ()
+                file.write_all(PARSER_SRC).map_err(|e| LogFormatError::Io("Could not create script", e))?;
             }
-            Err(e) => { return Err(format!("Could not find script: {}", e)); }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => { }
+            Err(e) => { return Err(LogFormatError::Io("Could not find script", e)); }
         }
 
-        let schema_path = script_dir.cache_dir().join("schema.json");
+        let header = fs::read(format_file_name).map_err(|e| LogFormatError::Io("Could not read format", e))?;
+        // The key has to stay the same across builds of the program, so it is made of CRCs rather
+        // than std's hashes, which may change between Rust releases.
+        let path_crc = Crc::Crc32.checksum(&[format_file_name.as_os_str().as_encoded_bytes()]);
+        let header_crc = Crc::Crc32.checksum(&[&PARSER_VERSION.to_le_bytes(), &header]);
+        let schema_path = cache_dir.join("schemas").join(format!("schema-{:08x}{:08x}.json", path_crc, header_crc));
+        if schema_path.exists() {
+            return Self::from_schema_json(&schema_path);
+        }
 
         let mut command = Command::new(python);
-
         command
             .arg(&main_path)
             .arg("-S")
             .arg("--format")
-            .arg(&format_file_name)
+            .arg(format_file_name)
             .arg("--out")
             .arg(&schema_path);
-        let output = command
-            .output()
-            .map_err(|e| format!("Could not run python: {}", e))?;
 
-        if !output.status.success() {
-            return Err(format!("Script Error: {}", String::from_utf8_lossy(&output.stderr)));
+        if let Err(e) = run_parser(command, timeout, on_poll) {
+            // A stopped or failed parser may have left a partial schema, which would otherwise be cached.
+            let _ = fs::remove_file(&schema_path);
+            return Err(e);
         }
 
-        let format = fs::read_to_string(&schema_path).map_err(|e| format!("Could not read schema {}", e))?;
-        let format = serde_json::from_str::<LogFormat>(&format).map_err(|e| format!("Could not read schema {}", e))?;
+        Self::from_schema_json(&schema_path)
+    }
+
+    /// Reads a format from a `schema.json` previously produced by the Python parser, without needing
+    /// Python.
+    pub fn from_schema_json(schema_path: &Path) -> Result<Self, LogFormatError> {
+        let schema = fs::read_to_string(schema_path).map_err(|e| LogFormatError::Io("Could not read schema", e))?;
+        Self::from_schema(&schema)
+    }
+
+    /// Reads a format from the contents of a `schema.json`. Fails if the header or trailer are
+    /// malformed, or if a variant has a field of a type that can't be read, so that a format that
+    /// loads can always read files.
+    pub fn from_schema(schema: &str) -> Result<Self, LogFormatError> {
+        let format = serde_json::from_str::<LogFormat>(schema)?;
+        format.header.validate()?;
+        if let Some(trailer) = &format.trailer {
+            trailer.validate()?;
+        }
+        format.size()?;
 
         Ok(format)
     }
 
-    pub fn read_file(&self, file: &mut impl Read, file_size: Option<u64>, mut on_row_callback: impl FnMut(u64)) -> io::Result<DataFrameView> {
+    /// A Markdown description of the packets of this format, for documenting it: the header and
+    /// trailer, then a table for each variant with the offset, size and type of each of its fields.
+    pub fn report(&self) -> Result<String, LogFormatError> {
+        let mut report = format!("# Launch Format 0x{:0>8x}\n\n", self.checksum);
+        report += &format!(
+            "Each packet starts with a {} byte discriminant and a {} byte timestamp, followed by the body of its variant",
+            self.header.discriminant, self.header.timestamp
+        );
+        report += &match self.trailer {
+            Some(PacketTrailer { size, crc: Some(crc) }) => format!(" and a {} byte trailer holding a {} of the header and body.\n", size, crc.name()),
+            Some(PacketTrailer { size, crc: None }) => format!(" and a {} byte trailer.\n", size),
+            None => ".\n".to_string()
+        };
+
+        let mut dataframe_builder = DataFrameBuilder::new();
+        for (name, (discriminant, format)) in &self.variants {
+            let mut builder = DeserializerBuilder::new(&mut dataframe_builder, LargeUnsigned::default());
+            format.to_fast(&mut builder, name)?;
+            let deserializer = builder.finish();
+
+            report += &format!("\n## {}\n\n", name);
+            report += &format!("Discriminant {}, with a body of {} bytes. Offsets are from the start of the body.\n\n", discriminant, deserializer.size);
+            report += "| Offset | Size | Field | Type |\n| ---: | ---: | --- | --- |\n";
+            let mut end = 0;
+            for field in &deserializer.fields {
+                if field.offset > end {
+                    report += &format!("| {} | {} | | padding |\n", end, field.offset - end);
+                }
+                report += &format!("| {} | {} | {} | {} |\n", field.offset, field.size, field.name, field.ty);
+                end = field.offset + field.size;
+            }
+            if deserializer.size > end {
+                report += &format!("| {} | {} | | padding |\n", end, deserializer.size - end);
+            }
+        }
+        Ok(report)
+    }
+
+    /// A log of reading `source` with this format, for finding out what went wrong with a corrupted
+    /// file. Each line is a record type followed by `key=value` pairs: the checksums, each variant,
+    /// each gap skipped while resynchronizing along with what was found where it starts, the CRC
+    /// failures, a summary, and if reading stopped early, the error and the offset it stopped at.
+    pub fn parse_log(&self, source: &str, summary: &ReadSummary, error: Option<(&io::Error, u64)>) -> String {
+        let mut log = format!("source path={:?}\n", source);
+        log += &format!(
+            "checksum file=0x{:0>8x} format=0x{:0>8x} matches={}\n",
+            summary.checksum, self.checksum, summary.checksum == self.checksum
+        );
+        for variant in &summary.variants {
+            log += &format!("variant name={:?} discriminant={} size={} rows={}\n", variant.name, variant.discriminant, variant.size, variant.rows);
+        }
+        for event in &summary.resync_events {
+            let known = self.variants.values().any(|(discriminant, _)| *discriminant == event.discriminant);
+            let reason = if known { "timestamp jump" } else { "unknown discriminant" };
+            log += &format!(
+                "resync offset={} bytes_skipped={} last_good_timestamp={} found_discriminant={} found_timestamp={} reason={:?}\n",
+                event.offset, event.bytes_skipped, event.timestamp, event.discriminant, event.found_timestamp, reason
+            );
+        }
+        log += &format!("crc_failures count={}\n", summary.crc_failures);
+        log += &format!(
+            "summary packets={} gaps={} bytes_skipped={}\n",
+            summary.variants.iter().map(|variant| variant.rows).sum::<usize>(), summary.resync_events.len(), summary.bytes_skipped()
+        );
+        if let Some((error, offset)) = error {
+            log += &format!("error offset={} message={:?}\n", offset, error.to_string());
+        }
+        log
+    }
+
+    /// The smallest packet body of this format and how many columns its fields are read into.
+    pub fn size(&self) -> Result<FormatSize, LogFormatError> {
+        let mut dataframe_builder = DataFrameBuilder::new();
+        let mut smallest_body = usize::MAX;
+        for (name, (_, format)) in &self.variants {
+            let mut builder = DeserializerBuilder::new(&mut dataframe_builder, LargeUnsigned::default());
+            format.to_fast(&mut builder, name)?;
+            smallest_body = smallest_body.min(builder.finish().size).max(1);
+        }
+        Ok(FormatSize { smallest_body, columns: dataframe_builder.build().shape().cols })
+    }
+
+    /// An upper bound on the bytes a file of `file_size` bytes takes up once read with `options`, which
+    /// assumes every packet is of the smallest type. `size` is the [`Self::size`] of this format.
+    pub fn estimate_memory_bytes(&self, size: FormatSize, file_size: u64, options: ReadOptions) -> u64 {
+        let cols = size.columns + 2 + options.start_epoch.is_some() as usize + options.discriminant as usize;
+
+        let packets = file_size.saturating_sub(4 + options.preamble) / size.smallest_body.saturating_add(self.packet_overhead()) as u64;
+        let mut rows = packets.saturating_sub(options.skip_rows as u64);
+        if let Some(max_rows) = options.max_rows {
+            rows = rows.min(max_rows as u64);
+        }
+        // Each row is a cell per column, plus its place in the row order.
+        rows * (cols * std::mem::size_of::<u32>() + std::mem::size_of::<usize>()) as u64
+    }
+
+    /// The bytes of each packet besides its body.
+    fn packet_overhead(&self) -> usize {
+        self.header.size() + self.trailer.map_or(0, |trailer| trailer.size as usize)
+    }
+
+    /// Reads the packets of a launch file from `file`. Resynchronizing slides over the header in
+    /// memory rather than seeking back, so `file` may be any stream, such as stdin or a gzip decoder.
+    /// `file_size` is used to reserve rows up front and to check that the preamble fits.
+    pub fn read_file(&self, file: &mut impl Read, file_size: Option<u64>, options: ReadOptions, on_row_callback: impl FnMut(u64)) -> io::Result<(DataFrameView, ReadSummary)> {
+        if file_size.is_some_and(|file_size| 4 + options.preamble > file_size) {
+            return Err(preamble_too_long(options.preamble));
+        }
+
+        let mut reader = self.reader(file_size, options, |_, _| {}).map_err(io::Error::other)?;
+        reader.read(file, on_row_callback)?;
+        reader.finish()
+    }
+
+    /// Reads each of `files`, given with its size, on a thread of its own, and returns what
+    /// [`Self::read_file`] does for each, in the order of `files`. `on_progress_callback` is called
+    /// regularly on this thread with how many bytes have been read across all of them.
+    pub fn read_files<R: Read + Send>(&self, files: Vec<(R, Option<u64>)>, options: ReadOptions, mut on_progress_callback: impl FnMut(u64)) -> Vec<io::Result<(DataFrameView, ReadSummary)>> {
+        let bytes_read = AtomicU64::new(0);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = files.into_iter().map(|(mut file, file_size)| {
+                let bytes_read = &bytes_read;
+                scope.spawn(move || {
+                    let mut last_offset = 0;
+                    self.read_file(&mut file, file_size, options, |offset| {
+                        bytes_read.fetch_add(offset.saturating_sub(last_offset), Ordering::Relaxed);
+                        last_offset = last_offset.max(offset);
+                    })
+                })
+            }).collect();
+
+            while !workers.iter().all(|worker| worker.is_finished()) {
+                on_progress_callback(bytes_read.load(Ordering::Relaxed));
+                std::thread::sleep(READ_FILES_POLL_INTERVAL);
+            }
+            on_progress_callback(bytes_read.load(Ordering::Relaxed));
+
+            workers.into_iter().map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+        })
+    }
+
+    /// Walks the packets of a launch file the same way [`Self::read_file`] does, but only counts them
+    /// rather than building a frame, so that a file can be checked before spending the memory to load
+    /// it. Only the preamble and byte order of `options` are used.
+    pub fn validate_file(&self, file: &mut impl Read, options: ReadOptions, on_row_callback: impl FnMut(u64)) -> io::Result<ValidationReport> {
+        self.validate_with_header(self.header, file, options, on_row_callback)
+    }
+
+    /// Tries each byte order and header layout on the first [`GUESS_LAYOUT_BYTES`] of a file, such as
+    /// one from an unfamiliar logger, and returns how each went, best first. The best layout is one
+    /// that reads the checksum as the format's, then that finds the most packets, then that skips the
+    /// fewest bytes.
+    ///
+    /// This is only a guess. Packets are recognized by their discriminant and a plausible timestamp,
+    /// so a wrong layout can still find some by chance, most of all in formats with many types or
+    /// small discriminants. If the file holds only a few packets, or the format itself is wrong, no
+    /// layout stands out, and the best may be no better than the rest.
+    pub fn guess_layouts(&self, file: &mut impl Read, preamble: u64) -> io::Result<Vec<LayoutGuess>> {
+        let mut start = vec![];
+        file.take(GUESS_LAYOUT_BYTES).read_to_end(&mut start)?;
+
+        let mut guesses = vec![];
+        for big_endian in [false, true] {
+            for (discriminant, timestamp) in [(4, 4), (2, 4), (4, 8), (2, 8)] {
+                let header = PacketHeader { discriminant, timestamp };
+                let options = ReadOptions { preamble, big_endian, ..ReadOptions::default() };
+                let report = self.validate_with_header(header, &mut &start[..], options, |_| {})?;
+                guesses.push(LayoutGuess {
+                    header,
+                    big_endian,
+                    checksum_matches: report.summary.checksum == self.checksum,
+                    packets: report.packets(),
+                    bytes_skipped: report.summary.bytes_skipped()
+                });
+            }
+        }
+        // The sort is stable, so the layout of the format wins ties between the most likely layouts.
+        guesses.sort_by_key(|guess| (!guess.checksum_matches, Reverse(guess.packets), guess.bytes_skipped, guess.header != self.header));
+        Ok(guesses)
+    }
+
+    fn validate_with_header(&self, header: PacketHeader, file: &mut impl Read, options: ReadOptions, on_row_callback: impl FnMut(u64)) -> io::Result<ValidationReport> {
+        let options = ReadOptions { preamble: options.preamble, big_endian: options.big_endian, ..ReadOptions::default() };
+        let mut reader = self.new_reader(None, options, true, |_, _| {}).map_err(io::Error::other)?;
+        reader.header = header;
+        // Counts every byte taken from the file, including those of a packet cut off part way.
+        let mut file = file.take(u64::MAX);
+        reader.read(&mut file, on_row_callback)?;
+        let read_to = u64::MAX - file.limit();
+
+        if !reader.started {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The file is too short to hold a checksum."));
+        }
+        let mut trailing_bytes = 0;
+        match reader.gap.take() {
+            Some(mut event) => {
+                event.bytes_skipped = read_to - event.offset;
+                reader.summary.resync_events.push(event);
+            }
+            None => trailing_bytes = read_to - reader.offset
+        }
+        Ok(ValidationReport { summary: reader.summary, trailing_bytes })
+    }
+
+    /// A reader for the packets of a launch file, which can carry on reading as the file grows.
+    /// `file_size` is used to reserve rows up front. Setting up the columns of a format with many
+    /// variants takes a while, so `on_variant_callback` is called with how many variants are set up
+    /// after each one, along with how many there are.
+    pub fn reader(&self, file_size: Option<u64>, options: ReadOptions, on_variant_callback: impl FnMut(usize, usize)) -> Result<PacketReader, LogFormatError> {
+        self.new_reader(file_size, options, false, on_variant_callback)
+    }
+
+    /// A reader as from [`Self::reader`], or if `dry_run`, one that only counts packets. Counting
+    /// only needs the size of each packet, so no columns are set up for their fields.
+    fn new_reader(&self, file_size: Option<u64>, options: ReadOptions, dry_run: bool, mut on_variant_callback: impl FnMut(usize, usize)) -> Result<PacketReader, LogFormatError> {
         let mut dataframe_builder = DataFrameBuilder::new();
         dataframe_builder.add_column("sensor", DataType::Intern);
-        dataframe_builder.add_column("timestamp", DataType::Integer);
+        dataframe_builder.add_column("timestamp", DataType::Unsigned);
+        let datetime_col = options.start_epoch.map(|_| dataframe_builder.add_column("datetime", DataType::Integer));
+        let discriminant_col = options.discriminant.then(|| dataframe_builder.add_column("discriminant", DataType::Unsigned));
 
-        let mut variants: AHashMap<u32, (NonZeroU32, Deserializer)> = AHashMap::new();
+        let mut summary = ReadSummary::default();
+        let mut variants: AHashMap<u32, (NonZeroU32, Deserializer, usize)> = AHashMap::new();
         let mut smallest = usize::MAX;
         let mut largest = usize::MIN;
+        // Each variant name is interned for the sensor column.
+        dataframe_builder.reserve_strings(self.variants.len());
         for (name, (disc, format)) in &self.variants {
-            let mut builder = DeserializerBuilder::new(&mut dataframe_builder);
-            format.to_fast(&mut builder, name);
-            let fast_format = builder.finish();
+            let fast_format = if dry_run {
+                Deserializer::skip(format.size())
+            } else {
+                let mut builder = DeserializerBuilder::new(&mut dataframe_builder, options.large_unsigned);
+                format.to_fast(&mut builder, name)?;
+                builder.finish()
+            };
             smallest = smallest.min(fast_format.size).max(1);
             largest = largest.max(fast_format.size);
 
+            summary.variants.push(VariantSummary { name: name.clone(), discriminant: *disc, size: fast_format.size, rows: 0 });
             let key = dataframe_builder.add_interned_string(name);
-            variants.insert(*disc, (key, fast_format));
+            variants.insert(*disc, (key, fast_format, summary.variants.len() - 1));
+            on_variant_callback(summary.variants.len(), self.variants.len());
         }
-        let mut dataframe;
+        let dataframe;
         let mut row_numbers = Vec::new();
         if let Some(file_size) = file_size {
-            let rows = (file_size / (smallest as u64 + 8)) as usize;
+            let mut rows = (file_size / (smallest + self.packet_overhead()) as u64) as usize;
+            if let Some(max_rows) = options.max_rows {
+                rows = rows.min(max_rows);
+            }
             dataframe = dataframe_builder.build_with_capacity(rows);
             row_numbers.reserve(rows);
         } else {
             dataframe = dataframe_builder.build();
         }
 
-        let mut offset: u64 = 0;
-        let mut i = 0;
+        Ok(PacketReader {
+            header: self.header,
+            trailer: self.trailer,
+            options,
+            variants,
+            largest,
+            datetime_col,
+            discriminant_col,
+            dataframe,
+            row_numbers,
+            taken: 0,
+            summary,
+            gap: None,
+            last_timestamp: None,
+            packets: 0,
+            offset: 0,
+            read_to: 0,
+            started: false,
+            gave_up: false,
+            dry_run
+        })
+    }
+}
+
+/// Runs the format parser, stopping it if it runs for longer than `timeout` or `on_poll` returns
+/// `false`. Its output is thrown away and stderr is read on a thread of its own, so the parser
+/// never blocks on a full pipe however much it writes.
+fn run_parser(mut command: Command, timeout: Duration, mut on_poll: impl FnMut(&str) -> bool) -> Result<(), LogFormatError> {
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(LogFormatError::PythonSpawn)?;
+
+    let (line_sender, lines) = mpsc::channel();
+    let stderr = child.stderr.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let start = Instant::now();
+    let mut stderr = String::new();
+    let result = loop {
+        while let Ok(line) = lines.try_recv() {
+            stderr.push_str(&line);
+            stderr.push('\n');
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) => { }
+            Err(e) => break Err(LogFormatError::PythonSpawn(e))
+        }
+
+        if start.elapsed() > timeout {
+            break Err(LogFormatError::Timeout(timeout, std::mem::take(&mut stderr)));
+        }
+        if !on_poll(&stderr) {
+            break Err(LogFormatError::Cancelled);
+        }
+        std::thread::sleep(PYTHON_POLL_INTERVAL);
+    };
+
+    let status = match result {
+        Ok(status) => status,
+        Err(e) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+
+    // The rest of stderr arrives once the reading thread sees the pipe close.
+    stderr.extend(lines.iter().map(|line| line + "\n"));
+    if !status.success() {
+        return Err(LogFormatError::ScriptFailed(stderr));
+    }
+    Ok(())
+}
 
-        let _checksum = file.read_u32::<LittleEndian>()?; offset += 4;
+/// `data` with a `file number` column holding `file_number` and a `source file` column holding `name`
+/// on every row, to tell apart the rows of files loaded one after another. The number is cheaper to
+/// filter and sort by, while the name says which file it is.
+pub fn with_source_columns(data: &DataFrameView, file_number: usize, name: &str) -> DataFrameView {
+    let rows = data.shape().rows;
+    data.with_columns(vec![
+        ("file number".to_string(), DataType::Integer, std::iter::repeat_n(Data::Integer(file_number as i32), rows)),
+        ("source file".to_string(), DataType::Intern, std::iter::repeat_n(Data::Str(name), rows)),
+    ])
+}
+
+
+/// Reads packets into a frame, keeping its place between reads so that packets appended to the file
+/// later can be read too.
+pub struct PacketReader {
+    header: PacketHeader,
+    trailer: Option<PacketTrailer>,
+    options: ReadOptions,
+    /// The interned name, deserializer and index in the summary of each variant by discriminant.
+    variants: AHashMap<u32, (NonZeroU32, Deserializer, usize)>,
+    /// The size of the largest packet body.
+    largest: usize,
+    datetime_col: Option<usize>,
+    discriminant_col: Option<usize>,
+
+    dataframe: DataFrame,
+    row_numbers: Vec<usize>,
+    /// How many rows were handed over by [`Self::take_rows`], and so are no longer in `dataframe`.
+    taken: usize,
+    summary: ReadSummary,
+    /// The bytes being skipped while resynchronizing, until a packet is found.
+    gap: Option<ResyncEvent>,
+    last_timestamp: Option<u64>,
+    packets: usize,
+    /// Where in the file the next packet, or the bytes being searched for one, starts.
+    offset: u64,
+    /// How much of the file has been read.
+    read_to: u64,
+    /// Whether the checksum and preamble have been read.
+    started: bool,
+    /// Whether resynchronizing went on for [`RESYNC_MAX_BYTES`], so the rest of the file is skipped.
+    gave_up: bool,
+    /// Only count packets rather than adding their rows, for [`LogFormat::validate_file`].
+    dry_run: bool,
+}
 
+impl PacketReader {
+    /// Reads packets from `file` until it ends, which must continue from [`Self::offset`]. A packet
+    /// cut off by the end of the file is left for the next read, once the rest has been written.
+    pub fn read(&mut self, file: &mut impl Read, mut on_row_callback: impl FnMut(u64)) -> io::Result<()> {
         let result: io::Result<()> = try_catch!({
-            let mut read_buf = vec![0u8; largest].into_boxed_slice();
+            if !self.started {
+                let checksum = if self.options.big_endian { file.read_u32::<BigEndian>()? } else { file.read_u32::<LittleEndian>()? };
+                let skipped = io::copy(&mut file.by_ref().take(self.options.preamble), &mut io::sink())?;
+                if skipped < self.options.preamble {
+                    return Err(preamble_too_long(self.options.preamble));
+                }
+                self.summary.checksum = checksum;
+                self.offset = 4 + self.options.preamble;
+                self.read_to = self.offset;
+                self.started = true;
+            }
+            if self.options.max_rows.is_some_and(|max_rows| self.rows() >= max_rows) {
+                return Ok(());
+            }
+            if self.gave_up {
+                return self.skip_rest(file, &mut on_row_callback);
+            }
+
+            let mut read_buf = vec![0u8; self.largest].into_boxed_slice();
+            // The discriminant and timestamp of the packet starting at `self.offset`.
+            let header_size = self.header.size();
+            let mut header_buf = [0u8; PacketHeader::MAX_SIZE];
+            let header = &mut header_buf[..header_size];
+            let header_len = header_size as u64;
+            let mut trailer_buf = [0u8; PacketTrailer::MAX_SIZE];
+            file.read_exact(header)?; self.read_to = self.offset + header_len;
             loop {
-                let row_idx = dataframe.add_null_row();
-                let mut row = dataframe.row_mut(row_idx);
+                let (determinant, timestamp_ms) = if self.options.big_endian {
+                    self.header.parse::<BigEndian>(header)
+                } else {
+                    self.header.parse::<LittleEndian>(header)
+                };
 
-                let determinant = file.read_u32::<LittleEndian>()?; offset += 4;
-                let timestamp_ms = file.read_u32::<LittleEndian>()?; offset += 4;
+                let plausible = match (&self.gap, self.last_timestamp) {
+                    (Some(_), Some(last)) => last.abs_diff(timestamp_ms) <= RESYNC_MAX_TIMESTAMP_JUMP_MS,
+                    _ => true
+                };
+                let Some((key, fast_format, variant_idx)) = self.variants.get(&determinant).filter(|_| plausible) else {
+                    // Not a packet, so slide forward a byte and try again.
+                    let gap = self.gap.get_or_insert(ResyncEvent {
+                        offset: self.offset,
+                        bytes_skipped: 0,
+                        timestamp: self.last_timestamp.unwrap_or(0),
+                        discriminant: determinant,
+                        found_timestamp: timestamp_ms
+                    });
+                    if self.read_to - gap.offset >= RESYNC_MAX_BYTES {
+                        self.gave_up = true;
+                        return self.skip_rest(file, &mut on_row_callback);
+                    }
+                    header.copy_within(1.., 0);
+                    self.offset += 1;
+                    file.read_exact(&mut header[header_size - 1..])?; self.read_to += 1;
+                    continue;
+                };
 
-                let (key, fast_format) = variants.get(&determinant)
-                    .ok_or_else(|| io::Error::other(format!("No variant for discriminant {} at offset {}", determinant, offset - 8)))?;
+                file.read_exact(&mut read_buf[..fast_format.size])?;
+                self.read_to += fast_format.size as u64;
+                let trailer = &mut trailer_buf[..self.trailer.map_or(0, |trailer| trailer.size as usize)];
+                file.read_exact(trailer)?;
+                self.read_to += trailer.len() as u64;
+                if let Some(mut event) = self.gap.take() {
+                    event.bytes_skipped = self.offset - event.offset;
+                    self.summary.resync_events.push(event);
+                }
+                self.offset = self.read_to;
 
-                row.set_col_raw(0, Some(*key));
-                // row.set_col_with_ty(0, DataType::Intern, Data::Str(name));
-                row.set_col_with_ty(1, DataType::Integer, Data::Integer(timestamp_ms as i32));
+                // A packet with a bad CRC is left out, but its length is still trusted, since its
+                // discriminant was valid.
+                let body = &read_buf[..fast_format.size];
+                if self.trailer.is_some_and(|format| !format.matches(header, body, trailer, self.options.big_endian)) {
+                    self.summary.crc_failures += 1;
+                    on_row_callback(self.offset);
+                    file.read_exact(header)?; self.read_to = self.offset + header_len;
+                    continue;
+                }
+                self.last_timestamp = Some(timestamp_ms);
 
-                file.read_exact(&mut read_buf[..fast_format.size])?;
+                self.packets += 1;
+                if self.packets > self.options.skip_rows && self.dry_run {
+                    self.summary.variants[*variant_idx].rows += 1;
+                } else if self.packets > self.options.skip_rows {
+                    let row_idx = self.dataframe.add_null_row();
+                    let mut row = self.dataframe.row_mut(row_idx);
+                    row.set_col_raw(0, Some(*key));
+                    // The column only holds timestamps below `u32::MAX`, so larger ones are left empty.
+                    let timestamp = u32::try_from(timestamp_ms).ok().filter(|&timestamp| timestamp != u32::MAX);
+                    if timestamp.is_none() {
+                        self.summary.large_timestamps += 1;
+                    }
+                    row.set_col_with_ty(1, DataType::Unsigned, timestamp.map_or(Data::Null, Data::Unsigned));
+                    if let (Some(col_idx), Some(start_epoch)) = (self.datetime_col, self.options.start_epoch) {
+                        let datetime = i32::try_from(timestamp_ms / 1000).ok().and_then(|seconds| start_epoch.checked_add(seconds));
+                        row.set_col_with_ty(col_idx, DataType::Integer, datetime.map_or(Data::Null, Data::Integer));
+                    }
+                    if let Some(col_idx) = self.discriminant_col {
+                        row.set_col_with_ty(col_idx, DataType::Unsigned, Data::Unsigned(determinant));
+                    }
+                    if self.options.big_endian {
+                        fast_format.parse::<BigEndian>(body, &mut row, self.options.non_finite);
+                    } else {
+                        fast_format.parse::<LittleEndian>(body, &mut row, self.options.non_finite);
+                    }
+                    self.row_numbers.push(row_idx);
+                    self.summary.variants[*variant_idx].rows += 1;
 
-                fast_format.parse(&read_buf[..fast_format.size], &mut row);
-                row_numbers.push(i);
-                offset += fast_format.size as u64;
-                i += 1;
+                    if self.options.max_rows.is_some_and(|max_rows| self.rows() >= max_rows) {
+                        on_row_callback(self.offset);
+                        return Ok(());
+                    }
+                }
 
-                on_row_callback(offset);
+                on_row_callback(self.offset);
+
+                file.read_exact(header)?; self.read_to = self.offset + header_len;
             }
         });
-        let result = result.unwrap_err();
 
+        match result {
+            Err(e) if e.kind() != io::ErrorKind::UnexpectedEof => Err(e),
+            _ => Ok(())
+        }
+    }
+
+    /// Counts the rest of `file` as part of the gap, once resynchronizing has given up.
+    fn skip_rest(&mut self, file: &mut impl Read, on_row_callback: &mut impl FnMut(u64)) -> io::Result<()> {
+        self.read_to += io::copy(file, &mut io::sink())?;
+        self.offset = self.read_to;
+        on_row_callback(self.offset);
+        Ok(())
+    }
+
+    /// Where in the file the next read continues from.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// How many rows have been read so far, including those already taken.
+    pub fn rows(&self) -> usize {
+        self.taken + self.row_numbers.len()
+    }
+
+    /// What was found so far, as [`Self::finish`] would return it, such as to log why reading failed.
+    pub fn summary(&self) -> ReadSummary {
+        let mut summary = self.summary.clone();
+        if let Some(mut event) = self.gap {
+            event.bytes_skipped = self.read_to - event.offset;
+            summary.resync_events.push(event);
+        }
+        summary
+    }
+
+    /// The rows read since they were last taken, along with the summary so far, leaving the reader
+    /// to carry on with none. Rows already taken aren't copied again, so a followed file can be
+    /// handed over a few rows at a time.
+    pub fn take_rows(&mut self) -> (DataFrameView, ReadSummary) {
+        let empty = self.dataframe.empty_like(0);
+        let mut dataframe = std::mem::replace(&mut self.dataframe, empty);
         dataframe.hint_complete();
+        let rows = std::mem::take(&mut self.row_numbers);
+        self.taken += rows.len();
+        (DataFrameView { rows, df: Arc::new(dataframe) }, self.summary())
+    }
 
-        if result.kind() == io::ErrorKind::UnexpectedEof {
-            Ok(DataFrameView {
-                rows: row_numbers,
-                df: Arc::new(dataframe)
-            })
-        } else {
-            Err(result)
+    /// The rows read, along with the summary. Bytes skipped at the end of the file without finding
+    /// another packet count as a final gap. Fails if the file ended before its checksum.
+    pub fn finish(mut self) -> io::Result<(DataFrameView, ReadSummary)> {
+        if !self.started {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The file is too short to hold a checksum."));
+        }
+        if let Some(mut event) = self.gap.take() {
+            event.bytes_skipped = self.read_to - event.offset;
+            self.summary.resync_events.push(event);
         }
+        self.dataframe.hint_complete();
+        Ok((DataFrameView { rows: self.row_numbers, df: Arc::new(self.dataframe) }, self.summary))
     }
 }
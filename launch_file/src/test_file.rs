@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, WriteBytesExt};
+
+use dataframe::Data;
+
+use crate::LogFormat;
+use crate::deserialize::SerializedCpp;
+
+/// Lays out launch files for a format the way firmware writes them, so that reading can be tested
+/// without a log from a flight. Fields are laid out with the same alignment and padding as the C++
+/// structs, and are named like the columns they are read into.
+pub(crate) struct LaunchFileBuilder<'a> {
+    format: &'a LogFormat,
+    big_endian: bool,
+    checksum: u32,
+    /// Everything after the checksum.
+    bytes: Vec<u8>,
+}
+
+impl<'a> LaunchFileBuilder<'a> {
+    /// A little endian file written with the checksum of `format`.
+    pub fn new(format: &'a LogFormat) -> Self {
+        LaunchFileBuilder { format, big_endian: false, checksum: format.checksum, bytes: vec![] }
+    }
+
+    pub fn big_endian(mut self) -> Self {
+        self.big_endian = true;
+        self
+    }
+
+    /// Writes `checksum` at the start of the file instead of the format's.
+    pub fn checksum(mut self, checksum: u32) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Where the next packet starts in the file.
+    pub fn offset(&self) -> u64 {
+        4 + self.bytes.len() as u64
+    }
+
+    /// Adds bytes that aren't a packet, such as a preamble or corruption.
+    pub fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    /// Adds a packet of `variant` at `timestamp`, with each field named in `values` set to its value
+    /// and every other field zero. Enums take the name of their value, and each flag of a bitfield
+    /// is a field of its own.
+    pub fn packet(&mut self, variant: &str, timestamp: u64, values: &[(&str, Data)]) -> &mut Self {
+        if self.big_endian {
+            self.packet_with::<BigEndian>(variant, timestamp, values)
+        } else {
+            self.packet_with::<LittleEndian>(variant, timestamp, values)
+        }
+    }
+
+    fn packet_with<B: ByteOrder>(&mut self, variant: &str, timestamp: u64, values: &[(&str, Data)]) -> &mut Self {
+        let (discriminant, ty) = &self.format.variants[variant];
+        let values: HashMap<&str, Data> = values.iter().copied().collect();
+
+        let mut header = vec![];
+        header.write_uint::<B>(*discriminant as u64, self.format.header.discriminant as usize).unwrap();
+        header.write_uint::<B>(timestamp, self.format.header.timestamp as usize).unwrap();
+        let mut body = vec![];
+        encode::<B>(ty, variant, &values, &mut body);
+
+        self.bytes.extend_from_slice(&header);
+        self.bytes.extend_from_slice(&body);
+        if let Some(trailer) = self.format.trailer {
+            let start = self.bytes.len();
+            if let Some(crc) = trailer.crc {
+                self.bytes.write_uint::<B>(crc.checksum(&[&header, &body]) as u64, crc.size()).unwrap();
+            }
+            self.bytes.resize(start + trailer.size as usize, 0);
+        }
+        self
+    }
+
+    pub fn finish(&self) -> Vec<u8> {
+        let mut file = vec![];
+        if self.big_endian {
+            file.write_u32::<BigEndian>(self.checksum).unwrap();
+        } else {
+            file.write_u32::<LittleEndian>(self.checksum).unwrap();
+        }
+        file.extend_from_slice(&self.bytes);
+        file
+    }
+}
+
+fn align_to(body: &mut Vec<u8>, align: u8) {
+    body.resize(body.len().next_multiple_of(align as usize), 0);
+}
+
+fn integer(value: Option<&Data>) -> i64 {
+    match value {
+        Some(Data::Integer(num)) => *num as i64,
+        Some(Data::Unsigned(num)) => *num as i64,
+        Some(Data::Float(num)) => *num as i64,
+        _ => 0
+    }
+}
+
+fn float(value: Option<&Data>) -> f64 {
+    match value {
+        Some(Data::Float(num)) => *num as f64,
+        value => integer(value) as f64
+    }
+}
+
+/// Writes a value of `ty` named `name` to `body`, returning its alignment like
+/// [`SerializedCpp::to_fast`].
+fn encode<B: ByteOrder>(ty: &SerializedCpp, name: &str, values: &HashMap<&str, Data>, body: &mut Vec<u8>) -> u8 {
+    let value = values.get(name);
+    match ty {
+        SerializedCpp::Boolean => {
+            body.push((integer(value) != 0) as u8);
+            1
+        }
+        SerializedCpp::Integer { size, .. } => {
+            body.write_int::<B>(integer(value), *size as usize).unwrap();
+            *size
+        }
+        SerializedCpp::Float { size: 4 } => {
+            body.write_f32::<B>(float(value) as f32).unwrap();
+            4
+        }
+        SerializedCpp::Float { .. } => {
+            body.write_f64::<B>(float(value)).unwrap();
+            8
+        }
+        SerializedCpp::Enum { variants, .. } => {
+            let disc = match value {
+                Some(Data::Str(variant)) => variants[*variant],
+                value => integer(value) as u32
+            };
+            body.write_u32::<B>(disc).unwrap();
+            4
+        }
+        SerializedCpp::Array { item, count } if matches!(**item, SerializedCpp::Integer { signed: true, size: 1 }) => {
+            let mut bytes = match value {
+                Some(Data::Str(s)) => s.as_bytes().to_vec(),
+                _ => vec![]
+            };
+            bytes.resize(*count as usize, 0);
+            body.extend_from_slice(&bytes);
+            1
+        }
+        SerializedCpp::Array { item, count } => {
+            let mut align = 1;
+            for i in 0..*count {
+                align = encode::<B>(item, &format!("{}[{}]", name, i), values, body);
+                align_to(body, align);
+            }
+            align
+        }
+        SerializedCpp::Struct { members } => {
+            let mut max_align = 1;
+            for (field_name, member) in members {
+                align_to(body, member.align());
+                max_align = max_align.max(encode::<B>(member, &format!("{}.{}", name, field_name), values, body));
+            }
+            align_to(body, max_align);
+            max_align
+        }
+        SerializedCpp::Union { .. } => panic!("Unions can't be read"),
+        SerializedCpp::Bitfield { bits } => {
+            let register = bits.iter()
+                .filter(|(flag, _)| integer(values.get(format!("{}.{}", name, flag).as_str())) != 0)
+                .fold(0u32, |register, (_, bit)| register | 1 << bit);
+            body.write_u32::<B>(register).unwrap();
+            4
+        }
+    }
+}
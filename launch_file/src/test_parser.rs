@@ -0,0 +1,10 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Writes a shell script to `path` that stands in for Python, which needs `lark` installed to run
+/// the real parser, by writing `schema` wherever `--out` says. Shared with the `midas-cli` tests.
+pub(crate) fn write_test_parser(path: &Path, schema: &str) {
+    fs::write(path, format!("#!/bin/sh\nwhile [ \"$1\" != --out ]; do shift; done\ncat > \"$2\" <<'EOF'\n{}\nEOF\n", schema)).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
@@ -0,0 +1,777 @@
+use dataframe::{Data, DataFrameView};
+
+use crate::{with_source_columns, LargeUnsigned, LogFormat, LogFormatError, NonFinite, ReadOptions, ReadSummary, RESYNC_MAX_BYTES};
+use crate::deserialize::SerializedCpp;
+use crate::test_file::LaunchFileBuilder;
+#[cfg(unix)]
+use crate::run_parser;
+#[cfg(unix)]
+use crate::test_parser::write_test_parser;
+
+/// `Imu` has padding after `id` and after `tag`, and `Gps` after `fix`, so both are 16 bytes.
+const SCHEMA: &str = r#"{
+    "<checksum>": 305419896,
+    "Imu": [1, {"type": "struct", "members": {
+        "id": {"type": "int", "signed": false, "size": 1},
+        "accel": {"type": "float", "size": 4},
+        "tag": {"type": "array", "item": {"type": "int", "signed": true, "size": 1}, "count": 3},
+        "count": {"type": "int", "signed": true, "size": 4}
+    }}],
+    "Gps": [2, {"type": "struct", "members": {
+        "fix": {"type": "bool"},
+        "alt": {"type": "float", "size": 8}
+    }}]
+}"#;
+
+fn format(schema: &str) -> LogFormat {
+    LogFormat::from_schema(schema).unwrap()
+}
+
+fn read(format: &LogFormat, file: &[u8], options: ReadOptions) -> (DataFrameView, ReadSummary) {
+    format.read_file(&mut &file[..], Some(file.len() as u64), options, |_| {}).unwrap()
+}
+
+/// Each cell of the column named `name`, in the form `Integer(1)`, so that types are compared too.
+fn cells(view: &DataFrameView, name: &str) -> Vec<String> {
+    let col = view.col_names().position(|col_name| col_name == name).unwrap_or_else(|| panic!("no column {}", name));
+    view.col(col).iter().map(|data| format!("{:?}", data)).collect()
+}
+
+fn imu(id: i32, accel: f32, count: i32) -> [(&'static str, Data<'static>); 3] {
+    [("Imu.id", Data::Integer(id)), ("Imu.accel", Data::Float(accel)), ("Imu.count", Data::Integer(count))]
+}
+
+#[test]
+fn builder_pads_fields_like_the_cpp_struct() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 7, &[("Imu.id", Data::Integer(3)), ("Imu.accel", Data::Float(1.5)), ("Imu.tag", Data::Str("ab")), ("Imu.count", Data::Integer(-2))]);
+
+    let mut expected = vec![];
+    expected.extend_from_slice(&305419896u32.to_le_bytes());
+    expected.extend_from_slice(&1u32.to_le_bytes());
+    expected.extend_from_slice(&7u32.to_le_bytes());
+    expected.extend_from_slice(&[3, 0, 0, 0]);
+    expected.extend_from_slice(&1.5f32.to_le_bytes());
+    expected.extend_from_slice(&[b'a', b'b', 0, 0]);
+    expected.extend_from_slice(&(-2i32).to_le_bytes());
+    assert_eq!(builder.finish(), expected);
+}
+
+#[test]
+fn reads_packets_back() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder
+        .packet("Imu", 10, &[("Imu.id", Data::Integer(200)), ("Imu.accel", Data::Float(-9.5)), ("Imu.tag", Data::Str("xyz")), ("Imu.count", Data::Integer(i32::MAX))])
+        .packet("Gps", 20, &[("Gps.fix", Data::Integer(1)), ("Gps.alt", Data::Float(1250.25))]);
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+
+    assert_eq!(summary.checksum, format.checksum);
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(summary.variants.iter().map(|variant| (variant.size, variant.rows)).collect::<Vec<_>>(), [(16, 1), (16, 1)]);
+    assert_eq!(cells(&view, "sensor"), ["Str(\"Imu\")", "Str(\"Gps\")"]);
+    assert_eq!(cells(&view, "timestamp"), ["Unsigned(10)", "Unsigned(20)"]);
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(200)", "Null"]);
+    assert_eq!(cells(&view, "Imu.accel"), ["Float(-9.5)", "Null"]);
+    assert_eq!(cells(&view, "Imu.tag"), ["Str(\"xyz\")", "Null"]);
+    assert_eq!(cells(&view, "Imu.count"), ["Integer(2147483647)", "Null"]);
+    assert_eq!(cells(&view, "Gps.fix"), ["Null", "Integer(1)"]);
+    assert_eq!(cells(&view, "Gps.alt"), ["Null", "Float(1250.25)"]);
+}
+
+#[test]
+fn pads_between_structs_in_an_array() {
+    let format = format(r#"{
+        "<checksum>": 1,
+        "Log": [5, {"type": "struct", "members": {
+            "points": {"type": "array", "count": 2, "item": {"type": "struct", "members": {
+                "flag": {"type": "int", "signed": false, "size": 1},
+                "value": {"type": "int", "signed": true, "size": 4}
+            }}},
+            "last": {"type": "int", "signed": false, "size": 1}
+        }}]
+    }"#);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Log", 0, &[
+        ("Log.points[0].flag", Data::Integer(1)), ("Log.points[0].value", Data::Integer(-1)),
+        ("Log.points[1].flag", Data::Integer(2)), ("Log.points[1].value", Data::Integer(300)),
+        ("Log.last", Data::Integer(9))
+    ]);
+    let file = builder.finish();
+    // Two 8 byte structs, then the last byte padded out to the alignment of the struct.
+    assert_eq!(file.len(), 4 + 8 + 20);
+    assert_eq!(file[4 + 8 + 16], 9);
+
+    let (view, summary) = read(&format, &file, ReadOptions::default());
+    assert_eq!(summary.variants[0].size, 20);
+    assert_eq!(cells(&view, "Log.points[0].value"), ["Integer(-1)"]);
+    assert_eq!(cells(&view, "Log.points[1].flag"), ["Integer(2)"]);
+    assert_eq!(cells(&view, "Log.points[1].value"), ["Integer(300)"]);
+    assert_eq!(cells(&view, "Log.last"), ["Integer(9)"]);
+}
+
+const NAME_SCHEMA: &str = r#"{
+    "<checksum>": 1,
+    "Name": [1, {"type": "struct", "members": {
+        "name": {"type": "array", "item": {"type": "int", "signed": true, "size": 1}, "count": 8}
+    }}]
+}"#;
+
+/// A `Name` packet at `timestamp` whose `char[8]` holds `name`.
+fn name_packet(timestamp: u32, name: &[u8; 8]) -> Vec<u8> {
+    let mut packet = vec![];
+    packet.extend_from_slice(&1u32.to_le_bytes());
+    packet.extend_from_slice(&timestamp.to_le_bytes());
+    packet.extend_from_slice(name);
+    packet
+}
+
+#[test]
+fn reads_a_char_array_up_to_its_first_nul() {
+    let format = format(NAME_SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.raw(&name_packet(1, b"ab\0\0\0\0\0\0")).raw(&name_packet(2, b"ab\0xyz\0\0")).raw(&name_packet(3, b"\0bcdefgh"));
+    let (view, _) = read(&format, &builder.finish(), ReadOptions::default());
+    assert_eq!(cells(&view, "Name.name"), ["Str(\"ab\")", "Str(\"ab\")", "Str(\"\")"]);
+}
+
+#[test]
+fn reads_a_char_array_without_a_nul_to_its_end() {
+    let format = format(NAME_SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.raw(&name_packet(1, b"abcdefgh")).raw(&name_packet(2, b"abc\xffefgh"));
+    let (view, _) = read(&format, &builder.finish(), ReadOptions::default());
+    assert_eq!(cells(&view, "Name.name"), ["Str(\"abcdefgh\")", "Str(\"abc\u{fffd}efgh\")"]);
+}
+
+#[test]
+fn reads_other_header_layouts_and_byte_orders() {
+    let format = format(&SCHEMA.replacen('{', r#"{"<header>": {"discriminant": 2, "timestamp": 8},"#, 1));
+    let mut builder = LaunchFileBuilder::new(&format).big_endian();
+    builder.packet("Imu", 5_000_000_000, &imu(1, 2.0, 3)).packet("Imu", 6, &imu(4, 5.0, 6));
+    let file = builder.finish();
+    assert_eq!(file.len(), 4 + 2 * (10 + 16));
+
+    let (view, summary) = read(&format, &file, ReadOptions { big_endian: true, ..ReadOptions::default() });
+    assert_eq!(summary.checksum, format.checksum);
+    // The timestamp column only holds 32 bits.
+    assert_eq!(cells(&view, "timestamp"), ["Null", "Unsigned(6)"]);
+    assert_eq!(cells(&view, "Imu.accel"), ["Float(2.0)", "Float(5.0)"]);
+    assert_eq!(cells(&view, "Imu.count"), ["Integer(3)", "Integer(6)"]);
+}
+
+#[test]
+fn counts_epoch_millisecond_timestamps_too_large_for_the_column() {
+    let format = format(&SCHEMA.replacen('{', r#"{"<header>": {"discriminant": 2, "timestamp": 8},"#, 1));
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder
+        .packet("Imu", 1_700_000_000_000, &imu(1, 1.0, 1))
+        .packet("Gps", 1_700_000_000_010, &[])
+        .packet("Imu", 1_700_000_000_020, &imu(2, 2.0, 2));
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(summary.large_timestamps, 3);
+    assert_eq!(cells(&view, "timestamp"), ["Null", "Null", "Null"]);
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Null", "Integer(2)"]);
+}
+
+#[test]
+fn reads_timestamps_either_side_of_2_to_the_31_milliseconds_in_order() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    for (id, timestamp) in [(1, (1 << 31) - 10), (2, (1 << 31) - 1), (3, 1 << 31), (4, (1 << 31) + 10)] {
+        builder.packet("Imu", timestamp, &imu(id, 1.0, 1));
+    }
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(summary.large_timestamps, 0);
+    assert_eq!(cells(&view, "timestamp"), ["Unsigned(2147483638)", "Unsigned(2147483647)", "Unsigned(2147483648)", "Unsigned(2147483658)"]);
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Integer(2)", "Integer(3)", "Integer(4)"]);
+}
+
+#[test]
+fn leaves_out_a_datetime_past_the_largest_i32() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    for timestamp in [(1 << 31) - 1, 1 << 31, (1 << 31) + 1000] {
+        builder.packet("Imu", timestamp, &imu(1, 1.0, 1));
+    }
+    // 2^31 milliseconds after the start is 2147483 seconds, landing exactly on `i32::MAX`.
+    let options = ReadOptions { start_epoch: Some(i32::MAX - 2_147_483), ..ReadOptions::default() };
+    let (view, summary) = read(&format, &builder.finish(), options);
+
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(cells(&view, "datetime"), ["Integer(2147483647)", "Integer(2147483647)", "Null"]);
+}
+
+#[test]
+fn guesses_a_big_endian_file_is_big_endian() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format).big_endian();
+    for i in 0..20 {
+        builder.packet(if i % 3 == 0 { "Gps" } else { "Imu" }, 1000 + i * 10, &imu(i as i32, 1.0, 1));
+    }
+    let guesses = format.guess_layouts(&mut &builder.finish()[..], 0).unwrap();
+
+    assert_eq!(guesses.len(), 8);
+    let best = guesses[0];
+    assert!(best.big_endian);
+    assert!(best.checksum_matches);
+    assert_eq!(best.header, format.header);
+    assert_eq!((best.packets, best.bytes_skipped), (20, 0));
+    assert!(guesses.iter().filter(|guess| !guess.big_endian).all(|guess| !guess.checksum_matches));
+}
+
+#[test]
+fn skips_a_preamble() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.raw(&[0xAB; 10]).packet("Imu", 1, &imu(1, 1.0, 1));
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions { preamble: 10, ..ReadOptions::default() });
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)"]);
+}
+
+#[test]
+fn reads_at_most_max_rows_after_skipping_skip_rows() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    for i in 0..25 {
+        builder.packet("Imu", i as u64, &imu(i, 1.0, 1));
+    }
+    let file = builder.finish();
+    let ids = |ids: std::ops::Range<i32>| ids.map(|id| format!("Integer({})", id)).collect::<Vec<_>>();
+
+    let (view, summary) = read(&format, &file, ReadOptions { max_rows: Some(10), ..ReadOptions::default() });
+    assert_eq!(view.shape().rows, 10);
+    assert_eq!(summary.variants[0].rows, 10);
+    assert_eq!(cells(&view, "Imu.id"), ids(0..10));
+
+    let (view, _) = read(&format, &file, ReadOptions { skip_rows: 20, ..ReadOptions::default() });
+    assert_eq!(cells(&view, "Imu.id"), ids(20..25));
+
+    let (view, _) = read(&format, &file, ReadOptions { skip_rows: 5, max_rows: Some(10), ..ReadOptions::default() });
+    assert_eq!(cells(&view, "Imu.id"), ids(5..15));
+}
+
+#[test]
+fn resyncs_after_a_bad_discriminant() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 100, &imu(1, 1.0, 1));
+    let gap_offset = builder.offset();
+    // A header with an unknown discriminant, then bytes that can't start one either.
+    builder.raw(&[9, 0, 0, 0, 100, 0, 0, 0, 0xFF, 0xFF, 0xFF]);
+    builder.packet("Imu", 110, &imu(2, 2.0, 2)).packet("Gps", 120, &[]);
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+
+    assert_eq!(summary.resync_events.len(), 1);
+    let event = summary.resync_events[0];
+    assert_eq!(event.offset, gap_offset);
+    assert_eq!(event.bytes_skipped, 11);
+    assert_eq!(event.timestamp, 100);
+    assert_eq!(event.discriminant, 9);
+    assert_eq!(cells(&view, "timestamp"), ["Unsigned(100)", "Unsigned(110)", "Unsigned(120)"]);
+}
+
+#[test]
+fn resync_rejects_a_timestamp_jump() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1000, &imu(1, 1.0, 1)).raw(&[0xEE; 3]);
+    // A real discriminant found while resynchronizing, but far too late to follow the last packet.
+    let jump_offset = builder.offset();
+    builder.packet("Gps", 900_000, &[]).packet("Imu", 1100, &imu(2, 2.0, 2));
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+
+    assert_eq!(summary.resync_events.len(), 1);
+    assert_eq!(summary.resync_events[0].bytes_skipped, jump_offset - summary.resync_events[0].offset + 8 + 16);
+    assert_eq!(cells(&view, "timestamp"), ["Unsigned(1000)", "Unsigned(1100)"]);
+}
+
+#[test]
+fn leaves_out_a_packet_cut_off_by_the_end_of_the_file() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1));
+    let cut_offset = builder.offset();
+    builder.packet("Imu", 2, &imu(2, 2.0, 2));
+    let mut file = builder.finish();
+    file.truncate(file.len() - 5);
+
+    let (view, summary) = read(&format, &file, ReadOptions::default());
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)"]);
+
+    let mut reader = format.reader(None, ReadOptions::default(), |_, _| {}).unwrap();
+    reader.read(&mut &file[..], |_| {}).unwrap();
+    assert_eq!(reader.offset(), cut_offset);
+
+    let report = format.validate_file(&mut &file[..], ReadOptions::default(), |_| {}).unwrap();
+    assert_eq!(report.packets(), 1);
+    assert_eq!(report.trailing_bytes, 8 + 16 - 5);
+}
+
+#[test]
+fn validating_finds_what_reading_does() {
+    let format = format(r#"{
+        "<checksum>": 1,
+        "<trailer>": {"size": 4, "crc": "crc16"},
+        "Log": [5, {"type": "struct", "members": {
+            "points": {"type": "array", "count": 3, "item": {"type": "struct", "members": {
+                "flag": {"type": "bool"},
+                "value": {"type": "float", "size": 8}
+            }}},
+            "name": {"type": "array", "item": {"type": "int", "signed": true, "size": 1}, "count": 5},
+            "last": {"type": "int", "signed": false, "size": 1}
+        }}],
+        "Gps": [2, {"type": "struct", "members": {
+            "fix": {"type": "bool"},
+            "alt": {"type": "float", "size": 8}
+        }}]
+    }"#);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Log", 1, &[("Log.last", Data::Integer(1))]).packet("Gps", 2, &[]);
+    let bad_offset = builder.offset() as usize;
+    builder.packet("Log", 3, &[]).raw(&[0xEE; 7]).packet("Gps", 4, &[]).packet("Log", 5, &[]);
+    let mut file = builder.finish();
+    file[bad_offset + 8] ^= 0x01;
+    file.truncate(file.len() - 3);
+
+    let (_, read) = read(&format, &file, ReadOptions::default());
+    let validated = format.validate_file(&mut &file[..], ReadOptions::default(), |_| {}).unwrap().summary;
+
+    let variants = |summary: &ReadSummary| summary.variants.iter().map(|variant| (variant.name.clone(), variant.size, variant.rows)).collect::<Vec<_>>();
+    let gaps = |summary: &ReadSummary| summary.resync_events.iter().map(|event| (event.offset, event.bytes_skipped)).collect::<Vec<_>>();
+    assert_eq!(variants(&validated), variants(&read));
+    assert_eq!(variants(&read), [("Log".to_string(), 16 * 3 + 8, 1), ("Gps".to_string(), 16, 2)]);
+    assert_eq!(gaps(&validated), gaps(&read));
+    assert_eq!(gaps(&read).len(), 1);
+    assert_eq!((validated.crc_failures, validated.checksum), (read.crc_failures, read.checksum));
+    assert_eq!(read.crc_failures, 1);
+}
+
+#[test]
+fn logs_what_went_wrong_in_a_corrupted_file() {
+    let format = format(&SCHEMA.replacen('{', r#"{"<trailer>": {"size": 4, "crc": "crc16"},"#, 1));
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 100, &imu(1, 1.0, 1));
+    let gap_offset = builder.offset();
+    builder.raw(&[9, 0, 0, 0, 100, 0, 0, 0, 0xFF]);
+    let bad_offset = builder.offset() as usize;
+    builder.packet("Imu", 110, &imu(2, 2.0, 2)).packet("Gps", 120, &[]);
+    let mut file = builder.finish();
+    file[bad_offset + 8 + 4] ^= 0x10;
+
+    let mut reader = format.reader(None, ReadOptions::default(), |_, _| {}).unwrap();
+    reader.read(&mut &file[..], |_| {}).unwrap();
+    let log = format.parse_log("flight.launch", &reader.summary(), None);
+    let lines: Vec<&str> = log.lines().collect();
+
+    assert_eq!(lines, [
+        "source path=\"flight.launch\"",
+        "checksum file=0x12345678 format=0x12345678 matches=true",
+        "variant name=\"Imu\" discriminant=1 size=16 rows=1",
+        "variant name=\"Gps\" discriminant=2 size=16 rows=1",
+        &format!("resync offset={} bytes_skipped=9 last_good_timestamp=100 found_discriminant=9 found_timestamp=100 reason=\"unknown discriminant\"", gap_offset),
+        "crc_failures count=1",
+        "summary packets=2 gaps=1 bytes_skipped=9",
+    ]);
+}
+
+#[test]
+fn logs_why_a_file_could_not_be_read() {
+    let format = format(SCHEMA);
+    let mut reader = format.reader(None, ReadOptions::default(), |_, _| {}).unwrap();
+    reader.read(&mut &[1u8, 2][..], |_| {}).unwrap();
+    let (summary, offset) = (reader.summary(), reader.offset());
+    let error = reader.finish().err().unwrap();
+
+    let log = format.parse_log("short.launch", &summary, Some((&error, offset)));
+    assert!(log.contains("checksum file=0x00000000 format=0x12345678 matches=false\n"));
+    assert!(log.ends_with("error offset=0 message=\"The file is too short to hold a checksum.\"\n"));
+}
+
+#[test]
+fn fails_on_a_file_without_a_checksum() {
+    let format = format(SCHEMA);
+    assert!(format.read_file(&mut &[1u8, 2][..], None, ReadOptions::default(), |_| {}).is_err());
+}
+
+#[test]
+fn carries_on_reading_as_the_file_grows() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1)).packet("Imu", 2, &imu(2, 2.0, 2));
+    let file = builder.finish();
+
+    // The second packet arrives in two writes.
+    let split = file.len() - 10;
+    let mut reader = format.reader(None, ReadOptions::default(), |_, _| {}).unwrap();
+    reader.read(&mut &file[..split], |_| {}).unwrap();
+    assert_eq!(reader.rows(), 1);
+    reader.read(&mut &file[reader.offset() as usize..], |_| {}).unwrap();
+    assert_eq!(reader.rows(), 2);
+
+    let (view, summary) = reader.finish().unwrap();
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Integer(2)"]);
+}
+
+#[test]
+fn hands_over_only_the_rows_read_since_they_were_last_taken() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1)).packet("Gps", 2, &[("Gps.fix", Data::Integer(1))]);
+    let split = builder.offset() as usize;
+    builder.packet("Imu", 3, &imu(3, 3.0, 3));
+    let file = builder.finish();
+
+    let mut reader = format.reader(None, ReadOptions { max_rows: Some(3), ..ReadOptions::default() }, |_, _| {}).unwrap();
+    reader.read(&mut &file[..split], |_| {}).unwrap();
+    let (first, _) = reader.take_rows();
+    assert_eq!(cells(&first, "timestamp"), ["Unsigned(1)", "Unsigned(2)"]);
+
+    reader.read(&mut &file[reader.offset() as usize..], |_| {}).unwrap();
+    let (second, summary) = reader.take_rows();
+    assert_eq!(cells(&second, "sensor"), ["Str(\"Imu\")"]);
+    assert_eq!(cells(&second, "Imu.id"), ["Integer(3)"]);
+    assert_eq!(reader.rows(), 3);
+    assert_eq!(summary.variants.iter().map(|variant| variant.rows).sum::<usize>(), 3);
+
+    let mut followed = first;
+    followed.extend(&second).unwrap();
+    assert_eq!(cells(&followed, "sensor"), ["Str(\"Imu\")", "Str(\"Gps\")", "Str(\"Imu\")"]);
+}
+
+#[test]
+fn leaves_out_packets_failing_their_crc() {
+    let format = format(&SCHEMA.replacen('{', r#"{"<trailer>": {"size": 4, "crc": "crc16"},"#, 1));
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1));
+    let bad_offset = builder.offset() as usize;
+    builder.packet("Imu", 2, &imu(2, 2.0, 2)).packet("Imu", 3, &imu(3, 3.0, 3));
+    let mut file = builder.finish();
+    // Corrupt a byte of the second packet's body.
+    file[bad_offset + 8 + 4] ^= 0x10;
+
+    let (view, summary) = read(&format, &file, ReadOptions::default());
+    assert_eq!(summary.crc_failures, 1);
+    assert!(summary.resync_events.is_empty());
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Integer(3)"]);
+}
+
+#[test]
+fn reports_the_checksum_the_file_was_written_with() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format).checksum(0xDEADBEEF);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1));
+    let (view, summary) = read(&format, &builder.finish(), ReadOptions::default());
+    assert_eq!(summary.checksum, 0xDEADBEEF);
+    assert_eq!(view.shape().rows, 1);
+}
+
+#[test]
+fn gives_up_resyncing_after_a_long_run_without_packets() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1));
+    let gap_offset = builder.offset();
+    builder.raw(&vec![0xEE; RESYNC_MAX_BYTES as usize + 100]);
+    // Far enough into the gap that it isn't searched for.
+    builder.packet("Imu", 2, &imu(2, 2.0, 2));
+    let file = builder.finish();
+    let (view, summary) = read(&format, &file, ReadOptions::default());
+
+    assert_eq!(summary.resync_events.len(), 1);
+    assert_eq!(summary.resync_events[0].offset, gap_offset);
+    assert_eq!(summary.resync_events[0].bytes_skipped, file.len() as u64 - gap_offset);
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)"]);
+
+    let report = format.validate_file(&mut &file[..], ReadOptions::default(), |_| {}).unwrap();
+    assert_eq!(report.summary.bytes_skipped(), file.len() as u64 - gap_offset);
+    assert_eq!(report.trailing_bytes, 0);
+}
+
+#[test]
+fn loads_large_unsigned_fields_each_way() {
+    let format = format(r#"{"<checksum>": 1, "Count": [1, {"type": "struct", "members": {
+        "n": {"type": "int", "signed": false, "size": 4}
+    }}]}"#);
+    let mut builder = LaunchFileBuilder::new(&format);
+    for n in [7, 0x8000_0000, 0xFFFF_FFFE, 0xFFFF_FFFF] {
+        builder.packet("Count", 1, &[("Count.n", Data::Unsigned(n))]);
+    }
+    let file = builder.finish();
+    let read_with = |large_unsigned| cells(&read(&format, &file, ReadOptions { large_unsigned, ..ReadOptions::default() }).0, "Count.n");
+
+    assert_eq!(read_with(LargeUnsigned::Saturate), ["Integer(7)", "Integer(2147483647)", "Integer(2147483647)", "Integer(2147483647)"]);
+    assert_eq!(read_with(LargeUnsigned::Float), ["Float(7.0)", "Float(2147483600.0)", "Float(4294967300.0)", "Float(4294967300.0)"]);
+    // `u32::MAX` is the one value an unsigned column can't hold, and often means the field wasn't set.
+    assert_eq!(read_with(LargeUnsigned::Unsigned), ["Unsigned(7)", "Unsigned(2147483648)", "Unsigned(4294967294)", "Null"]);
+}
+
+#[test]
+fn adds_an_unsigned_discriminant_column() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1)).packet("Gps", 2, &[]);
+    let (view, _) = read(&format, &builder.finish(), ReadOptions { discriminant: true, ..ReadOptions::default() });
+    assert_eq!(cells(&view, "discriminant"), ["Unsigned(1)", "Unsigned(2)"]);
+}
+
+#[test]
+fn reports_progress_once_for_each_variant_set_up() {
+    let format = format(SCHEMA);
+    let mut calls = vec![];
+    format.reader(None, ReadOptions::default(), |done, total| calls.push((done, total))).unwrap();
+    assert_eq!(calls, [(1, 2), (2, 2)]);
+}
+
+#[test]
+fn names_the_columns_of_variants_sharing_a_field_name_apart() {
+    // `Imu.raw.x` is both the `x` of `Imu`'s `raw` struct and the `x` of the `Imu.raw` variant.
+    let format = format(r#"{
+        "<checksum>": 1,
+        "Imu": [1, {"type": "struct", "members": {
+            "count": {"type": "int", "signed": true, "size": 4},
+            "raw": {"type": "struct", "members": {"x": {"type": "int", "signed": true, "size": 4}}}
+        }}],
+        "Baro": [2, {"type": "struct", "members": {"count": {"type": "int", "signed": true, "size": 4}}}],
+        "Imu.raw": [3, {"type": "struct", "members": {"x": {"type": "int", "signed": true, "size": 4}}}]
+    }"#);
+    // The builder names fields within their own variant, so `Imu.raw`'s is `Imu.raw.x` there.
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder
+        .packet("Imu.raw", 1, &[("Imu.raw.x", Data::Integer(3))])
+        .packet("Baro", 2, &[("Baro.count", Data::Integer(2))])
+        .packet("Imu", 3, &[("Imu.count", Data::Integer(1)), ("Imu.raw.x", Data::Integer(4))]);
+    let file = builder.finish();
+
+    let expected = ["sensor", "timestamp", "Imu.count", "Imu.raw.x", "Baro.count", "Imu.raw.x.1"];
+    for _ in 0..2 {
+        let (view, _) = read(&format, &file, ReadOptions::default());
+        assert_eq!(view.col_names().collect::<Vec<_>>(), expected);
+        assert_eq!(cells(&view, "Imu.count"), ["Null", "Null", "Integer(1)"]);
+        assert_eq!(cells(&view, "Baro.count"), ["Null", "Integer(2)", "Null"]);
+        assert_eq!(cells(&view, "Imu.raw.x"), ["Null", "Null", "Integer(4)"]);
+        assert_eq!(cells(&view, "Imu.raw.x.1"), ["Integer(3)", "Null", "Null"]);
+    }
+}
+
+#[test]
+fn reads_each_bit_of_a_bitfield_into_its_own_column() {
+    let format = format(r#"{
+        "<checksum>": 1,
+        "Status": [1, {"type": "struct", "members": {
+            "flags": {"type": "bitfield", "bits": {"armed": 0, "apogee": 5, "landed": 31}},
+            "mode": {"type": "int", "signed": false, "size": 1}
+        }}]
+    }"#);
+    for big_endian in [false, true] {
+        let mut builder = if big_endian { LaunchFileBuilder::new(&format).big_endian() } else { LaunchFileBuilder::new(&format) };
+        builder
+            .packet("Status", 1, &[("Status.flags.armed", Data::Integer(1)), ("Status.flags.landed", Data::Integer(1)), ("Status.mode", Data::Integer(3))])
+            .packet("Status", 2, &[("Status.flags.apogee", Data::Integer(1)), ("Status.mode", Data::Integer(4))]);
+        let (view, _) = read(&format, &builder.finish(), ReadOptions { big_endian, ..ReadOptions::default() });
+
+        assert_eq!(cells(&view, "Status.flags.armed"), ["Integer(1)", "Integer(0)"]);
+        assert_eq!(cells(&view, "Status.flags.apogee"), ["Integer(0)", "Integer(1)"]);
+        assert_eq!(cells(&view, "Status.flags.landed"), ["Integer(1)", "Integer(0)"]);
+        assert_eq!(cells(&view, "Status.mode"), ["Integer(3)", "Integer(4)"]);
+    }
+}
+
+#[test]
+fn keeps_empties_or_clamps_floats_that_arent_finite() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder
+        .packet("Imu", 1, &imu(1, f32::NAN, 1))
+        .packet("Imu", 2, &imu(2, f32::INFINITY, 2))
+        .packet("Imu", 3, &imu(3, f32::NEG_INFINITY, 3))
+        .packet("Imu", 4, &imu(4, 1.5, 4))
+        .packet("Gps", 5, &[("Gps.alt", Data::Float(f32::INFINITY))]);
+    let file = builder.finish();
+    let floats = |non_finite| {
+        let (view, _) = read(&format, &file, ReadOptions { non_finite, ..ReadOptions::default() });
+        assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Integer(2)", "Integer(3)", "Integer(4)", "Null"]);
+        [cells(&view, "Imu.accel"), cells(&view, "Gps.alt")].concat()
+    };
+
+    assert_eq!(floats(NonFinite::Keep), ["Float(NaN)", "Float(inf)", "Float(-inf)", "Float(1.5)", "Null", "Null", "Null", "Null", "Null", "Float(inf)"]);
+    assert_eq!(floats(NonFinite::Null), ["Null", "Null", "Null", "Float(1.5)", "Null", "Null", "Null", "Null", "Null", "Null"]);
+    assert_eq!(floats(NonFinite::Clamp), ["Null", "Float(3.4028235e38)", "Float(-3.4028235e38)", "Float(1.5)", "Null", "Null", "Null", "Null", "Null", "Float(3.4028235e38)"]);
+}
+
+#[test]
+fn reads_files_on_their_own_threads_the_same_as_one_after_another() {
+    let format = format(SCHEMA);
+    let mut first = LaunchFileBuilder::new(&format);
+    first.packet("Imu", 1, &imu(1, 1.5, 10)).packet("Gps", 2, &[("Gps.alt", Data::Float(20.0))]);
+    let mut second = LaunchFileBuilder::new(&format);
+    second.packet("Gps", 3, &[("Gps.fix", Data::Integer(1))]).packet("Imu", 4, &[("Imu.tag", Data::Str("ab"))]).packet("Imu", 5, &imu(2, -0.5, 20));
+    let files = [first.finish(), second.finish()];
+
+    let sequential: Vec<_> = files.iter().enumerate()
+        .map(|(file_number, file)| with_source_columns(&read(&format, file, ReadOptions::default()).0, file_number, &format!("{}.launch", file_number)))
+        .collect();
+    let sequential = DataFrameView::concat(&sequential).unwrap();
+
+    let mut progress = vec![];
+    let results = format.read_files(files.iter().map(|file| (&file[..], Some(file.len() as u64))).collect(), ReadOptions::default(), |bytes_read| progress.push(bytes_read));
+    let concurrent: Vec<_> = results.into_iter().enumerate()
+        .map(|(file_number, result)| with_source_columns(&result.unwrap().0, file_number, &format!("{}.launch", file_number)))
+        .collect();
+    let concurrent = DataFrameView::concat(&concurrent).unwrap();
+
+    assert_eq!(concurrent.col_names().collect::<Vec<_>>(), sequential.col_names().collect::<Vec<_>>());
+    for name in sequential.col_names() {
+        assert_eq!(cells(&concurrent, name), cells(&sequential, name), "{}", name);
+    }
+    assert_eq!(cells(&concurrent, "Imu.tag"), ["Str(\"\")", "Null", "Null", "Str(\"ab\")", "Str(\"\")"]);
+    assert_eq!(cells(&concurrent, "file number"), ["Integer(0)", "Integer(0)", "Integer(1)", "Integer(1)", "Integer(1)"]);
+    assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(progress.last().copied(), Some((files[0].len() + files[1].len()) as u64));
+}
+
+#[test]
+fn adds_the_file_number_and_name_to_every_row() {
+    let format = format(SCHEMA);
+    let mut builder = LaunchFileBuilder::new(&format);
+    builder.packet("Imu", 1, &imu(1, 1.0, 1)).packet("Gps", 2, &[]);
+    let (view, _) = read(&format, &builder.finish(), ReadOptions::default());
+    let view = with_source_columns(&view, 3, "flight.launch");
+
+    assert_eq!(view.col_names().skip(8).collect::<Vec<_>>(), ["file number", "source file"]);
+    assert_eq!(cells(&view, "file number"), ["Integer(3)", "Integer(3)"]);
+    assert_eq!(cells(&view, "source file"), ["Str(\"flight.launch\")", "Str(\"flight.launch\")"]);
+    assert_eq!(cells(&view, "Imu.id"), ["Integer(1)", "Null"]);
+}
+
+#[test]
+fn fails_to_load_a_format_with_a_type_that_cant_be_read() {
+    let error = |schema: &str| LogFormat::from_schema(schema).err().unwrap();
+
+    let short = SCHEMA.replacen(r#""type": "int", "signed": true, "size": 4"#, r#""type": "int", "signed": true, "size": 2"#, 1);
+    assert!(matches!(error(&short), LogFormatError::UnsupportedType(msg) if msg == "Imu.count: 2 byte signed integers can't be read"));
+
+    let bitfield = r#"{"<checksum>": 1, "Status": [1, {"type": "struct", "members": {
+        "flags": {"type": "bitfield", "bits": {"armed": 0, "landed": 32}}
+    }}]}"#;
+    assert!(matches!(error(bitfield), LogFormatError::UnsupportedType(msg) if msg == "Status.flags.landed: bit 32 is outside the 32 bit register"));
+
+    let small_enum = r#"{"<checksum>": 1, "Status": [1, {"type": "struct", "members": {
+        "mode": {"type": "enum", "variants": {"IDLE": 0, "FLYING": 1}, "size": 2}
+    }}]}"#;
+    assert!(matches!(error(small_enum), LogFormatError::UnsupportedType(msg) if msg == "Status.mode: 2 byte enums can't be read"));
+}
+
+#[test]
+fn fails_to_load_a_format_with_a_union() {
+    let schema = r#"{"<checksum>": 1, "Reading": [1, {"type": "struct", "members": {
+        "value": {"type": "union", "variants": [["raw", {"type": "int", "signed": false, "size": 4}], ["scaled", {"type": "float", "size": 8}]]}
+    }}]}"#;
+    let error = LogFormat::from_schema(schema).err().unwrap();
+    assert!(matches!(error, LogFormatError::UnsupportedType(msg) if msg == "Reading.value: unions can't be read"));
+}
+
+#[test]
+fn fails_to_load_a_format_with_a_malformed_schema_header_or_trailer() {
+    let error = |schema: &str| LogFormat::from_schema(schema).err().unwrap();
+
+    assert!(matches!(error("{\"<checksum>\": "), LogFormatError::Schema(_)));
+    assert!(matches!(error(r#"{"<checksum>": "none"}"#), LogFormatError::Schema(_)));
+
+    let header = SCHEMA.replacen('{', r#"{"<header>": {"discriminant": 3, "timestamp": 4},"#, 1);
+    assert!(matches!(error(&header), LogFormatError::MalformedHeader(msg) if msg == "Unsupported discriminant size 3 in packet header"));
+    let header = SCHEMA.replacen('{', r#"{"<header>": {"discriminant": 4, "timestamp": 2},"#, 1);
+    assert!(matches!(error(&header), LogFormatError::MalformedHeader(msg) if msg == "Unsupported timestamp size 2 in packet header"));
+
+    let trailer = SCHEMA.replacen('{', r#"{"<trailer>": {"size": 2, "crc": "crc32"},"#, 1);
+    assert!(matches!(error(&trailer), LogFormatError::MalformedTrailer(msg) if msg == "Packet trailer of 2 bytes can't hold a 4 byte CRC"));
+
+    let missing = std::env::temp_dir().join(format!("midas-launch-{}-missing-schema.json", std::process::id()));
+    assert!(matches!(LogFormat::from_schema_json(&missing).err().unwrap(), LogFormatError::Io("Could not read schema", e) if e.kind() == std::io::ErrorKind::NotFound));
+}
+
+#[test]
+fn aligns_a_union_to_its_most_aligned_variant() {
+    let union: SerializedCpp = serde_json::from_str(r#"{"type": "union", "variants": [
+        ["flag", {"type": "bool"}], ["scaled", {"type": "float", "size": 8}], ["raw", {"type": "int", "signed": false, "size": 4}]
+    ]}"#).unwrap();
+    assert_eq!(union.align(), 8);
+}
+
+#[test]
+fn estimates_memory_from_the_smallest_packet() {
+    let format = format(SCHEMA);
+    let size = format.size().unwrap();
+    assert_eq!(size.smallest_body, 16);
+    // id, accel, tag, count, fix and alt. The sensor and timestamp columns are added when estimating.
+    assert_eq!(size.columns, 6);
+
+    // Ten packets of 8 + 16 bytes, each a row of 8 cells plus its place in the row order.
+    let estimate = format.estimate_memory_bytes(size, 4 + 10 * 24, ReadOptions::default());
+    assert_eq!(estimate, 10 * (8 * 4 + std::mem::size_of::<usize>() as u64));
+}
+
+#[test]
+fn describes_each_field_and_the_padding_between_them() {
+    let report = format(SCHEMA).report().unwrap();
+    assert!(report.contains("## Imu\n\nDiscriminant 1, with a body of 16 bytes."));
+    assert!(report.contains("| 0 | 1 | Imu.id | uint8_t |\n| 1 | 3 | | padding |\n| 4 | 4 | Imu.accel | float |\n"));
+    assert!(report.contains("| 0 | 1 | Gps.fix | bool |\n| 1 | 7 | | padding |\n| 8 | 8 | Gps.alt | double |\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn stops_a_parser_that_runs_past_the_timeout() {
+    let mut command = std::process::Command::new("sh");
+    command.arg("-c").arg("sleep 10");
+    let start = std::time::Instant::now();
+    let result = run_parser(command, std::time::Duration::from_millis(200), |_| true);
+
+    assert!(matches!(result, Err(LogFormatError::Timeout(..))));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[cfg(unix)]
+#[test]
+fn does_not_block_on_a_parser_writing_more_than_a_pipe_holds() {
+    let mut command = std::process::Command::new("sh");
+    command.arg("-c").arg("yes error | head -c 1000000 >&2; yes output | head -c 1000000; exit 1");
+    let result = run_parser(command, std::time::Duration::from_secs(30), |_| true);
+
+    match result {
+        Err(LogFormatError::ScriptFailed(stderr)) => assert!(stderr.starts_with("error\n") && stderr.len() >= 1000000),
+        _ => panic!("expected the parser to fail")
+    }
+}
+
+// The stand-in parser is a shell script.
+#[cfg(unix)]
+#[test]
+fn reads_an_unchanged_header_from_the_cache_without_running_the_parser() {
+    let dir = std::env::temp_dir().join(format!("midas-launch-{}-schema-cache", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let header = dir.join("log_format.h");
+    std::fs::write(&header, "struct LoggedReading;").unwrap();
+    let parser = dir.join("parser.sh");
+    write_test_parser(&parser, SCHEMA);
+    let missing = dir.join("missing-python");
+    let timeout = std::time::Duration::from_secs(30);
+
+    let parsed = LogFormat::from_file_in(&dir, &dir, &header, &parser, timeout, |_| true).map(|format| format.checksum);
+    let cached = LogFormat::from_file_in(&dir, &dir, &header, &missing, timeout, |_| true).map(|format| format.checksum);
+    std::fs::write(&header, "struct LoggedReading {};").unwrap();
+    let changed = LogFormat::from_file_in(&dir, &dir, &header, &missing, timeout, |_| true);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(parsed.unwrap(), 305419896);
+    assert_eq!(cached.unwrap(), 305419896);
+    assert!(matches!(changed, Err(LogFormatError::PythonSpawn(_))));
+}
@@ -0,0 +1,144 @@
+//! Converts .launch files to a CSV file without opening a window.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use dataframe::DataFrameView;
+use launch_file::{DEFAULT_PYTHON_TIMEOUT, LogFormat, ReadOptions, with_source_columns};
+
+const USAGE: &str = "Usage: midas-cli --format <log_format.h> [--python <command>] [--timeout <seconds>] [--source] --out <file.csv> <file.launch>...";
+
+struct Args {
+    format: PathBuf,
+    python: OsString,
+    timeout: Duration,
+    out: PathBuf,
+    source: bool,
+    inputs: Vec<PathBuf>
+}
+
+/// The arguments, or `None` if help was asked for.
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut format = None;
+    let mut python = OsString::from("python");
+    let mut timeout = DEFAULT_PYTHON_TIMEOUT;
+    let mut out = None;
+    let mut source = false;
+    let mut inputs = vec![];
+
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--format") => format = Some(PathBuf::from(args.next().ok_or("Missing value for --format.")?)),
+            Some("--python") => python = args.next().ok_or("Missing value for --python.")?,
+            Some("--timeout") => {
+                let seconds = args.next().ok_or("Missing value for --timeout.")?;
+                let seconds = seconds.to_str().and_then(|s| s.parse::<u64>().ok()).ok_or("--timeout must be a whole number of seconds.")?;
+                timeout = Duration::from_secs(seconds);
+            }
+            Some("--out") => out = Some(PathBuf::from(args.next().ok_or("Missing value for --out.")?)),
+            Some("--source") => source = true,
+            Some("-h" | "--help") => return Ok(None),
+            _ => inputs.push(PathBuf::from(arg))
+        }
+    }
+
+    let format = format.ok_or(format!("Missing --format.\n{}", USAGE))?;
+    let out = out.ok_or(format!("Missing --out.\n{}", USAGE))?;
+    if inputs.is_empty() {
+        return Err(format!("No input files.\n{}", USAGE));
+    }
+
+    Ok(Some(Args { format, python, timeout, out, source, inputs }))
+}
+
+/// Prints the percentage done to stderr each time it goes up.
+fn report_progress(label: &str, last_percent: &mut Option<u64>, done: u64, total: u64) {
+    let percent = (done * 100).checked_div(total).map_or(100, |percent| percent.min(100));
+    if *last_percent != Some(percent) {
+        *last_percent = Some(percent);
+        eprint!("\r{}: {}%", label, percent);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let format = LogFormat::from_file(&args.format, &args.python, args.timeout, |_| true).map_err(|e| e.to_string())?;
+    eprintln!("Loaded format 0x{:0>8x}", format.checksum);
+
+    let mut files = vec![];
+    for input in &args.inputs {
+        let file = BufReader::new(File::open(input).map_err(|e| format!("Could not open {}: {}", input.display(), e))?);
+        let size = file.get_ref().metadata().map_or(0, |m| m.len());
+        files.push((file, Some(size)));
+    }
+
+    // Each file is read on a thread of its own, with their progress reported together.
+    let total_size = files.iter().filter_map(|&(_, size)| size).sum();
+    let label = format!("Reading {} files", files.len());
+    let mut last_percent = None;
+    let results = format.read_files(files, ReadOptions::default(), |bytes_read| {
+        report_progress(&label, &mut last_percent, bytes_read, total_size);
+    });
+    eprintln!();
+
+    let mut frames = vec![];
+    for (file_number, (input, result)) in args.inputs.iter().zip(results).enumerate() {
+        let label = input.display().to_string();
+        let (frame, summary) = result.map_err(|e| format!("Could not read {}: {}", label, e))?;
+
+        if summary.checksum != format.checksum {
+            eprintln!("{}: written with format 0x{:0>8x}, but the loaded format is 0x{:0>8x}", label, summary.checksum, format.checksum);
+        }
+        if !summary.resync_events.is_empty() {
+            eprintln!("{}: {} bytes skipped across {} gaps", label, summary.bytes_skipped(), summary.resync_events.len());
+        }
+        if summary.crc_failures > 0 {
+            eprintln!("{}: {} packets left out for failing their CRC", label, summary.crc_failures);
+        }
+        if summary.large_timestamps > 0 {
+            eprintln!("{}: {} timestamps too large to show were left empty", label, summary.large_timestamps);
+        }
+
+        if args.source {
+            let name = input.file_name().map_or(label.clone(), |name| name.to_string_lossy().into_owned());
+            eprintln!("{}: file number {}", name, file_number);
+            frames.push(with_source_columns(&frame, file_number, &name));
+        } else {
+            frames.push(frame);
+        }
+    }
+
+    let data = DataFrameView::concat(&frames)?;
+
+    let out_label = args.out.display().to_string();
+    let mut out = BufWriter::new(File::create(&args.out).map_err(|e| format!("Could not create {}: {}", out_label, e))?);
+    let total_rows = data.shape().rows as u64;
+    let mut last_percent = None;
+    data.to_csv(&mut out, true, |row_idx| {
+        report_progress(&out_label, &mut last_percent, row_idx as u64 + 1, total_rows);
+    }).and_then(|()| out.flush()).map_err(|e| format!("\nCould not write {}: {}", out_label, e))?;
+    eprintln!();
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_args().and_then(|args| match args {
+        Some(args) => run(args),
+        None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            ExitCode::FAILURE
+        }
+    }
+}
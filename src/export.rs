@@ -2,22 +2,55 @@ use std::fs::{File, OpenOptions};
 use std::{io, io::Write};
 use std::io::BufWriter;
 use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
 
+use arrow_array::{ArrayRef, Float32Array, Int32Array, RecordBatch, StringArray, UInt32Array};
+use arrow_schema::{DataType as ArrowType, Field, Schema};
 use egui::{Color32, Ui};
 use eframe::Storage;
+use parquet::arrow::ArrowWriter;
+use serde_json::{Number, Value};
 
-use crate::{DataShared, ProgressTask};
+use dataframe::{Data, DataFrameView, DataType};
+
+use crate::{DataShared, Progress, ProgressTask, VisibleRange};
 use crate::file_picker::FilePicker;
 
+const PARQUET_BATCH_ROWS: usize = 65536;
+
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum ExportFormats {
-    Csv
+    Csv,
+    Parquet,
+    Json
 }
 
 struct CsvExport {
     path: String,
     append_mode: bool,
+    /// Whether to follow each column name in the header with its type, so it is read back as is.
+    typed_header: bool,
+
+    post_process: bool,
+    script_path: String,
+    python_command: String,
+
+    export: Option<ProgressTask<Result<(), io::Error>>>,
+    msg: Option<String>
+}
+
+struct ParquetExport {
+    path: String,
+
+    export: Option<ProgressTask<Result<(), io::Error>>>,
+    msg: Option<String>
+}
+
+struct JsonExport {
+    path: String,
+    ndjson: bool,
 
     export: Option<ProgressTask<Result<(), io::Error>>>,
     msg: Option<String>
@@ -25,16 +58,43 @@ struct CsvExport {
 
 pub struct ExportTab {
     export: ExportFormats,
-    csv: CsvExport
+    /// Whether to export only the rows within the x range last shown in the plot.
+    visible_only: bool,
+    csv: CsvExport,
+    parquet: ParquetExport,
+    json: JsonExport
 }
 
 impl ExportTab {
-    pub fn new(_cc: &eframe::CreationContext) -> ExportTab {
+    pub fn new(cc: &eframe::CreationContext) -> ExportTab {
+        let post_process = cc.storage.and_then(|storage| storage.get_string("export-post-process")).is_some_and(|s| s == "true");
+        let script_path = cc.storage.and_then(|storage| storage.get_string("export-script-path")).unwrap_or("".to_string());
+        let python_command = cc.storage.and_then(|storage| storage.get_string("export-python-command")).unwrap_or("python".to_string());
+
         ExportTab {
             export: ExportFormats::Csv,
+            visible_only: false,
             csv: CsvExport {
                 path: String::new(),
                 append_mode: false,
+                typed_header: false,
+
+                post_process,
+                script_path,
+                python_command,
+
+                export: None,
+                msg: None
+            },
+            parquet: ParquetExport {
+                path: String::new(),
+
+                export: None,
+                msg: None
+            },
+            json: JsonExport {
+                path: String::new(),
+                ndjson: true,
 
                 export: None,
                 msg: None
@@ -42,9 +102,13 @@ impl ExportTab {
         }
     }
 
-    pub fn save(&self, _storage: &mut dyn Storage) { }
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set_string("export-post-process", self.csv.post_process.to_string());
+        storage.set_string("export-script-path", self.csv.script_path.clone());
+        storage.set_string("export-python-command", self.csv.python_command.clone());
+    }
 
-    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
+    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>, visible_range: Option<&VisibleRange>) {
         ui.add_space(3.0);
 
         if let Some(csv_export) = &self.csv.export {
@@ -59,6 +123,45 @@ impl ExportTab {
             }
         }
 
+        if let Some(parquet_export) = &self.parquet.export {
+            if parquet_export.is_finished() {
+                let result = self.parquet.export.take().unwrap().handle.join().unwrap();
+                if let Err(e) = result {
+                    self.parquet.msg = Some(e.to_string());
+                }
+            }
+        }
+
+        if let Some(json_export) = &self.json.export {
+            if json_export.is_finished() {
+                let result = self.json.export.take().unwrap().handle.join().unwrap();
+                if let Err(e) = result {
+                    self.json.msg = Some(e.to_string());
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Format:");
+            ui.selectable_value(&mut self.export, ExportFormats::Csv, ".csv File");
+            ui.selectable_value(&mut self.export, ExportFormats::Parquet, ".parquet File");
+            ui.selectable_value(&mut self.export, ExportFormats::Json, ".json File");
+        });
+
+        // The columns may have changed since the plot was drawn.
+        let visible_range = visible_range.filter(|range| range.x_idx.map_or(true, |x_idx| {
+            shared.as_ref().is_some_and(|shared| x_idx < shared.shown_data.shape().cols && shared.shown_data.col_name(x_idx) == range.x_name)
+        }));
+        ui.horizontal(|ui| {
+            ui.add_enabled(visible_range.is_some(), egui::Checkbox::new(&mut self.visible_only, "Only the Plotted Range"))
+                .on_hover_text("Export the rows within the range of X values last shown in the line or scatter plot.")
+                .on_disabled_hover_text("Show the data as a line or scatter plot first.");
+            if let Some(range) = visible_range.filter(|_| self.visible_only) {
+                ui.label(format!("{} from {:.3} to {:.3}", range.x_name, range.min, range.max));
+            }
+        });
+        let visible_range = visible_range.filter(|_| self.visible_only);
+
         match self.export {
             ExportFormats::Csv => {
                 ui.horizontal(|ui| {
@@ -71,8 +174,22 @@ impl ExportTab {
 
                 ui.horizontal(|ui| {
                     ui.checkbox(&mut self.csv.append_mode, "Append");
+                    ui.add_enabled(!self.csv.append_mode, egui::Checkbox::new(&mut self.csv.typed_header, "Column Types in Header"))
+                        .on_hover_text("Write each column name as 'name:type', so the columns keep their types when the file is imported again.")
+                        .on_disabled_hover_text("Appended rows are written without a header.");
                 });
 
+                ui.checkbox(&mut self.csv.post_process, "Post-process with Python");
+                if self.csv.post_process {
+                    ui.add(FilePicker::new("csv-script-picker", &mut self.csv.script_path)
+                        .dialog_title("Post-process Script")
+                        .add_filter("Python", &["py"]));
+                    ui.horizontal(|ui| {
+                        ui.label("Python Command:");
+                        ui.text_edit_singleline(&mut self.csv.python_command);
+                    });
+                }
+
                 ui.horizontal(|ui| {
                     if let Some(export) = &self.csv.export {
                             ui.add_enabled(false, egui::Button::new("Exporting"));
@@ -82,46 +199,43 @@ impl ExportTab {
                         if ui.button("Export").clicked() {
                             self.csv.msg = None;
 
-                            let data = shared.as_ref().unwrap().shown_data.clone();
+                            let data = rows_to_export(shared.as_ref().unwrap(), visible_range);
+                            let column_order = shared.as_ref().unwrap().column_order.clone();
                             let path = PathBuf::from(self.csv.path.clone());
                             let is_append = self.csv.append_mode;
+                            let typed_header = self.csv.typed_header;
+                            let post_process = self.csv.post_process.then(|| (self.csv.python_command.clone(), PathBuf::from(self.csv.script_path.clone())));
 
                             self.csv.export = Some(ProgressTask::new(ui.ctx(), move |progress| {
-                                let mut file;
-                                if is_append {
-                                    file = BufWriter::new(OpenOptions::new().write(true).append(true).open(&path)?);
+                                let mut file = if is_append {
+                                    BufWriter::new(OpenOptions::new().append(true).open(&path)?)
                                 } else {
-                                    file = BufWriter::new(File::create(&path)?);
+                                    BufWriter::new(File::create(&path)?)
+                                };
 
-                                    let mut col_iterator = data.col_names();
-                                    if let Some(name) = col_iterator.next() {
-                                        write!(&mut file, "{}", name)?;
+                                let total_rows = data.shape().rows;
+                                let on_row = |idx| progress.set(idx as f32 / total_rows as f32);
+                                if typed_header && !is_append {
+                                    data.to_csv_typed(&mut file, &column_order, on_row)?;
+                                } else {
+                                    data.to_csv_columns(&mut file, !is_append, &column_order, on_row)?;
+                                }
 
-                                        while let Some(name) = col_iterator.next() {
-                                            write!(&mut file, ",{}", name)?;
-                                        }
+                                file.flush()?;
+                                drop(file);
 
-                                        file.write(&[b'\n'])?;
-                                    }
-                                }
+                                if let Some((python, script_path)) = post_process {
+                                    let output = Command::new(python)
+                                        .arg(&script_path)
+                                        .arg(&path)
+                                        .output()
+                                        .map_err(|e| io::Error::other(format!("Could not run python: {}", e)))?;
 
-                                let total_rows = data.shape().rows;
-                                for idx in 0..total_rows {
-                                    let mut row_iterator = data.row(idx).iter();
-                                    if let Some(data) = row_iterator.next() {
-                                        write!(&mut file, "{}", data)?;
-
-                                        while let Some(data) = row_iterator.next() {
-                                            write!(&mut file, ",{}", data)?;
-                                        }
+                                    if !output.status.success() {
+                                        return Err(io::Error::other(format!("Script Error: {}", String::from_utf8_lossy(&output.stderr))));
                                     }
-                                    file.write(&[b'\n'])?;
-
-                                    progress.set(idx as f32 / total_rows as f32);
                                 }
 
-                                file.flush()?;
-
                                 Ok(())
                             }));
                         }
@@ -132,6 +246,335 @@ impl ExportTab {
                     }
                 });
             }
+            ExportFormats::Parquet => {
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    ui.add(FilePicker::new("parquet-picker", &mut self.parquet.path)
+                        .add_filter("Parquet", &["parquet"])
+                        .set_is_save(true)
+                        .dialog_title("Save"));
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(export) = &self.parquet.export {
+                        ui.add_enabled(false, egui::Button::new("Exporting"));
+
+                        ui.add(egui::ProgressBar::new(export.progress()).show_percentage());
+                    } else {
+                        if ui.button("Export").clicked() {
+                            self.parquet.msg = None;
+
+                            let data = rows_to_export(shared.as_ref().unwrap(), visible_range);
+                            let column_order = shared.as_ref().unwrap().column_order.clone();
+                            let path = PathBuf::from(self.parquet.path.clone());
+
+                            self.parquet.export = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                                let file = BufWriter::new(File::create(&path)?);
+                                write_parquet(&data, &column_order, file, progress)
+                            }));
+                        }
+
+                        if let Some(msg) = &self.parquet.msg {
+                            ui.colored_label(Color32::RED, msg);
+                        }
+                    }
+                });
+            }
+            ExportFormats::Json => {
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    ui.add(FilePicker::new("json-picker", &mut self.json.path)
+                        .add_filter("JSON", &["json", "ndjson", "jsonl"])
+                        .set_is_save(true)
+                        .dialog_title("Save"));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Layout:");
+                    ui.selectable_value(&mut self.json.ndjson, true, "One Object per Line")
+                        .on_hover_text("Newline-delimited JSON, with each row as an object on its own line.");
+                    ui.selectable_value(&mut self.json.ndjson, false, "Array")
+                        .on_hover_text("A single JSON array holding an object for each row.");
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(export) = &self.json.export {
+                        ui.add_enabled(false, egui::Button::new("Exporting"));
+
+                        ui.add(egui::ProgressBar::new(export.progress()).show_percentage());
+                    } else {
+                        if ui.button("Export").clicked() {
+                            self.json.msg = None;
+
+                            let data = rows_to_export(shared.as_ref().unwrap(), visible_range);
+                            let column_order = shared.as_ref().unwrap().column_order.clone();
+                            let path = PathBuf::from(self.json.path.clone());
+                            let ndjson = self.json.ndjson;
+
+                            self.json.export = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                                let mut file = BufWriter::new(File::create(&path)?);
+                                write_json(&data, &column_order, &mut file, ndjson, progress)?;
+                                file.flush()
+                            }));
+                        }
+
+                        if let Some(msg) = &self.json.msg {
+                            ui.colored_label(Color32::RED, msg);
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// The shown rows, or only those with an x value within `visible_range` if there is one. Rows are
+/// compared the same way they are plotted, so the rows without a numeric x value are left out.
+fn rows_to_export(shared: &DataShared, visible_range: Option<&VisibleRange>) -> DataFrameView {
+    let mut data = shared.shown_data.clone();
+    if let Some(range) = visible_range {
+        let in_range = |x: f64| (range.min..=range.max).contains(&x);
+        match range.x_idx {
+            Some(x_idx) => data.filter_by(x_idx, |_, x| x.as_float().is_some_and(|x| in_range(x as f64))),
+            None => data.filter_by_row(|row_idx, _| in_range(row_idx as f64))
+        }
+    }
+    data
+}
+
+/// Writes the columns `cols`, in that order, as a Parquet file.
+fn write_parquet(data: &DataFrameView, cols: &[usize], file: impl Write + Send, progress: &Progress) -> io::Result<()> {
+    let shape = data.shape();
+
+    let fields: Vec<Field> = cols.iter().map(|&col_idx| {
+        let ty = match data.df.col(col_idx).data_type() {
+            DataType::Integer => ArrowType::Int32,
+            DataType::Unsigned => ArrowType::UInt32,
+            DataType::Float => ArrowType::Float32,
+            DataType::Intern => ArrowType::Utf8
+        };
+        Field::new(data.col_name(col_idx), ty, true)
+    }).collect();
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), None).map_err(io::Error::other)?;
+
+    for start in (0..shape.rows).step_by(PARQUET_BATCH_ROWS) {
+        let end = (start + PARQUET_BATCH_ROWS).min(shape.rows);
+
+        let columns: Vec<ArrayRef> = cols.iter().map(|&col_idx| {
+            let col = data.col(col_idx);
+            let array: ArrayRef = match data.df.col(col_idx).data_type() {
+                DataType::Integer => {
+                    Arc::new((start..end).map(|row_idx| col.get_row(row_idx).as_integer()).collect::<Int32Array>())
+                }
+                DataType::Unsigned => {
+                    Arc::new((start..end).map(|row_idx| match col.get_row(row_idx) {
+                        Data::Unsigned(num) => Some(num),
+                        _ => None
+                    }).collect::<UInt32Array>())
+                }
+                DataType::Float => {
+                    Arc::new((start..end).map(|row_idx| col.get_row(row_idx).as_float()).collect::<Float32Array>())
+                }
+                DataType::Intern => {
+                    Arc::new((start..end).map(|row_idx| match col.get_row(row_idx) {
+                        Data::Str(s) => Some(s),
+                        _ => None
+                    }).collect::<StringArray>())
+                }
+            };
+            array
+        }).collect();
+
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+
+        progress.set(end as f32 / shape.rows as f32);
+    }
+
+    writer.close().map_err(io::Error::other)?;
+
+    Ok(())
+}
+
+/// Writes each row as a JSON object keyed by column name, either one per line or all in one array.
+/// The keys are the columns `cols`, in that order.
+fn write_json(data: &DataFrameView, cols: &[usize], mut file: impl Write, ndjson: bool, progress: &Progress) -> io::Result<()> {
+    let shape = data.shape();
+
+    if !ndjson {
+        file.write_all(b"[")?;
+    }
+
+    for (row_idx, row) in data.iter_rows().enumerate() {
+        let object = cols.iter().map(|&col_idx| {
+            let value = match row.get_col(col_idx) {
+                Data::Null => Value::Null,
+                Data::Integer(num) => Value::from(num),
+                Data::Unsigned(num) => Value::from(num),
+                // Going through the shortest decimal form keeps e.g. 0.1 from being written as
+                // 0.10000000149011612. Non-finite floats have no JSON representation.
+                Data::Float(num) => Number::from_f64(num.to_string().parse().unwrap()).map_or(Value::Null, Value::Number),
+                Data::Str(s) => Value::from(s)
+            };
+            (data.col_name(col_idx), value)
+        });
+
+        if !ndjson && row_idx > 0 {
+            file.write_all(b",")?;
+        }
+        if !ndjson {
+            file.write_all(b"\n")?;
+        }
+        // Written by hand, since a JSON map would put the keys in alphabetical order.
+        file.write_all(b"{")?;
+        for (i, (name, value)) in object.enumerate() {
+            if i > 0 {
+                file.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut file, name)?;
+            file.write_all(b":")?;
+            serde_json::to_writer(&mut file, &value)?;
+        }
+        file.write_all(b"}")?;
+        if ndjson {
+            file.write_all(b"\n")?;
+        }
+
+        if row_idx % 3000 == 0 {
+            progress.set(row_idx as f32 / shape.rows as f32);
         }
     }
+
+    if !ndjson {
+        file.write_all(b"\n]\n")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use arrow_array::{Array, Float32Array, Int32Array, StringArray, UInt32Array};
+    use arrow_schema::DataType as ArrowType;
+    use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::{json, Value};
+
+    use crate::{DataShared, Progress, VisibleRange};
+    use super::{rows_to_export, write_json, write_parquet};
+
+    /// A column of each type, with a null in a different row of each.
+    fn frame() -> DataFrameView {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("integer", DataType::Integer);
+        builder.add_column("unsigned", DataType::Unsigned);
+        builder.add_column("float", DataType::Float);
+        builder.add_column("sensor", DataType::Intern);
+        let mut df = builder.build();
+        df.add_row(&[Data::Integer(-5), Data::Unsigned(4_000_000_000), Data::Float(0.5), Data::Null]);
+        df.add_row(&[Data::Null, Data::Unsigned(7), Data::Float(-1.25), Data::Str("gps")]);
+        df.add_row(&[Data::Integer(12), Data::Null, Data::Null, Data::Str("imu")]);
+        DataFrameView::from_dataframe(df)
+    }
+
+    #[test]
+    fn writes_parquet_that_reads_back_with_the_same_types_and_values() {
+        let path = std::env::temp_dir().join(format!("midas-launch-{}-export.parquet", std::process::id()));
+        write_parquet(&frame(), &[3, 0, 1, 2], File::create(&path).unwrap(), &Progress::detached()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap().build().unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        let schema = batch.schema();
+        let fields = schema.fields().iter().map(|field| (field.name().as_str(), field.data_type().clone(), field.is_nullable())).collect::<Vec<_>>();
+        assert_eq!(fields, [
+            ("sensor", ArrowType::Utf8, true),
+            ("integer", ArrowType::Int32, true),
+            ("unsigned", ArrowType::UInt32, true),
+            ("float", ArrowType::Float32, true)
+        ]);
+
+        let column = |idx: usize| batch.column(idx).as_any();
+        let sensor = column(0).downcast_ref::<StringArray>().unwrap();
+        assert_eq!(sensor.iter().collect::<Vec<_>>(), [None, Some("gps"), Some("imu")]);
+        let integer = column(1).downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(integer.iter().collect::<Vec<_>>(), [Some(-5), None, Some(12)]);
+        let unsigned = column(2).downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(unsigned.iter().collect::<Vec<_>>(), [Some(4_000_000_000), Some(7), None]);
+        let float = column(3).downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(float.iter().collect::<Vec<_>>(), [Some(0.5), Some(-1.25), None]);
+        assert_eq!(float.null_count(), 1);
+    }
+
+    #[test]
+    fn writes_json_rows_with_their_types_and_nulls_in_either_layout() {
+        let mut df = frame();
+        df.set_by_index(2, 1, Data::Float(f32::NAN));
+        let expected = [
+            json!({"sensor": null, "integer": -5, "unsigned": 4_000_000_000u32, "float": 0.5}),
+            json!({"sensor": "gps", "integer": null, "unsigned": 7, "float": null}),
+            json!({"sensor": "imu", "integer": 12, "unsigned": null, "float": null})
+        ];
+
+        let mut ndjson = vec![];
+        write_json(&df, &[3, 0, 1, 2], &mut ndjson, true, &Progress::detached()).unwrap();
+        let ndjson = String::from_utf8(ndjson).unwrap();
+        let lines = ndjson.lines().map(|line| serde_json::from_str::<Value>(line).unwrap()).collect::<Vec<_>>();
+        assert_eq!(lines, expected);
+        // The keys are in the order of the columns rather than alphabetical.
+        assert!(ndjson.starts_with(r#"{"sensor":null,"integer":-5,"unsigned":4000000000,"float":0.5}"#));
+
+        let mut array = vec![];
+        write_json(&df, &[3, 0, 1, 2], &mut array, false, &Progress::detached()).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&array).unwrap(), Value::Array(expected.to_vec()));
+    }
+
+    #[test]
+    fn writes_csv_columns_in_the_order_they_are_shown() {
+        let mut shared = DataShared::new(frame());
+        // Moves 'sensor' to the front and 'integer' to the end.
+        shared.move_column(3, 0);
+        shared.move_column(1, 3);
+        assert_eq!(shared.column_order, [3, 1, 2, 0]);
+
+        let mut csv = vec![];
+        shared.shown_data.to_csv_columns(&mut csv, true, &shared.column_order, |_| {}).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "sensor,unsigned,float,integer\n,4000000000,0.5,-5\ngps,7,-1.25,\nimu,,,12\n");
+
+        // Appending to a file leaves out the header.
+        let mut csv = vec![];
+        shared.shown_data.to_csv_columns(&mut csv, false, &shared.column_order[..2], |_| {}).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), ",4000000000\ngps,7\nimu,\n");
+    }
+
+    #[test]
+    fn exports_only_the_rows_in_the_visible_range() {
+        let mut shared = DataShared::new(frame());
+        let mut shown = frame();
+        shown.filter_by(2, |_, value| !value.is_null());
+        shared.show(shown);
+        let integers = |data: &DataFrameView| data.col(0).iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>();
+
+        assert_eq!(integers(&rows_to_export(&shared, None)), ["Integer(-5)", "Null"]);
+
+        // Against the float column, rows outside the range or without an x value are left out.
+        let range = VisibleRange { x_idx: Some(2), x_name: "float".to_string(), min: 0.0, max: 1.0 };
+        assert_eq!(integers(&rows_to_export(&shared, Some(&range))), ["Integer(-5)"]);
+        let range = VisibleRange { x_idx: Some(0), x_name: "integer".to_string(), min: -10.0, max: 20.0 };
+        assert_eq!(integers(&rows_to_export(&shared, Some(&range))), ["Integer(-5)"]);
+
+        // Against the row number, which counts the shown rows.
+        let range = VisibleRange { x_idx: None, x_name: "row".to_string(), min: 0.5, max: 1.0 };
+        let exported = rows_to_export(&shared, Some(&range));
+        assert_eq!(integers(&exported), ["Null"]);
+        assert_eq!(exported.col(3).iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>(), ["Str(\"gps\")"]);
+    }
 }
+
@@ -1,30 +1,75 @@
-use std::fs::File;
-use std::{io, io::{BufReader, Read}};
-use std::path::PathBuf;
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::{fs, fs::File};
+use std::{io, io::{BufRead, BufReader, Read, Seek, SeekFrom}};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, Weak, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::JoinHandle;
 use std::time::Duration;
 
 use egui::{Color32, Ui};
 use eframe::Storage;
+use flate2::bufread::MultiGzDecoder;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 
-use launch_file::LogFormat;
-use dataframe::DataFrameView;
+use launch_file::{DEFAULT_PYTHON_TIMEOUT, GUESS_LAYOUT_BYTES, LogFormat, NonFinite, PacketReader, LargeUnsigned, LayoutGuess, FormatSize, ReadOptions, ReadSummary, ValidationReport, with_source_columns};
+use dataframe::{CsvOptions, Data, DataFrame, DataFrameView, DataType};
 
-use crate::DataShared;
+use crate::{DataShared, column_combo, drop_in_background};
 use crate::ProgressTask;
 use crate::file_picker::FilePicker;
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum ImportFrom {
     Launch,
-    CSV
+    Csv
 }
 
+impl ImportFrom {
+    /// The import that opens `path`, by its extension. Gzipped files go by the extension before
+    /// `.gz`, so one without `.launch` or `.csv` there can't be opened either.
+    fn for_path(path: &Path) -> Option<ImportFrom> {
+        let extension_of = |path: &Path| path.extension().and_then(OsStr::to_str).map(str::to_ascii_lowercase);
+        let mut extension = extension_of(path)?;
+        if extension == "gz" {
+            extension = extension_of(Path::new(path.file_stem()?))?;
+        }
+        match extension.as_str() {
+            "launch" => Some(ImportFrom::Launch),
+            "csv" => Some(ImportFrom::Csv),
+            _ => None
+        }
+    }
+}
+
+/// The frame a column's type was changed in, the data with the new type, and how many values came
+/// out different.
+type Retyped = (Arc<DataFrame>, DataFrameView, usize);
+/// The version of the shown data, the frame it is a view of, and how many rows it has.
+type NullCountsKey = (u64, Weak<DataFrame>, usize);
+
 pub struct ImportTab {
     state: ImportFrom,
 
     import_launch_tab: ImportLaunchTab,
-    import_csv_tab: ImportCSVTab
+    import_csv_tab: ImportCsvTab,
+    /// Whether loaded data is added after the rows of the data loaded before, instead of replacing it.
+    append: bool,
+    /// The files of the last drop onto the window that weren't opened, and why.
+    drop_message: Option<String>,
+
+    /// The column picked in the 'Column Types' editor, and the type to change it to.
+    retype_col: usize,
+    retype_to: DataType,
+    retyping: Option<ProgressTask<Retyped>>,
+    /// A change of type that lost values, waiting to be confirmed.
+    pending_retype: Option<Retyped>,
+
+    /// The empty cells in each column of the shown data, for the data they were counted in.
+    null_counts: Option<(NullCountsKey, Vec<usize>)>,
+    counting_nulls: Option<(NullCountsKey, ProgressTask<Vec<usize>>)>
 }
 
 impl ImportTab {
@@ -32,7 +77,17 @@ impl ImportTab {
         Self {
             state: ImportFrom::Launch,
             import_launch_tab: ImportLaunchTab::new(cc),
-            import_csv_tab: ImportCSVTab::new(cc)
+            import_csv_tab: ImportCsvTab::new(cc),
+            append: false,
+            drop_message: None,
+
+            retype_col: 0,
+            retype_to: DataType::Intern,
+            retyping: None,
+            pending_retype: None,
+
+            null_counts: None,
+            counting_nulls: None
         }
     }
 
@@ -41,42 +96,347 @@ impl ImportTab {
         self.import_csv_tab.save(storage);
     }
 
+    /// Opens files dropped onto the window in the import for their type, and shows the import of the
+    /// first one. Only one file of each type can be opened at a time, so later ones are left out.
+    pub fn open_dropped(&mut self, paths: &[PathBuf]) {
+        let mut opened = vec![];
+        let mut skipped = vec![];
+        for path in paths {
+            match ImportFrom::for_path(path) {
+                Some(from) if !opened.contains(&from) => {
+                    match from {
+                        ImportFrom::Launch => self.import_launch_tab.open(path),
+                        ImportFrom::Csv => self.import_csv_tab.open(path)
+                    }
+                    if opened.is_empty() {
+                        self.state = from;
+                    }
+                    opened.push(from);
+                }
+                Some(_) => skipped.push(format!("{}, since another file of its type was dropped with it", file_name(&path.to_string_lossy()))),
+                None => skipped.push(format!("{}, since only .launch and .csv files can be imported", file_name(&path.to_string_lossy())))
+            }
+        }
+        self.drop_message = (!skipped.is_empty()).then(|| format!("Not opened: {}.", skipped.join("; ")));
+    }
+
+    /// Appends the rows read from a followed file since the last frame with `grow`, which is given
+    /// the loaded data and the rows. Called every frame, whichever tab is open.
+    pub fn follow(&mut self, shared: &mut Option<DataShared>, grow: impl FnMut(&mut DataShared, &DataFrameView) -> Result<(), String>) {
+        self.import_launch_tab.follow(shared, grow);
+    }
+
     pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
         // ui.columns(2, |cols| {
         //     cols[0].vertical_centered_justified(|ui| {
         //         ui.selectable_value(&mut self.state, ImportFrom::Launch, ".launch File");
         //     });
         //     cols[1].vertical_centered_justified(|ui| {
-        //         ui.selectable_value(&mut self.state, ImportFrom::CSV, ".csv File");
+        //         ui.selectable_value(&mut self.state, ImportFrom::Csv, ".csv File");
         //     });
         // });
         ui.horizontal(|ui| {
             ui.label("Source type:");
             ui.selectable_value(&mut self.state, ImportFrom::Launch, ".launch File");
-            ui.selectable_value(&mut self.state, ImportFrom::CSV, ".csv File");
+            ui.selectable_value(&mut self.state, ImportFrom::Csv, ".csv File");
         });
+        if let Some(msg) = &self.drop_message {
+            ui.colored_label(ui.visuals().warn_fg_color, msg);
+        }
+
+        ui.add_enabled(shared.is_some(), egui::Checkbox::new(&mut self.append, "Append to Loaded Data"))
+            .on_hover_text("Add the rows of the next file after those already loaded, such as another log of the same flight. Its columns have to match.")
+            .on_disabled_hover_text("No data is loaded yet.");
+        let append = self.append && shared.is_some();
 
         match self.state {
-            ImportFrom::Launch => self.import_launch_tab.show(ui, shared),
-            ImportFrom::CSV => self.import_csv_tab.show(ui, shared)
+            ImportFrom::Launch => self.import_launch_tab.show(ui, shared, append),
+            ImportFrom::Csv => self.import_csv_tab.show(ui, shared, append)
+        }
+
+        if shared.is_some() {
+            ui.add_space(3.0);
+            egui::CollapsingHeader::new("Column Types").id_source("column-types-header").show(ui, |ui| {
+                self.show_column_types(ui, shared);
+            });
+        }
+        if let Some(shared) = shared {
+            egui::CollapsingHeader::new("Completeness").id_source("completeness-header").show(ui, |ui| {
+                self.show_completeness(ui, shared);
+            });
+        }
+    }
+
+    /// Shows how full each column of the shown data is, so that sparse columns, such as those only
+    /// filled by a rare packet, stand out. The cells are counted again whenever the data changes.
+    fn show_completeness(&mut self, ui: &mut Ui, shared: &DataShared) {
+        let data = &shared.shown_data;
+        let rows = data.shape().rows;
+        let is_current = |(version, df, key_rows): &NullCountsKey| *version == shared.version && df.ptr_eq(&Arc::downgrade(&data.df)) && *key_rows == rows;
+
+        if let Some((_, task)) = &self.counting_nulls {
+            if task.is_finished() {
+                let (key, task) = self.counting_nulls.take().unwrap();
+                self.null_counts = Some((key, task.handle.join().unwrap()));
+            }
+        }
+        if !self.null_counts.as_ref().is_some_and(|(key, _)| is_current(key)) && !self.counting_nulls.as_ref().is_some_and(|(key, _)| is_current(key)) {
+            let data = data.clone();
+            let key = (shared.version, Arc::downgrade(&data.df), rows);
+            self.counting_nulls = Some((key, ProgressTask::new(ui.ctx(), move |progress| {
+                const CHUNK: usize = 100_000;
+                let mut counts = vec![0; data.shape().cols];
+                for start in (0..rows).step_by(CHUNK) {
+                    progress.set(start as f32 / rows as f32);
+                    for (count, chunk_count) in counts.iter_mut().zip(data.null_counts_in(start..(start + CHUNK).min(rows))) {
+                        *count += chunk_count;
+                    }
+                }
+                counts
+            })));
+        }
+
+        match (&self.counting_nulls, &self.null_counts) {
+            (Some((_, task)), _) => {
+                ui.horizontal(|ui| {
+                    ui.label("Counting empty cells");
+                    ui.add(egui::ProgressBar::new(task.progress()).show_percentage());
+                });
+            }
+            (None, Some((_, counts))) => {
+                egui::Grid::new("completeness-grid").striped(true).show(ui, |ui| {
+                    ui.strong("Column");
+                    ui.strong("Filled");
+                    ui.strong("Empty");
+                    ui.end_row();
+
+                    for &col_idx in &shared.column_order {
+                        let empty = counts[col_idx];
+                        let filled = if rows == 0 { 0.0 } else { (rows - empty) as f32 / rows as f32 };
+                        ui.label(data.col_name(col_idx));
+                        ui.add(egui::ProgressBar::new(filled).desired_width(120.0).text(format!("{:.0}%", filled * 100.0)))
+                            .on_hover_text(format!("{} of {} rows have a value", rows - empty, rows));
+                        ui.label(empty.to_string());
+                        ui.end_row();
+                    }
+                });
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Lets the type of a loaded column be changed, such as when a numeric ID should be text.
+    /// Changing a type starts over from the loaded data.
+    fn show_column_types(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
+        if let Some(task) = &self.retyping {
+            if task.is_finished() {
+                let retyped = self.retyping.take().unwrap().handle.join().unwrap();
+                if retyped.2 == 0 {
+                    self.accept_retype(retyped, shared);
+                } else {
+                    self.pending_retype = Some(retyped);
+                }
+            }
+        }
+
+        let Some(data) = shared.as_ref().map(|shared| &shared.complete_data) else { return };
+        let cols = data.shape().cols;
+        if self.retype_col >= cols {
+            self.retype_col = 0;
+        }
+        let current_type = data.df.col(self.retype_col).data_type();
+
+        ui.horizontal(|ui| {
+            ui.label("Column:");
+            column_combo(ui, "combo-retype-col", data, &mut self.retype_col);
+            ui.label(current_type.name());
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Change To:");
+            for ty in [DataType::Integer, DataType::Unsigned, DataType::Float, DataType::Intern] {
+                ui.selectable_value(&mut self.retype_to, ty, ty.name());
+            }
+        });
+
+        let mut accepted = None;
+        ui.horizontal(|ui| {
+            if let Some(task) = &self.retyping {
+                ui.add_enabled(false, egui::Button::new("Changing"));
+                ui.add(egui::ProgressBar::new(task.progress()).show_percentage());
+            } else if let Some((_, _, changed)) = &self.pending_retype {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{} values will change or be left empty.", changed))
+                    .on_hover_text("Such as text that isn't a number, or decimals rounded to whole numbers.");
+                if ui.button("Change Anyway").clicked() {
+                    accepted = self.pending_retype.take();
+                }
+                if ui.button("Cancel").clicked() {
+                    self.pending_retype = None;
+                }
+            } else {
+                let response = ui.add_enabled(current_type != self.retype_to, egui::Button::new("Change Type"))
+                    .on_hover_text("Filters have to be applied again afterwards.");
+                if response.clicked() {
+                    let data = data.clone();
+                    let (col, ty) = (self.retype_col, self.retype_to);
+                    self.retyping = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                        let rows = data.shape().rows as f32;
+                        let (retyped, changed) = data.with_column_type(col, ty, |row_idx| {
+                            if row_idx % 3000 == 0 {
+                                progress.set(row_idx as f32 / rows);
+                            }
+                        });
+                        (data.df, retyped, changed)
+                    }));
+                }
+            }
+        });
+
+        if let Some(retyped) = accepted {
+            self.accept_retype(retyped, shared);
+        }
+    }
+
+    /// Replaces the loaded data with `retyped`, unless other data has been loaded in the meantime.
+    /// What was shown before can be gone back to with 'Undo'.
+    fn accept_retype(&self, (source, retyped, _): Retyped, shared: &mut Option<DataShared>) {
+        if let Some(shared) = shared.as_mut().filter(|shared| Arc::ptr_eq(&shared.complete_data.df, &source)) {
+            shared.replace_complete(retyped);
         }
     }
 }
 
+/// How often a followed file is checked for new packets.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The loaded data, what was found while loading it, the path it was read from, and the reader to
+/// carry on with if the file is followed. The path is kept from when loading started, since the
+/// chosen file may have been changed since.
+type LaunchLoad = (Loaded, ReadSummary, String, Option<PacketReader>);
+
+/// The result of loading a launch file, along with where its parse log was written, or why it
+/// couldn't be, if one was asked for.
+type LaunchParse = (Result<LaunchLoad, io::Error>, Option<Result<PathBuf, String>>);
+
+/// A launch file that is read again as it grows, on a thread of its own.
+struct Following {
+    /// The rows read since they were last handed over along with the summary so far, or why reading
+    /// stopped.
+    rows: mpsc::Receiver<io::Result<(DataFrameView, ReadSummary)>>,
+    stop: Arc<AtomicBool>,
+    /// The data the rows are appended to, so following stops once other data is loaded.
+    df: Weak<DataFrame>,
+    path: PathBuf
+}
+
+impl Following {
+    /// Checks `path` for new packets every [`FOLLOW_INTERVAL`], carrying on from where `reader`
+    /// left off. Only the new rows are handed over, to be appended to `df`.
+    fn start(ctx: &egui::Context, path: PathBuf, mut reader: PacketReader, df: &Arc<DataFrame>) -> Following {
+        let (sender, rows) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let ctx = ctx.clone();
+
+        let followed = path.clone();
+        std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                std::thread::sleep(FOLLOW_INTERVAL);
+
+                let rows = reader.rows();
+                let read = File::open(&path).and_then(|mut file| {
+                    file.seek(SeekFrom::Start(reader.offset()))?;
+                    reader.read(&mut BufReader::new(file), |_| {})
+                });
+                let update = match read {
+                    Ok(()) if reader.rows() == rows => continue,
+                    Ok(()) => Ok(reader.take_rows()),
+                    Err(e) => Err(e)
+                };
+
+                let failed = update.is_err();
+                let sent = sender.send(update);
+                ctx.request_repaint();
+                if failed || sent.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Following { rows, stop, df: Arc::downgrade(df), path: followed }
+    }
+}
+
+impl Drop for Following {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
 struct ImportLaunchTab {
     source_path: String,
     inspect_source_task: Option<JoinHandle<Result<u32, String>>>,
     inspected_checksum: Option<u32>,
     inspect_message: Option<String>,
+    has_start_epoch: bool,
+    start_epoch: String,
+    /// Bytes between the checksum and the first packet to skip.
+    preamble: u64,
+    big_endian: bool,
+    skip_rows: usize,
+    has_max_rows: bool,
+    max_rows: usize,
+    discriminant: bool,
+    /// Whether to add columns saying which file each row was loaded from.
+    source_columns: bool,
+    non_finite: NonFinite,
+    large_unsigned: LargeUnsigned,
+    /// Whether to keep reading packets appended to the file after it is loaded.
+    follow: bool,
+    /// Whether to write a log of the gaps and errors found while loading next to the data file.
+    write_parse_log: bool,
+    following: Option<Following>,
 
     format_path: String,
     python_command: String,
-    loading_format_task: Option<JoinHandle<Result<LogFormat, String>>>,
+    /// How many seconds Python is given to compile the format.
+    python_timeout: u64,
+    format_from_schema: bool,
+    schema_path: String,
+    loading_format_task: Option<JoinHandle<Result<(LogFormat, FormatSize), String>>>,
+    /// Set to stop the format that is loading.
+    format_cancel: Arc<AtomicBool>,
+    /// What Python has written to stderr so far while loading the format.
+    format_output: Arc<Mutex<String>>,
     loaded_format: Option<LogFormat>,
+    /// The size of the loaded format, worked out while loading it, for estimating memory.
+    loaded_format_size: Option<FormatSize>,
     format_message: Option<String>,
+    /// Where a description of the loaded format is saved.
+    schema_report_path: String,
+    /// Writes the description of the loaded format in the background.
+    exporting_schema: Option<JoinHandle<Result<(), String>>>,
+    schema_report_message: Option<String>,
 
-    parsing: Option<ProgressTask<Result<DataFrameView, io::Error>>>,
-    parsing_message: Option<String>
+    parsing: Option<ProgressTask<LaunchParse>>,
+    parsing_message: Option<String>,
+    parsing_warning: Option<String>,
+    /// Where the parse log of the last load was written, or why it couldn't be.
+    parse_log: Option<Result<PathBuf, String>>,
+    /// The estimated bytes needed to load the data file, and the file, format and options it is for.
+    /// Gzipped files aren't estimated, since their size says little about the data.
+    memory_estimate: Option<((String, u32, ReadOptions), Option<u64>)>,
+    /// The bytes taken up by the last loaded data, and how many distinct strings it holds.
+    loaded_memory: Option<(usize, usize)>,
+    /// What was found while loading the last data, such as how many packets of each type there were.
+    read_summary: Option<ReadSummary>,
+    validating: Option<ProgressTask<io::Result<ValidationReport>>>,
+    /// What was found by the last validation of the data file, or why it failed.
+    validation: Option<Result<ValidationReport, String>>,
+    guessing_layout: Option<ProgressTask<io::Result<Vec<LayoutGuess>>>>,
+    /// How each byte order and header layout fit the data file, best first, or why they couldn't be
+    /// tried.
+    layout_guesses: Option<Result<Vec<LayoutGuess>, String>>
 }
 
 impl ImportLaunchTab {
@@ -84,21 +444,59 @@ impl ImportLaunchTab {
         let source_path = cc.storage.and_then(|storage| storage.get_string("import-source-path")).unwrap_or("".to_string());
         let format_path = cc.storage.and_then(|storage| storage.get_string("import-format-path")).unwrap_or("".to_string());
         let python_command = cc.storage.and_then(|storage| storage.get_string("import-python-command")).unwrap_or("python".to_string());
+        let python_timeout = cc.storage.and_then(|storage| storage.get_string("import-python-timeout"))
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_PYTHON_TIMEOUT.as_secs());
+        let format_from_schema = cc.storage.and_then(|storage| storage.get_string("import-format-from-schema")).is_some_and(|s| s == "true");
+        let schema_path = cc.storage.and_then(|storage| storage.get_string("import-schema-path")).unwrap_or("".to_string());
+        let schema_report_path = cc.storage.and_then(|storage| storage.get_string("import-schema-report-path")).unwrap_or("".to_string());
 
         ImportLaunchTab {
             source_path,
             inspect_source_task: None,
             inspected_checksum: None,
             inspect_message: None,
+            has_start_epoch: false,
+            start_epoch: String::new(),
+            preamble: 0,
+            big_endian: false,
+            skip_rows: 0,
+            has_max_rows: false,
+            max_rows: 10000,
+            discriminant: false,
+            source_columns: false,
+            non_finite: NonFinite::Keep,
+            large_unsigned: LargeUnsigned::default(),
+            follow: false,
+            write_parse_log: false,
+            following: None,
 
             format_path,
             python_command,
+            python_timeout,
+            format_from_schema,
+            schema_path,
             loading_format_task: None,
+            format_cancel: Arc::new(AtomicBool::new(false)),
+            format_output: Arc::new(Mutex::new(String::new())),
             loaded_format: None,
+            loaded_format_size: None,
             format_message: None,
+            schema_report_path,
+            exporting_schema: None,
+            schema_report_message: None,
 
             parsing: None,
-            parsing_message: None
+            parsing_message: None,
+            parsing_warning: None,
+            parse_log: None,
+            memory_estimate: None,
+            loaded_memory: None,
+            read_summary: None,
+            validating: None,
+            validation: None,
+            guessing_layout: None,
+            layout_guesses: None
         }
     }
 
@@ -106,15 +504,87 @@ impl ImportLaunchTab {
         storage.set_string("import-source-path", self.source_path.clone());
         storage.set_string("import-format-path", self.format_path.clone());
         storage.set_string("import-python-command", self.python_command.clone());
+        storage.set_string("import-python-timeout", self.python_timeout.to_string());
+        storage.set_string("import-format-from-schema", self.format_from_schema.to_string());
+        storage.set_string("import-schema-path", self.schema_path.clone());
+        storage.set_string("import-schema-report-path", self.schema_report_path.clone());
     }
 
-    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
+    fn read_options(&self, start_epoch: Option<i32>) -> ReadOptions {
+        ReadOptions {
+            start_epoch,
+            preamble: self.preamble,
+            skip_rows: self.skip_rows,
+            max_rows: self.has_max_rows.then_some(self.max_rows),
+            discriminant: self.discriminant,
+            non_finite: self.non_finite,
+            large_unsigned: self.large_unsigned,
+            big_endian: self.big_endian
+        }
+    }
+
+    fn follow(&mut self, shared: &mut Option<DataShared>, mut grow: impl FnMut(&mut DataShared, &DataFrameView) -> Result<(), String>) {
+        let Some(following) = &mut self.following else { return };
+        let Some(shared) = shared.as_mut().filter(|shared| following.df.ptr_eq(&Arc::downgrade(&shared.complete_data.df))) else {
+            self.following = None;
+            return;
+        };
+
+        while let Ok(update) = following.rows.try_recv() {
+            let grown = update.map_err(|e| e.to_string())
+                .and_then(|(rows, summary)| grow(shared, &rows).map(|()| summary));
+            match grown {
+                Ok(summary) => {
+                    // Appending may have moved the frame.
+                    following.df = Arc::downgrade(&shared.complete_data.df);
+                    self.loaded_memory = Some((shared.complete_data.memory_bytes(), shared.complete_data.distinct_strings()));
+                    self.read_summary = Some(summary);
+                }
+                Err(e) => {
+                    self.parsing_message = Some(format!("Stopped following: {}", e));
+                    self.following = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Makes `path` the data file and reads the checksum at its start.
+    fn open(&mut self, path: &Path) {
+        self.source_path = path.to_string_lossy().into_owned();
+        self.inspected_checksum = None;
+        self.loaded_memory = None;
+        // An inspection still running is of the previous file, so its result is dropped.
+        self.inspect_source();
+    }
+
+    /// Reads the checksum of the format the data file was written with in the background.
+    fn inspect_source(&mut self) {
+        let path = PathBuf::from(self.source_path.clone());
+        let big_endian = self.big_endian;
+
+        self.inspect_message = None;
+        self.inspect_source_task = Some(std::thread::spawn(move || {
+            let (mut file, _, _) = open_data_file(&path).map_err(|_| "Could not open file.".to_string())?;
+            let mut buf = [0; 4];
+            file.read_exact(&mut buf).map_err(|_| "Could not read from file.".to_string())?;
+            Ok(if big_endian { u32::from_be_bytes(buf) } else { u32::from_le_bytes(buf) })
+        }));
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>, append: bool) {
         let data_file_header = self.inspected_checksum.map_or("Data File".to_string(), |c| format!("Data File - 0x{:0>8x}", c));
         egui::CollapsingHeader::new(data_file_header).id_source("data-file-header").default_open(true).show(ui, |ui| {
+            let previous_path = self.source_path.clone();
             ui.add(FilePicker::new("data-file-picker", &mut self.source_path)
                 .dialog_title("Data File")
                 .add_filter("Launch", &["launch"])
+                .add_filter("Gzipped Launch", &["gz"])
             );
+            if self.source_path != previous_path {
+                self.inspected_checksum = None;
+                self.loaded_memory = None;
+            }
 
             ui.horizontal(|ui| {
                 if let Some(task) = &self.inspect_source_task {
@@ -132,15 +602,7 @@ impl ImportLaunchTab {
                     let response  = ui.add_enabled(!self.source_path.is_empty(), egui::Button::new("Inspect Source"))
                         .on_disabled_hover_text("Choose source file");
                     if response.clicked() {
-                        let path = PathBuf::from(self.source_path.clone());
-
-                        self.inspect_message = None;
-                        self.inspect_source_task = Some(std::thread::spawn(move || {
-                            let mut file = File::open(&path).map_err(|_| "Could not open file.".to_string())?;
-                            let mut buf = [0; 4];
-                            file.read_exact(&mut buf).map_err(|_| "Could not read from file.".to_string())?;
-                            Ok(u32::from_le_bytes(buf))
-                        }));
+                        self.inspect_source();
                     }
                 } else {
                     ui.add_enabled(false, egui::Button::new("Inspecting Source"));
@@ -150,22 +612,97 @@ impl ImportLaunchTab {
                     ui.colored_label(Color32::RED, "!").on_hover_text(msg);
                 }
             });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.has_start_epoch, "Start Time:")
+                    .on_hover_text("Adds a 'datetime' column with the Unix time of each packet, in seconds.");
+                ui.add_enabled(self.has_start_epoch, egui::TextEdit::singleline(&mut self.start_epoch).hint_text("Unix seconds"));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Preamble:");
+                ui.add(egui::DragValue::new(&mut self.preamble).suffix(" bytes"))
+                    .on_hover_text("Skips this many bytes of metadata after the checksum, before the first packet.");
+            });
+
+            ui.checkbox(&mut self.big_endian, "Big Endian")
+                .on_hover_text("Reads numbers most significant byte first, for logs written by big endian processors.");
+
+            ui.horizontal(|ui| {
+                ui.label("Skip First:");
+                ui.add(egui::DragValue::new(&mut self.skip_rows).suffix(" packets"));
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.has_max_rows, "Load At Most:");
+                ui.add_enabled(self.has_max_rows, egui::DragValue::new(&mut self.max_rows).suffix(" packets"));
+            });
+
+            ui.checkbox(&mut self.discriminant, "Discriminant Column")
+                .on_hover_text("Adds a 'discriminant' column with the number that picked each packet's type.");
+
+            ui.checkbox(&mut self.source_columns, "Source File Columns")
+                .on_hover_text("Adds a 'file number' column, counting the files appended to each other from 0, and a 'source file' column with the name of the file each row was loaded from. Followed files don't get them.");
+
+            ui.horizontal(|ui| {
+                ui.label("NaN and Infinity:");
+                ui.selectable_value(&mut self.non_finite, NonFinite::Keep, "Keep");
+                ui.selectable_value(&mut self.non_finite, NonFinite::Null, "Empty")
+                    .on_hover_text("Leave floats that are NaN or infinite empty.");
+                ui.selectable_value(&mut self.non_finite, NonFinite::Clamp, "Clamp")
+                    .on_hover_text("Replace infinite floats with the largest or smallest float, and leave NaN empty.");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Large Unsigned:");
+                ui.selectable_value(&mut self.large_unsigned, LargeUnsigned::Saturate, "Saturate")
+                    .on_hover_text("Keep uint32_t fields as integers, and show values above 2147483647 as 2147483647.");
+                ui.selectable_value(&mut self.large_unsigned, LargeUnsigned::Float, "Float")
+                    .on_hover_text("Load uint32_t fields as floats, which keeps the size of large values but rounds them.");
+                ui.selectable_value(&mut self.large_unsigned, LargeUnsigned::Unsigned, "Unsigned")
+                    .on_hover_text("Load uint32_t fields as unsigned integers, which keeps every value exactly, except 4294967295 which is left empty.");
+            });
+
+            ui.checkbox(&mut self.follow, "Follow File")
+                .on_hover_text("Keep reading packets as they are written to the file, such as during a ground test. Gzipped files can't be followed.");
+
+            ui.checkbox(&mut self.write_parse_log, "Write Parse Log")
+                .on_hover_text("Write where each gap in the file starts and what was found there, and where loading stopped if it failed, to a '.parse.log' file next to the data file.");
         });
 
         let data_format_header = self.loaded_format.as_ref().map_or("Data Format".to_string(), |f| format!("Data Format - 0x{:0>8x}", f.checksum));
         egui::CollapsingHeader::new(data_format_header).id_source("data-format-header").default_open(true).show(ui, |ui| {
-            ui.add(FilePicker::new("data-format-picker", &mut self.format_path)
-                .dialog_title("Data Format")
-                .add_filter("C++ Header", &["h", "hpp"])
-                .add_filter("C++ Source", &["c", "cc", "cpp"])
-            );
             ui.horizontal(|ui| {
-                ui.label("Python Command:");
-                ui.text_edit_singleline(&mut self.python_command);
+                ui.label("Format type:");
+                ui.selectable_value(&mut self.format_from_schema, false, "C++ Header");
+                ui.selectable_value(&mut self.format_from_schema, true, "schema.json")
+                    .on_hover_text("A schema already produced from a C++ header, which can be loaded without Python.");
             });
 
+            if self.format_from_schema {
+                ui.add(FilePicker::new("data-schema-picker", &mut self.schema_path)
+                    .dialog_title("Schema")
+                    .add_filter("JSON", &["json"])
+                );
+            } else {
+                ui.add(FilePicker::new("data-format-picker", &mut self.format_path)
+                    .dialog_title("Data Format")
+                    .add_filter("C++ Header", &["h", "hpp"])
+                    .add_filter("C++ Source", &["c", "cc", "cpp"])
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Python Command:");
+                    ui.text_edit_singleline(&mut self.python_command);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Timeout:");
+                    ui.add(egui::DragValue::new(&mut self.python_timeout).clamp_range(1..=3600).suffix(" s"))
+                        .on_hover_text("Python is stopped if it takes longer than this to compile the format.");
+                });
+            }
+
             ui.horizontal(|ui| {
-                if ui.button("⟳").clicked() {
+                if ui.button("⟳").on_hover_text("Reset the parser scripts and clear cached formats.").clicked() {
                     std::thread::spawn(LogFormat::clear_scripts);
                 }
 
@@ -173,8 +710,10 @@ impl ImportLaunchTab {
                     if handle.is_finished() {
                         let format_res = self.loading_format_task.take().unwrap().join().unwrap();
                         match format_res {
-                            Ok(format) => {
+                            Ok((format, size)) => {
                                 self.loaded_format = Some(format);
+                                self.loaded_format_size = Some(size);
+                                self.layout_guesses = None;
                             }
                             Err(msg) => { self.format_message = Some(msg); }
                         }
@@ -183,28 +722,94 @@ impl ImportLaunchTab {
                 }
 
                 if self.loading_format_task.is_none() {
-                    let response = ui.add_enabled(!self.format_path.is_empty(), egui::Button::new("Load Format"))
+                    let path = if self.format_from_schema { &self.schema_path } else { &self.format_path };
+                    let response = ui.add_enabled(!path.is_empty(), egui::Button::new("Load Format"))
                         .on_disabled_hover_text("Choose format file.");
                     if response.clicked() {
                         self.format_message = None;
 
                         let python = PathBuf::from(self.python_command.clone());
-                        let path = PathBuf::from(self.format_path.clone());
+                        let path = PathBuf::from(path.clone());
+                        let from_schema = self.format_from_schema;
+                        let timeout = Duration::from_secs(self.python_timeout);
                         let ctx_clone = ui.ctx().clone();
+                        self.format_cancel = Arc::new(AtomicBool::new(false));
+                        self.format_output = Arc::new(Mutex::new(String::new()));
+                        let cancel = self.format_cancel.clone();
+                        let output = self.format_output.clone();
                         self.loading_format_task = Some(std::thread::spawn(move || {
-                            let result = LogFormat::from_file(&path, python);
+                            let result = if from_schema {
+                                LogFormat::from_schema_json(&path)
+                            } else {
+                                LogFormat::from_file(&path, python, timeout, |stderr| {
+                                    let mut output = output.lock().unwrap();
+                                    if output.len() != stderr.len() {
+                                        stderr.clone_into(&mut output);
+                                        ctx_clone.request_repaint();
+                                    }
+                                    !cancel.load(Ordering::SeqCst)
+                                })
+                            };
+                            // Setting up the columns of every variant takes a while for a large format.
+                            let result = result.and_then(|format| {
+                                let size = format.size()?;
+                                Ok((format, size))
+                            });
                             ctx_clone.request_repaint_after(Duration::from_millis(100));
-                            result
+                            result.map_err(|e| e.to_string())
                         }));
                     }
                 } else {
                     ui.add_enabled(false, egui::Button::new("Loading Format"));
+                    ui.spinner();
+                    if ui.button("Cancel").clicked() {
+                        self.format_cancel.store(true, Ordering::SeqCst);
+                    }
+                    if let Some(line) = self.format_output.lock().unwrap().lines().last() {
+                        ui.add(egui::Label::new(line).truncate(true));
+                    }
                 }
 
                 if let Some(msg) = &self.format_message {
                     ui.colored_label(Color32::RED, "!").on_hover_text(msg);
                 }
             });
+
+            if let Some(loaded_format) = &self.loaded_format {
+                ui.horizontal(|ui| {
+                    ui.add(FilePicker::new("schema-report-picker", &mut self.schema_report_path)
+                        .add_filter("Markdown", &["md"])
+                        .set_is_save(true)
+                        .dialog_title("Save Schema"));
+                    if self.exporting_schema.as_ref().is_some_and(|task| task.is_finished()) {
+                        self.schema_report_message = self.exporting_schema.take().unwrap().join().unwrap().err();
+                    }
+
+                    if self.exporting_schema.is_some() {
+                        ui.add_enabled(false, egui::Button::new("Exporting Schema"));
+                        ui.spinner();
+                    } else {
+                        let response = ui.add_enabled(!self.schema_report_path.is_empty(), egui::Button::new("Export Schema"))
+                            .on_hover_text("Save a description of each packet type and the layout of its fields.")
+                            .on_disabled_hover_text("Choose where to save the description.");
+                        if response.clicked() {
+                            self.schema_report_message = None;
+                            let format = loaded_format.clone();
+                            let path = self.schema_report_path.clone();
+                            let ctx = ui.ctx().clone();
+                            self.exporting_schema = Some(std::thread::spawn(move || {
+                                let result = format.report().map_err(|e| e.to_string())
+                                    .and_then(|report| fs::write(&path, report).map_err(|e| e.to_string()));
+                                ctx.request_repaint();
+                                result
+                            }));
+                        }
+                    }
+                    if let Some(msg) = &self.schema_report_message {
+                        ui.colored_label(Color32::RED, "!").on_hover_text(msg);
+                    }
+                });
+            }
         });
 
         ui.add_space(3.0);
@@ -212,10 +817,31 @@ impl ImportLaunchTab {
         ui.horizontal(|ui| {
             if let Some(task) = &self.parsing {
                 if task.is_finished() {
-                    let result = self.parsing.take().unwrap().handle.join().unwrap();
+                    let (result, parse_log) = self.parsing.take().unwrap().handle.join().unwrap();
+                    self.parse_log = parse_log;
                     match result {
-                        Ok(dataframe) => {
-                            shared.replace(DataShared::new(dataframe));
+                        Ok((loaded, summary, source_path, reader)) => {
+                            self.loaded_memory = Some((loaded.data.memory_bytes(), loaded.data.distinct_strings()));
+                            self.following = reader.map(|reader| Following::start(ui.ctx(), PathBuf::from(&source_path), reader, &loaded.data.df));
+                            if let Err(e) = show_loaded(shared, loaded) {
+                                self.parsing_message = Some(e.to_string());
+                            }
+
+                            let mut warnings = vec![];
+                            if let Some(format) = self.loaded_format.as_ref().filter(|format| format.checksum != summary.checksum) {
+                                warnings.push(format!("{} was written with format 0x{:0>8x}, but the loaded format is 0x{:0>8x}, so its data may be wrong.", file_name(&source_path), summary.checksum, format.checksum));
+                            }
+                            if !summary.resync_events.is_empty() {
+                                warnings.push(format!("{} bytes skipped across {} gaps", summary.bytes_skipped(), summary.resync_events.len()));
+                            }
+                            if summary.crc_failures > 0 {
+                                warnings.push(format!("{} packets left out for failing their CRC", summary.crc_failures));
+                            }
+                            if summary.large_timestamps > 0 {
+                                warnings.push(format!("{} timestamps too large to show were left empty", summary.large_timestamps));
+                            }
+                            self.parsing_warning = (!warnings.is_empty()).then(|| warnings.join("\n"));
+                            self.read_summary = Some(summary);
                         }
                         Err(e) => {
                             self.parsing_message = Some(e.to_string());
@@ -227,26 +853,125 @@ impl ImportLaunchTab {
             if let Some(task) = &self.parsing {
                 ui.add_enabled(false, egui::Button::new("Loading"));
 
-                ui.add(egui::ProgressBar::new(task.progress()).show_percentage());
+                // Formats with many variants take a while to set up before the file is read.
+                let text = task.text();
+                if text.is_empty() {
+                    ui.add(egui::ProgressBar::new(task.progress()).show_percentage());
+                } else {
+                    ui.add(egui::ProgressBar::new(task.progress()).text(format!("{} {}%", text, (task.progress() * 100.0) as u32)));
+                }
             } else {
                 if let (Some(loaded_format), true) = (&self.loaded_format, !self.source_path.is_empty()) {
                     let response = ui.add_enabled(true, egui::Button::new("Load Data"));
 
+                    if let Some(checksum) = self.inspected_checksum.filter(|&checksum| checksum != loaded_format.checksum) {
+                        ui.colored_label(ui.visuals().warn_fg_color, "!")
+                            .on_hover_text(format!("{} was written with format 0x{:0>8x}, but the loaded format is 0x{:0>8x}.", file_name(&self.source_path), checksum, loaded_format.checksum));
+                    }
+
+                    // Only the presence of a start time matters to the estimate.
+                    let key = (self.source_path.clone(), loaded_format.checksum, self.read_options(self.has_start_epoch.then_some(0)));
+                    if !self.memory_estimate.as_ref().is_some_and(|(estimate_key, _)| *estimate_key == key) {
+                        let estimate = match (is_gzipped(&self.source_path), self.loaded_format_size) {
+                            (Ok(false), Some(size)) => {
+                                let file_size = std::fs::metadata(&self.source_path).map_or(0, |meta| meta.len());
+                                Some(loaded_format.estimate_memory_bytes(size, file_size, key.2))
+                            }
+                            _ => None
+                        };
+                        self.memory_estimate = Some((key, estimate));
+                    }
+                    if let Some((loaded_memory, distinct_strings)) = self.loaded_memory {
+                        ui.label(format_bytes(loaded_memory as u64))
+                            .on_hover_text(format!("Memory taken up by the loaded data, including {} distinct strings.", distinct_strings));
+                    } else if let Some((_, Some(estimate))) = self.memory_estimate {
+                        ui.label(format!("≤ {}", format_bytes(estimate)))
+                            .on_hover_text("Estimated memory needed to load the data file.");
+                        if let Some(available) = available_memory().filter(|&available| estimate > available) {
+                            ui.colored_label(ui.visuals().warn_fg_color, "!")
+                                .on_hover_text(format!("Loading {} may need up to {}, but only {} of memory is free. Use 'Load At Most' to load fewer packets.", file_name(&self.source_path), format_bytes(estimate), format_bytes(available)));
+                        }
+                    }
+
                     if response.clicked() {
                         self.parsing_message = None;
-                        shared.take();
-                        let format = loaded_format.clone();
-                        let source_path = self.source_path.clone();
+                        self.parsing_warning = None;
+                        self.parse_log = None;
+                        self.loaded_memory = None;
+                        self.read_summary = None;
+                        self.validation = None;
+                        self.following = None;
 
-                        self.parsing = Some(ProgressTask::new(ui.ctx(), move |progress| {
-                            let mut file = BufReader::new(File::open(source_path)?);
-                            let size: u64 = file.get_ref().metadata().map_or(0, |m| m.len());
+                        let start_epoch = match self.has_start_epoch.then(|| self.start_epoch.trim().parse::<i32>()) {
+                            Some(Ok(start_epoch)) => Ok(Some(start_epoch)),
+                            Some(Err(_)) => Err("Start time must be a whole number of seconds.".to_string()),
+                            None => Ok(None)
+                        };
 
-                            format.read_file(&mut file, Some(size), |offset| {
-                                progress.set(offset as f32 / size as f32);
-                            })
-                        }));
+                        match start_epoch {
+                            Ok(start_epoch) => {
+                                let base = take_for_load(shared, append);
+                                let format = loaded_format.clone();
+                                let source_path = self.source_path.clone();
+                                let options = self.read_options(start_epoch);
+                                // Appended data is a copy, so there is nothing to follow.
+                                let follow = self.follow && !append;
+                                let source_columns = self.source_columns;
+                                let log_path = self.write_parse_log.then(|| PathBuf::from(format!("{}.parse.log", source_path)));
+
+                                self.parsing = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                                    let (mut file, size, fraction_read) = match open_data_file(&source_path) {
+                                        Ok(opened) => opened,
+                                        Err(e) => return (Err(e), None)
+                                    };
+
+                                    progress.set_text("Setting up columns".to_string());
+                                    let reader = format.reader(size, options, |done, total| {
+                                        progress.set(done as f32 / total as f32);
+                                    });
+                                    let mut reader = match reader {
+                                        Ok(reader) => reader,
+                                        Err(e) => return (Err(io::Error::other(e)), None)
+                                    };
+                                    progress.set_text(String::new());
+                                    let read = reader.read(&mut file, |_| progress.set(fraction_read.get()));
+                                    // Kept for the log, since finishing uses up the reader.
+                                    let (summary, offset) = (reader.summary(), reader.offset());
+
+                                    let result = read.and_then(|()| {
+                                        // A followed file may not have a checksum yet, so it is kept
+                                        // even if it is too short for one.
+                                        if follow && size.is_some() {
+                                            let (dataframe, summary) = reader.take_rows();
+                                            Ok((Loaded::new(None, dataframe)?, summary, source_path.clone(), Some(reader)))
+                                        } else {
+                                            let (mut dataframe, summary) = reader.finish()?;
+                                            if source_columns {
+                                                dataframe = with_source_columns(&dataframe, next_file_number(base.as_ref()), &file_name(&source_path));
+                                            }
+                                            Ok((Loaded::new(base, dataframe)?, summary, source_path.clone(), None))
+                                        }
+                                    });
+
+                                    // The log is written whether or not loading failed, since that is
+                                    // when it's needed most.
+                                    let parse_log = log_path.map(|log_path| {
+                                        let error = result.as_ref().err().map(|e| (e, offset));
+                                        fs::write(&log_path, format.parse_log(&source_path, &summary, error))
+                                            .map(|()| log_path)
+                                            .map_err(|e| format!("Could not write the parse log: {}", e))
+                                    });
+                                    (result, parse_log)
+                                }));
+                            }
+                            Err(msg) => {
+                                self.parsing_message = Some(msg);
+                            }
+                        }
                     }
+
+                    self.show_validate(ui);
+                    self.show_guess_layout(ui);
                 } else {
                     ui.add_enabled(false, egui::Button::new("Load Data")).on_disabled_hover_text("Choose data and load format.");
                 }
@@ -255,36 +980,234 @@ impl ImportLaunchTab {
             if let Some(msg) = &self.parsing_message {
                 ui.colored_label(Color32::RED, "!").on_hover_text(msg);
             }
+
+            if let Some(msg) = &self.parsing_warning {
+                ui.colored_label(ui.visuals().warn_fg_color, "!").on_hover_text(msg);
+            }
+
+            match &self.parse_log {
+                Some(Ok(path)) => {
+                    ui.hyperlink_to("Parse Log", file_url(path)).on_hover_text(path.to_string_lossy());
+                }
+                Some(Err(msg)) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, "!").on_hover_text(msg);
+                }
+                None => {}
+            }
+
+            if let Some(following) = &self.following {
+                ui.spinner().on_hover_text(format!("Following {}", file_name(&following.path.to_string_lossy())));
+                if ui.button("Stop").clicked() {
+                    self.following = None;
+                }
+            }
         });
+
+        if let Some(summary) = self.read_summary.as_ref().or(self.validation.as_ref().and_then(|result| Some(&result.as_ref().ok()?.summary))) {
+            egui::CollapsingHeader::new("Packets").id_source("packets-header").show(ui, |ui| {
+                egui::Grid::new("packets-grid").striped(true).show(ui, |ui| {
+                    ui.strong("Type");
+                    ui.strong("Discriminant");
+                    ui.strong("Size");
+                    ui.strong("Rows");
+                    ui.end_row();
+
+                    for variant in &summary.variants {
+                        ui.label(&variant.name);
+                        ui.label(variant.discriminant.to_string());
+                        ui.label(format!("{} B", variant.size));
+                        ui.label(variant.rows.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+    }
+
+    /// A button to walk the packets of the data file without loading them, to check it for gaps and a
+    /// cut off final packet before committing the memory to load it.
+    fn show_validate(&mut self, ui: &mut Ui) {
+        let Some(loaded_format) = &self.loaded_format else {
+            return;
+        };
+        let checksum = loaded_format.checksum;
+
+        if self.validating.as_ref().is_some_and(|task| task.is_finished()) {
+            let result = self.validating.take().unwrap().handle.join().unwrap();
+            self.validation = Some(result.map_err(|e| e.to_string()));
+        }
+
+        if let Some(task) = &self.validating {
+            ui.add_enabled(false, egui::Button::new("Validating"));
+            ui.add(egui::ProgressBar::new(task.progress()).desired_width(100.0).show_percentage());
+            return;
+        }
+
+        if ui.button("Validate").on_hover_text("Check the data file for gaps and cut off packets without loading it.").clicked() {
+            let format = loaded_format.clone();
+            self.validation = None;
+            self.read_summary = None;
+            let source_path = self.source_path.clone();
+            let options = self.read_options(None);
+            self.validating = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                let (mut file, _, fraction_read) = open_data_file(&source_path)?;
+                format.validate_file(&mut file, options, |_| progress.set(fraction_read.get()))
+            }));
+        }
+
+        match &self.validation {
+            Some(Ok(report)) => {
+                let mut problems = vec![];
+                if report.summary.checksum != checksum {
+                    problems.push(format!("Written with format 0x{:0>8x}, but the loaded format is 0x{:0>8x}.", report.summary.checksum, checksum));
+                }
+                if !report.summary.resync_events.is_empty() {
+                    problems.push(format!("{} bytes skipped across {} gaps.", report.summary.bytes_skipped(), report.summary.resync_events.len()));
+                }
+                if report.summary.crc_failures > 0 {
+                    problems.push(format!("{} packets failed their CRC.", report.summary.crc_failures));
+                }
+                if report.trailing_bytes > 0 {
+                    problems.push(format!("The last packet is cut off after {} bytes.", report.trailing_bytes));
+                }
+
+                let text = format!("{} packets", report.packets());
+                if problems.is_empty() {
+                    ui.label(text).on_hover_text("Every packet was read without gaps.");
+                } else {
+                    ui.colored_label(ui.visuals().warn_fg_color, text).on_hover_text(problems.join("\n"));
+                }
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(Color32::RED, "!").on_hover_text(msg);
+            }
+            None => {}
+        }
+    }
+
+    /// A button to try each byte order and header layout on the start of the data file, for logs whose
+    /// layout isn't known, and use the one that reads the most packets.
+    fn show_guess_layout(&mut self, ui: &mut Ui) {
+        let Some(loaded_format) = &mut self.loaded_format else {
+            return;
+        };
+
+        if self.guessing_layout.as_ref().is_some_and(|task| task.is_finished()) {
+            let result = self.guessing_layout.take().unwrap().handle.join().unwrap();
+            self.layout_guesses = Some(result.map_err(|e| e.to_string()));
+        }
+
+        if self.guessing_layout.is_some() {
+            ui.add_enabled(false, egui::Button::new("Guessing Layout"));
+            ui.spinner();
+            return;
+        }
+
+        if ui.button("Guess Layout").on_hover_text("Try each byte order and header size on the start of the data file, and suggest the one that reads the most packets.").clicked() {
+            let format = loaded_format.clone();
+            let source_path = self.source_path.clone();
+            let preamble = self.preamble;
+            self.layout_guesses = None;
+            self.guessing_layout = Some(ProgressTask::new(ui.ctx(), move |_| {
+                let (mut file, _, _) = open_data_file(&source_path)?;
+                format.guess_layouts(&mut file, preamble)
+            }));
+        }
+
+        match &self.layout_guesses {
+            Some(Ok(guesses)) => {
+                let Some(best) = guesses.first() else { return };
+                let describe = |guess: &LayoutGuess| format!(
+                    "{}, {} byte discriminant, {} byte timestamp",
+                    if guess.big_endian { "Big endian" } else { "Little endian" }, guess.header.discriminant, guess.header.timestamp
+                );
+                let details = guesses.iter()
+                    .map(|guess| format!("{}: {} packets, {} bytes skipped{}", describe(guess), guess.packets, guess.bytes_skipped, if guess.checksum_matches { ", checksum matches" } else { "" }))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let hover = format!("Over the first {} of the file:\n{}\n\nThis is a guess, and can be wrong for short files or the wrong format.", format_bytes(GUESS_LAYOUT_BYTES), details);
+
+                if best.big_endian == self.big_endian && best.header == loaded_format.header {
+                    ui.label("Layout fits").on_hover_text(hover);
+                } else if ui.button(format!("Use {}", describe(best))).on_hover_text(hover).clicked() {
+                    self.big_endian = best.big_endian;
+                    loaded_format.header = best.header;
+                    self.layout_guesses = None;
+                    self.validation = None;
+                }
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(Color32::RED, "!").on_hover_text(msg);
+            }
+            None => {}
+        }
     }
 }
 
 
-struct ImportCSVTab {
+struct ImportCsvTab {
     source_path: String,
+    options: CsvOptions,
 
-    parsing: Option<ProgressTask<Result<DataFrameView, io::Error>>>,
-    parsing_message: Option<String>
+    parsing: Option<ProgressTask<Result<(Loaded, usize), io::Error>>>,
+    parsing_message: Option<String>,
+    parsing_warning: Option<String>,
+    /// The bytes taken up by the last loaded data, and how many distinct strings it holds.
+    loaded_memory: Option<(usize, usize)>,
 }
 
-impl ImportCSVTab {
+impl ImportCsvTab {
     pub fn new(_cc: &eframe::CreationContext) -> Self {
         Self {
             source_path: String::new(),
+            options: CsvOptions::default(),
             parsing: None,
-            parsing_message: None
+            parsing_message: None,
+            parsing_warning: None,
+            loaded_memory: None,
         }
     }
 
     pub fn save(&self, _storage: &mut dyn Storage) { }
 
-    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
+    /// Makes `path` the data file. Its header is read when it is loaded.
+    fn open(&mut self, path: &Path) {
+        self.source_path = path.to_string_lossy().into_owned();
+        self.loaded_memory = None;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>, append: bool) {
         ui.add(FilePicker::new("data-csv-file-picker", &mut self.source_path)
             .dialog_title("Data File")
             .add_filter("CSV", &["csv"])
+            .add_filter("Gzipped CSV", &["gz"])
             // .add_filter("Any", &[])
         );
 
+        ui.horizontal(|ui| {
+            ui.label("Delimiter:");
+            ui.selectable_value(&mut self.options.delimiter, ',', "Comma");
+            ui.selectable_value(&mut self.options.delimiter, ';', "Semicolon");
+            ui.selectable_value(&mut self.options.delimiter, '\t', "Tab");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Decimal Separator:");
+            ui.selectable_value(&mut self.options.decimal_separator, '.', "Point");
+            ui.selectable_value(&mut self.options.decimal_separator, ',', "Comma");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Infer Types From:");
+            ui.add(egui::DragValue::new(&mut self.options.infer_rows).clamp_range(1..=1_000_000).suffix(" rows"));
+        });
+
+        ui.checkbox(&mut self.options.lenient, "Allow Malformed Rows")
+            .on_hover_text("Pad rows with missing fields with nulls and drop extra fields, instead of failing.");
+
+        ui.checkbox(&mut self.options.typed_header, "Column Types in Header")
+            .on_hover_text("Read the type of each column from the header, such as 'timestamp:unsigned', as exported with 'Column Types in Header' checked.");
+
         ui.add_space(3.0);
 
         ui.horizontal(|ui| {
@@ -292,8 +1215,14 @@ impl ImportCSVTab {
                 if task.is_finished() {
                     let result = self.parsing.take().unwrap().handle.join().unwrap();
                     match result {
-                        Ok(dataframe) => {
-                            shared.replace(DataShared::new(dataframe));
+                        Ok((loaded, malformed_rows)) => {
+                            self.loaded_memory = Some((loaded.data.memory_bytes(), loaded.data.distinct_strings()));
+                            if let Err(e) = show_loaded(shared, loaded) {
+                                self.parsing_message = Some(e.to_string());
+                            }
+                            if malformed_rows > 0 {
+                                self.parsing_warning = Some(format!("{} malformed rows", malformed_rows));
+                            }
                         }
                         Err(e) => {
                             self.parsing_message = Some(e.to_string());
@@ -310,18 +1239,26 @@ impl ImportCSVTab {
                 if !self.source_path.is_empty() {
                     let response = ui.add_enabled(true, egui::Button::new("Load Data"));
 
+                    if let Some((loaded_memory, distinct_strings)) = self.loaded_memory {
+                        ui.label(format_bytes(loaded_memory as u64))
+                            .on_hover_text(format!("Memory taken up by the loaded data, including {} distinct strings.", distinct_strings));
+                    }
+
                     if response.clicked() {
                         self.parsing_message = None;
-                        shared.take();
+                        self.parsing_warning = None;
+                        self.loaded_memory = None;
+                        let base = take_for_load(shared, append);
                         let source_path = self.source_path.clone();
+                        let options = self.options;
 
                         self.parsing = Some(ProgressTask::new(ui.ctx(), move |progress| {
-                            let mut file = BufReader::new(File::open(source_path)?);
-                            let size: u64 = file.get_ref().metadata().map_or(0, |m| m.len());
+                            let (mut file, size, fraction_read) = open_data_file(&source_path)?;
 
-                            DataFrameView::from_csv(&mut file, |offset| {
-                                progress.set(offset as f32 / size as f32);
-                            })
+                            let (dataframe, malformed_rows) = DataFrameView::from_csv(&mut file, size, options, |_| {
+                                progress.set(fraction_read.get());
+                            })?;
+                            Ok((Loaded::new(base, dataframe)?, malformed_rows))
                         }));
                     }
                 } else {
@@ -332,6 +1269,273 @@ impl ImportCSVTab {
             if let Some(msg) = &self.parsing_message {
                 ui.colored_label(Color32::RED, "!").on_hover_text(msg);
             }
+
+            if let Some(msg) = &self.parsing_warning {
+                ui.colored_label(ui.visuals().warn_fg_color, "!").on_hover_text(msg);
+            }
         });
     }
 }
+
+/// Gets ready to load new data. The loaded data is kept to append to if `append` is set, in which
+/// case its complete data is returned, and freed otherwise.
+fn take_for_load(shared: &mut Option<DataShared>, append: bool) -> Option<DataFrameView> {
+    if append {
+        shared.as_ref().map(|shared| shared.complete_data.clone())
+    } else {
+        drop_in_background(shared.take());
+        None
+    }
+}
+
+/// The rows of `data` after those of `base`, if there is one. Fails if their columns don't match,
+/// so the loaded data is left as it is.
+fn append_to(base: Option<DataFrameView>, data: DataFrameView) -> io::Result<DataFrameView> {
+    match base {
+        Some(base) => DataFrameView::concat(&[base, data])
+            .map_err(|e| io::Error::other(format!("Not appended to the loaded data. {}", e))),
+        None => Ok(data)
+    }
+}
+
+/// The file number of a file appended to `base`: one more than the highest in its 'file number'
+/// column, or 0 if it has none.
+fn next_file_number(base: Option<&DataFrameView>) -> usize {
+    let Some(base) = base else { return 0 };
+    let Some(col_idx) = base.col_names().position(|name| name == "file number") else { return 0 };
+    base.col(col_idx).iter()
+        .filter_map(|data| match data {
+            Data::Integer(file_number) => usize::try_from(file_number).ok(),
+            _ => None
+        })
+        .max()
+        .map_or(0, |file_number| file_number + 1)
+}
+
+/// Data read from a file, ready to be shown.
+struct Loaded {
+    data: DataFrameView,
+    /// If the data was appended, the frame it was appended to and the rows of the file alone, so
+    /// that they can be appended again if the loaded data changed while the file was read.
+    appended: Option<(Arc<DataFrame>, DataFrameView)>
+}
+
+impl Loaded {
+    /// The rows of `rows` after those of `base`, if there is one.
+    fn new(base: Option<DataFrameView>, rows: DataFrameView) -> io::Result<Loaded> {
+        let appended = base.as_ref().map(|base| (base.df.clone(), rows.clone()));
+        Ok(Loaded { data: append_to(base, rows)?, appended })
+    }
+}
+
+/// Shows newly loaded data. Appended rows go after the loaded data as it is now, so that changes
+/// made to it while the file was read, such as rows read from a followed file, are kept. Fails if
+/// the columns no longer match, leaving the loaded data as it is.
+fn show_loaded(shared: &mut Option<DataShared>, loaded: Loaded) -> io::Result<()> {
+    let Some((base, rows)) = loaded.appended else {
+        drop_in_background(shared.replace(DataShared::new(loaded.data)));
+        return Ok(());
+    };
+    match shared {
+        Some(current) if Arc::ptr_eq(&current.complete_data.df, &base) => current.replace_complete(loaded.data),
+        Some(current) => {
+            drop_in_background(loaded.data);
+            let appended = append_to(Some(current.complete_data.clone()), rows)?;
+            current.replace_complete(appended);
+        }
+        None => *shared = Some(DataShared::new(loaded.data))
+    }
+    Ok(())
+}
+
+/// Counts the bytes read through it.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = self.inner.read(buf)?;
+        self.count.set(self.count.get() + amount as u64);
+        Ok(amount)
+    }
+}
+
+/// How much of a file has been read, shared with the [`CountingReader`] reading it.
+struct ReadFraction {
+    count: Rc<Cell<u64>>,
+    size: u64
+}
+
+impl ReadFraction {
+    /// From 0 to 1.
+    fn get(&self) -> f32 {
+        self.count.get() as f32 / self.size as f32
+    }
+}
+
+/// Whether the file at `path` starts like a gzip file.
+fn is_gzipped(path: impl AsRef<Path>) -> io::Result<bool> {
+    let mut magic = [0; 2];
+    match File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == [0x1f, 0x8b]),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+/// The characters escaped in the path of a `file://` link, since they would end the path early or
+/// be read as escapes themselves.
+const FILE_URL_ESCAPED: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+/// A `file://` link to `path`, to open it with whatever the system opens such files with.
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    format!("file:///{}", utf8_percent_encode(path.trim_start_matches('/'), FILE_URL_ESCAPED))
+}
+
+/// Opens a data file to import, decompressing it while reading if it is gzipped. Also returns the
+/// size of the data if it is known up front, which it isn't for gzipped files, and how much of the
+/// file has been read so far.
+fn open_data_file(path: impl AsRef<Path>) -> io::Result<(Box<dyn BufRead>, Option<u64>, ReadFraction)> {
+    let gzipped = is_gzipped(&path)?;
+    let file = File::open(path)?;
+    let size = file.metadata().map_or(0, |meta| meta.len());
+
+    let count = Rc::new(Cell::new(0));
+    let counted = BufReader::new(CountingReader { inner: file, count: count.clone() });
+    let fraction_read = ReadFraction { count, size };
+
+    if gzipped {
+        Ok((Box::new(BufReader::new(MultiGzDecoder::new(counted))), None, fraction_read))
+    } else {
+        Ok((Box::new(counted), Some(size), fraction_read))
+    }
+}
+
+/// A number of bytes in the largest unit that keeps it above 1, such as `1.5 GB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// The memory free for new programs, where the system says. Only Linux is supported so far.
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kilobytes: u64 = line.trim_start_matches("MemAvailable:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// The name of the file at `path`, for messages.
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or(path.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+    use launch_file::with_source_columns;
+
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use crate::DataShared;
+    use super::{ImportFrom, Loaded, append_to, file_url, next_file_number, open_data_file, show_loaded};
+
+    fn frame(values: &[f32]) -> DataFrameView {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("value", DataType::Float);
+        let mut df = builder.build();
+        for &value in values {
+            df.add_row(&[Data::Float(value)]);
+        }
+        DataFrameView::from_dataframe(df)
+    }
+
+    /// Each cell of column `col`, as shown in the table.
+    fn cells(df: &DataFrameView, col: usize) -> Vec<String> {
+        df.col(col).iter().map(|data| data.to_string()).collect()
+    }
+
+    #[test]
+    fn picks_the_import_by_the_extension_inside_a_gzip() {
+        let import = |path: &str| ImportFrom::for_path(Path::new(path));
+        assert!(import("flight.launch") == Some(ImportFrom::Launch));
+        assert!(import("FLIGHT.CSV") == Some(ImportFrom::Csv));
+        assert!(import("flight.launch.gz") == Some(ImportFrom::Launch));
+        assert!(import("flight.csv.GZ") == Some(ImportFrom::Csv));
+        assert!(import("notes.txt.gz").is_none());
+        assert!(import("flight.gz").is_none());
+        assert!(import("flight.csv.gz.gz").is_none());
+        assert!(import("flight").is_none());
+    }
+
+    #[test]
+    fn escapes_a_path_in_a_file_link() {
+        assert_eq!(file_url(Path::new("/tmp/flight 3#1.launch.parse.log")), "file:///tmp/flight%203%231.launch.parse.log");
+        assert_eq!(file_url(Path::new("C:\\logs\\vol%50\\é.log")), "file:///C:/logs/vol%2550/%C3%A9.log");
+    }
+
+    #[test]
+    fn numbers_appended_files_in_the_order_they_were_loaded() {
+        let mut loaded = None;
+        for (name, values) in [("a.launch", &[1.0, 2.0][..]), ("b.launch", &[3.0][..]), ("c.launch", &[4.0, 5.0][..])] {
+            let data = with_source_columns(&frame(values), next_file_number(loaded.as_ref()), name);
+            loaded = Some(append_to(loaded, data).unwrap());
+        }
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.col_names().collect::<Vec<_>>(), ["value", "file number", "source file"]);
+        assert_eq!(cells(&loaded, 0), ["1", "2", "3", "4", "5"]);
+        assert_eq!(cells(&loaded, 1), ["0", "0", "1", "2", "2"]);
+        assert_eq!(cells(&loaded, 2), ["a.launch", "a.launch", "b.launch", "c.launch", "c.launch"]);
+    }
+
+    #[test]
+    fn reads_every_member_of_a_concatenated_gzip_file() {
+        let mut file = vec![];
+        for part in ["first member\n", "second member\n"] {
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(part.as_bytes()).unwrap();
+            file.extend(encoder.finish().unwrap());
+        }
+        let path = std::env::temp_dir().join(format!("midas-launch-{}-concatenated.csv.gz", std::process::id()));
+        std::fs::write(&path, file).unwrap();
+
+        let (mut reader, size, _) = open_data_file(&path).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(size, None);
+        assert_eq!(text, "first member\nsecond member\n");
+    }
+
+    #[test]
+    fn appends_after_rows_added_while_the_file_was_read() {
+        let mut shared = Some(DataShared::new(frame(&[1.0, 2.0])));
+        let base = shared.as_ref().map(|shared| shared.complete_data.clone());
+        let loaded = Loaded::new(base, frame(&[5.0])).unwrap();
+
+        // Rows read from a followed file in the meantime.
+        shared.as_mut().unwrap().grow(&frame(&[3.0, 4.0]), []).unwrap();
+        show_loaded(&mut shared, loaded).unwrap();
+
+        let shared = shared.unwrap();
+        assert_eq!(cells(&shared.complete_data, 0), ["1", "2", "3", "4", "5"]);
+        assert_eq!(cells(&shared.shown_data, 0), ["1", "2", "3", "4", "5"]);
+    }
+}
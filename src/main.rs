@@ -5,22 +5,28 @@ mod task_button;
 mod process;
 mod import;
 mod export;
+mod query;
 
 use std::cell::Cell;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::{Range, RangeInclusive};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, Weak, mpsc};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::thread::JoinHandle;
 use std::time::Duration;
 
-use egui::{Align, Context, FontFamily, Layout, panel::Side, RichText, Visuals, Widget};
+use egui::{Align, Color32, Context, FontFamily, Layout, panel::Side, RichText, Ui, Visuals, Widget};
 use egui_plot as plot;
 use eframe::{Frame, Storage};
+use serde::{Deserialize, Serialize};
 // use egui_extras::image;
 
-use dataframe::{Data, DataFrameView};
+use dataframe::{ColumnFormat, ColumnView, Data, DataFrame, DataFrameView, DataType, Notation};
 
 use crate::import::ImportTab;
-use crate::process::ProcessTab;
+use crate::process::{as_f64, ProcessTab};
 use crate::export::ExportTab;
 
 
@@ -38,8 +44,249 @@ enum VisualState {
     Table
 }
 
+/// A table cell, as (row, column).
+type TableCell = (usize, usize);
+
+/// What a search's results are for: the query, the version of the data searched, and the sensors
+/// whose rows were left out.
+type SearchKey = (String, u64, HashSet<String>);
+/// The frame of the loaded data and how many rows it has, which change whenever it does.
+type SensorsKey = (Weak<DataFrame>, usize);
+
 struct TableTab {
+    /// The cell the selection was started from, the cell it was extended to, and the version
+    /// of the data they refer to.
+    selection: Option<(TableCell, TableCell, u64)>,
+
+    search: String,
+    /// The text the shown rows are searched for, if any.
+    query: Option<String>,
+    /// The search that last finished, and the matching rows.
+    search_result: Option<(SearchKey, DataFrameView)>,
+    /// The search running in the background, while the query or the data have changed since the
+    /// last one.
+    searching: Option<(SearchKey, ProgressTask<DataFrameView>)>,
+
+    /// The distinct values of the 'sensor' column of the loaded data, each shown as a chip, for the
+    /// frame and number of rows of the loaded data they were found in.
+    sensors: Option<(SensorsKey, Vec<String>)>,
+    /// The sensors whose chips are turned off, so that their rows are left out of the table.
+    hidden_sensors: HashSet<String>,
+    /// The rows of the sensors that are turned on, and the data version they are from.
+    sensor_result: Option<(u64, DataFrameView)>,
+
+    /// The column the table is sorted by, and whether it is ascending.
+    sort: Option<(usize, bool)>,
+    /// The shown rows in sorted order, along with the data version and sort they are for.
+    sorted: Option<(u64, (usize, bool), DataFrameView)>,
+
+    /// The format last set for each column name, kept between sessions and given to the columns of
+    /// that name in newly loaded or processed data. Columns without an entry are shown as is.
+    saved_formats: HashMap<String, ColumnFormat>,
+    /// The complete and shown frames that were last given the saved formats.
+    formatted: Option<(Weak<DataFrame>, Weak<DataFrame>)>,
+    /// The column picked in the 'Column Format' editor.
+    format_col: usize,
+
+    /// The names of the columns pinned to the left, so that they stay in view while scrolling
+    /// sideways. They are saved with the column formats, so they are kept across loads and restarts.
+    pinned: HashSet<String>,
+    /// Whether the row numbers are shown, pinned left of every column.
+    row_numbers: bool,
+    /// The vertical scroll offsets of the pinned part of the table, if it was drawn, and of the
+    /// scrolling part as last drawn, along with the offset they were last both at.
+    scroll_offsets: (Option<f32>, f32),
+    scroll_synced: f32,
+
+    /// How many of the shown rows are drawn.
+    row_cap: RowCap
+}
+
+/// What the table is drawn with, shared by its pinned and scrolling parts.
+struct TableLook<'a> {
+    data: &'a DataFrameView,
+    /// How many rows of `data` are drawn, from the first.
+    rows: usize,
+    formats: &'a [ColumnFormat],
+    sort: Option<(usize, bool)>,
+    selected: Option<(RangeInclusive<usize>, RangeInclusive<usize>)>,
+    highlight_changes: bool,
+    changed_color: Color32,
+    pinned: &'a HashSet<String>,
+    row_numbers: bool
+}
 
+/// What was clicked or dragged in the table while it was drawn, to be acted on once it is.
+#[derive(Default)]
+struct TableActions {
+    clicked: Option<TableCell>,
+    clicked_header: Option<usize>,
+    /// The shown positions a column was dragged from and to.
+    moved: Option<(usize, usize)>,
+    /// A column and the text of a value to keep the rows equal to, or drop them if false.
+    cell_filter: Option<(usize, String, bool)>,
+    /// A column to pin, or unpin if false.
+    pin: Option<(String, bool)>,
+    row_numbers: Option<bool>
+}
+
+/// The id source of the scroll area holding the body of a table. The table builder doesn't take
+/// one and uses egui's default, so each table is drawn in a ui of its own for the id to be unique.
+const TABLE_SCROLL_ID_SOURCE: &str = "scroll_area";
+
+impl TableLook<'_> {
+    /// Draws `cols` as a table, the first of which is shown at position `first_pos`. The pinned part
+    /// also holds the row numbers, if shown. Returns the vertical scroll offset of the table, which is
+    /// scrolled to `scroll_to` first if given.
+    fn show(&self, ui: &mut Ui, cols: &[usize], first_pos: usize, is_pinned: bool, scroll_to: Option<f32>, actions: &mut TableActions) -> f32 {
+        let data = self.data;
+        let row_numbers = is_pinned && self.row_numbers;
+        let scroll_id = ui.make_persistent_id(TABLE_SCROLL_ID_SOURCE);
+        let ctx = ui.ctx().clone();
+        if let Some(scroll_to) = scroll_to {
+            let mut state = egui::scroll_area::State::load(&ctx, scroll_id).unwrap_or_default();
+            state.offset.y = scroll_to;
+            state.store(&ctx, scroll_id);
+        }
+
+        let mut builder = egui_extras::TableBuilder::new(ui)
+            .auto_shrink([is_pinned, false])
+            .max_scroll_height(f32::INFINITY)
+            .resizable(true)
+            .striped(true)
+            .sense(egui::Sense::click())
+            .cell_layout(Layout::right_to_left(Align::Center));
+        if is_pinned {
+            builder = builder.scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden);
+        }
+        if row_numbers {
+            builder = builder.column(egui_extras::Column::auto());
+        }
+
+        builder
+            .columns(egui_extras::Column::auto().clip(true), cols.len())
+            .header(28.0, |mut row| {
+                if row_numbers {
+                    let (_, response) = row.col(|ui| {
+                        ui.label(RichText::new("#").family(FontFamily::Monospace).size(18.0));
+                    });
+                    response.on_hover_text("Row number").context_menu(|ui| {
+                        if ui.button("Hide Row Numbers").clicked() {
+                            actions.row_numbers = Some(false);
+                            ui.close_menu();
+                        }
+                    });
+                }
+                for (pos, &col_idx) in (first_pos..).zip(cols) {
+                    let col_name = data.col_name(col_idx);
+                    let label = self.formats[col_idx].label(col_name);
+                    let text = match self.sort {
+                        Some((sort_col, true)) if sort_col == col_idx => format!("{} ⬆", label),
+                        Some((sort_col, false)) if sort_col == col_idx => format!("{} ⬇", label),
+                        _ => label
+                    };
+                    let (_, response) = row.col(|ui| {
+                        ui.dnd_drag_source(egui::Id::new(("table-header", col_idx)), pos, |ui| {
+                            egui::Label::new(RichText::new(text).family(FontFamily::Monospace).size(18.0)).truncate(true).ui(ui);
+                        });
+                    });
+                    if let Some(from) = response.dnd_release_payload::<usize>() {
+                        actions.moved = Some((*from, pos));
+                    }
+                    response.context_menu(|ui| {
+                        let is_pinned = self.pinned.contains(col_name);
+                        if ui.button(if is_pinned { "Unpin" } else { "Pin to Left" }).clicked() {
+                            actions.pin = Some((col_name.to_string(), !is_pinned));
+                            ui.close_menu();
+                        }
+                        if ui.button(if self.row_numbers { "Hide Row Numbers" } else { "Show Row Numbers" }).clicked() {
+                            actions.row_numbers = Some(!self.row_numbers);
+                            ui.close_menu();
+                        }
+                    });
+                    if response.on_hover_text("Click to sort by this column, drag it to move it, or right click to pin it").clicked() {
+                        actions.clicked_header = Some(col_idx);
+                    }
+                }
+            })
+            .body(|body| {
+                body.rows(28.0, self.rows, |mut row| {
+                    let row_idx = row.index();
+                    let Some(data_row) = data.try_row(row_idx) else {
+                        return;
+                    };
+                    if row_numbers {
+                        row.col(|ui| {
+                            ui.label(RichText::new(row_idx.to_string()).size(15.0).weak());
+                        });
+                    }
+                    for (pos, &col_idx) in (first_pos..).zip(cols) {
+                        let is_selected = self.selected.as_ref().is_some_and(|(rows, cols)| rows.contains(&row_idx) && cols.contains(&pos));
+                        row.set_selected(is_selected);
+                        let value = data_row.get_col(col_idx);
+                        let (_, response) = row.col(|ui| {
+                            // Empty cells get a faint dash, so they stand out from empty text.
+                            let mut text = if value.is_null() {
+                                RichText::new("—").size(15.0).weak()
+                            } else {
+                                RichText::new(self.formats[col_idx].value(&value)).size(15.0)
+                            };
+                            // The first column of a comparison says which rows were matched.
+                            if self.highlight_changes && col_idx > 0 && value.as_float().is_some_and(|delta| delta != 0.0) {
+                                text = text.background_color(self.changed_color);
+                            }
+                            ui.add(egui::Label::new(text).truncate(true));
+                        });
+                        if response.clicked() {
+                            actions.clicked = Some((row_idx, pos));
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Copy").clicked() {
+                                ui.ctx().copy_text(value.to_string());
+                                ui.close_menu();
+                            }
+                            let raw = data_row.get_col_raw(col_idx);
+                            if ui.add_enabled(raw.is_some(), egui::Button::new("Filter Equal")).on_disabled_hover_text("The cell is empty.").clicked() {
+                                actions.cell_filter = Some((col_idx, value.to_string(), true));
+                                ui.close_menu();
+                            }
+                            if ui.add_enabled(raw.is_some(), egui::Button::new("Exclude Equal")).on_disabled_hover_text("The cell is empty.").clicked() {
+                                actions.cell_filter = Some((col_idx, value.to_string(), false));
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                });
+            });
+
+        egui::scroll_area::State::load(&ctx, scroll_id).map_or(0.0, |state| state.offset.y)
+    }
+}
+
+/// The text column of `data` that says which packet each row came from, if it has one.
+fn sensor_col(data: &DataFrameView) -> Option<usize> {
+    data.col_names().position(|name| name == "sensor").filter(|&col_idx| data.df.col(col_idx).data_type() == DataType::Intern)
+}
+
+/// Whether a row from `sensor` is shown while the sensors in `hidden` are turned off. Rows without
+/// a sensor are always shown.
+fn is_sensor_shown(sensor: &Data, hidden: &HashSet<String>) -> bool {
+    match sensor {
+        Data::Str(sensor) => !hidden.contains(*sensor),
+        _ => true
+    }
+}
+
+type PlotKey = (u64, Option<usize>, Option<usize>, Option<usize>, f64, [bool; 2], usize);
+type HistogramKey = (u64, Option<usize>, usize, usize);
+/// A range of x values, in plot coordinates.
+type PlotWindow = (f64, f64);
+type SpectrumKey = (u64, Option<usize>, Option<usize>, bool, (usize, usize));
+
+/// The amplitude at each frequency of a spectrum, and a warning about the samples it was taken from.
+struct Spectrum {
+    points: Vec<[f64; 2]>,
+    warning: Option<String>
 }
 
 struct PlotTab {
@@ -47,35 +294,547 @@ struct PlotTab {
 
     x_idx: Option<usize>,
     y_idx: Option<usize>,
+    /// The column that colors the points of a scatter plot.
+    color_idx: Option<usize>,
     resolution: f64,
+    style: PlotStyle,
+    bins: usize,
+    x_axis: AxisOptions,
+    y_axis: AxisOptions,
+
+    /// Whether to show the whole plot in a strip below it, to pick the x range shown above from.
+    overview: bool,
+    /// The x range picked on the overview, in plot coordinates.
+    window: Option<PlotWindow>,
+    window_drag: Option<WindowDrag>,
+
+    /// The plotted points with their color values, along with whether each axis could be put on a
+    /// log scale.
+    cache: Option<(PlotKey, Vec<[f64; 3]>, [bool; 2])>,
+    /// The points within the x range picked on the overview, decimated for that range alone.
+    detail_cache: Option<(PlotKey, PlotWindow, Vec<[f64; 3]>)>,
+    histogram_cache: Option<(HistogramKey, Vec<plot::Bar>)>,
+
+    /// Whether the time column of a spectrum is in milliseconds rather than seconds.
+    spectrum_millis: bool,
+    /// The first row of the spectrum and the row after its last.
+    spectrum_rows: (usize, usize),
+    spectrum_cache: Option<(SpectrumKey, Spectrum)>,
+    /// The spectrum being computed in the background, once the cached one is out of date.
+    spectrum_task: Option<(SpectrumKey, ProgressTask<Spectrum>)>,
+
+    /// The x values shown by the line or scatter plot when it was last drawn.
+    visible_range: Option<VisibleRange>,
+
+    /// How many of the shown rows are plotted.
+    row_cap: RowCap
+}
+
+/// A range of x values of the plot, so that only the rows within it can be exported.
+#[derive(Clone)]
+struct VisibleRange {
+    /// The x column, or `None` if the x axis is the row number.
+    x_idx: Option<usize>,
+    x_name: String,
+    min: f64,
+    max: f64
+}
 
-    cache: Option<((u64, Option<usize>, Option<usize>, f64), Vec<[f64; 2]>)>
+/// A drag on the overview strip of a plot.
+#[derive(Copy, Clone)]
+enum WindowDrag {
+    /// Moving the picked range, holding it this far right of its start.
+    Move(f64),
+    /// Picking a new range, from this x.
+    Select(f64)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PlotStyle {
+    Line,
+    Scatter,
+    Histogram,
+    /// The frequencies in a column, such as of vibrations.
+    Spectrum
 }
 
 impl TableTab {
-    fn new(_cc: &eframe::CreationContext) -> TableTab {
+    fn new(cc: &eframe::CreationContext) -> TableTab {
+        TableTab::from_storage(cc.storage)
+    }
+
+    /// The tab with the formats, pinned columns and row number setting saved in `storage`.
+    fn from_storage(storage: Option<&dyn Storage>) -> TableTab {
+        let saved_formats = storage
+            .and_then(|store| store.get_string("table-column-formats"))
+            .and_then(|formats| serde_json::from_str(&formats).ok())
+            .unwrap_or_default();
+        let pinned = storage
+            .and_then(|store| store.get_string("table-pinned-columns"))
+            .and_then(|pinned| serde_json::from_str(&pinned).ok())
+            .unwrap_or_default();
+        let row_numbers = storage.and_then(|store| store.get_string("table-row-numbers")).is_some_and(|s| s == "true");
+
         TableTab {
+            selection: None,
+
+            search: String::new(),
+            query: None,
+            search_result: None,
+            searching: None,
+
+            sensors: None,
+            hidden_sensors: HashSet::new(),
+            sensor_result: None,
+
+            sort: None,
+            sorted: None,
+
+            saved_formats,
+            formatted: None,
+            format_col: 0,
+
+            pinned,
+            row_numbers,
+            scroll_offsets: (None, 0.0),
+            scroll_synced: 0.0,
+
+            row_cap: RowCap::default()
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        if let Ok(formats) = serde_json::to_string(&self.saved_formats) {
+            storage.set_string("table-column-formats", formats);
+        }
+        if let Ok(pinned) = serde_json::to_string(&self.pinned) {
+            storage.set_string("table-pinned-columns", pinned);
+        }
+        storage.set_string("table-row-numbers", self.row_numbers.to_string());
+    }
+
+    /// Gives the columns of newly loaded or processed data the saved formats of their names.
+    fn update_formats(&mut self, shared: &DataShared) {
+        let (complete, shown) = (&shared.complete_data.df, &shared.shown_data.df);
+        if self.formatted.as_ref().is_some_and(|(formatted_complete, formatted_shown)| formatted_complete.ptr_eq(&Arc::downgrade(complete)) && formatted_shown.ptr_eq(&Arc::downgrade(shown))) {
+            return;
+        }
+        for df in [complete, shown] {
+            for (col_idx, name) in df.col_names().enumerate() {
+                if let Some(format) = self.saved_formats.get(name) {
+                    df.set_col_format(col_idx, format.clone());
+                }
+            }
+        }
+        self.formatted = Some((Arc::downgrade(complete), Arc::downgrade(shown)));
+    }
+
+    /// Shows column `col_idx` of the shown data in `format`, along with the column of the same name in
+    /// the complete data, and saves it for columns of that name loaded later.
+    fn set_format(&mut self, shared: &DataShared, col_idx: usize, format: ColumnFormat) {
+        let name = shared.shown_data.col_name(col_idx);
+        shared.shown_data.df.set_col_format(col_idx, format.clone());
+        if let Some(complete_idx) = shared.complete_data.col_names().position(|complete_name| complete_name == name) {
+            shared.complete_data.df.set_col_format(complete_idx, format.clone());
+        }
+
+        if format == ColumnFormat::default() {
+            self.saved_formats.remove(name);
+        } else {
+            self.saved_formats.insert(name.to_string(), format);
+        }
+    }
+
+    /// Sorts by `col` ascending, then descending, then goes back to the order of the data.
+    fn click_header(&mut self, col: usize) {
+        self.sort = match self.sort {
+            Some((sort_col, true)) if sort_col == col => Some((col, false)),
+            Some((sort_col, false)) if sort_col == col => None,
+            _ => Some((col, true))
+        };
+        self.selection = None;
+    }
+
+    /// Sorts the shown rows again if the data or the sort have changed since they were last sorted.
+    fn update_sort(&mut self, data: &DataFrameView, version: u64) {
+        if self.sort.is_some_and(|(col, _)| col >= data.shape().cols) {
+            self.sort = None;
+        }
+
+        let Some(sort) = self.sort else {
+            self.sorted = None;
+            return;
+        };
+        if self.sorted.as_ref().is_some_and(|(sorted_version, sorted_by, _)| *sorted_version == version && *sorted_by == sort) {
+            return;
+        }
+
+        let mut sorted = self.searched(data).clone();
+        match sort {
+            (col, true) => sorted.sort_by_asc(col),
+            (col, false) => sorted.sort_by_desc(col)
+        }
+        self.sorted = Some((version, sort, sorted));
+    }
+
+    /// The current search, for the shown data as it is now.
+    fn search_key(&self, version: u64) -> Option<SearchKey> {
+        self.query.as_ref().map(|query| (query.clone(), version, self.hidden_sensors.clone()))
+    }
+
+    /// Starts searching the shown rows in the background when the query, the data or the sensors
+    /// turned on have changed since the last search, and picks up the results of one that has
+    /// finished.
+    fn update_search(&mut self, ctx: &Context, data: &DataFrameView, version: u64) {
+        if let Some((_, task)) = &self.searching {
+            if task.is_finished() {
+                let (key, task) = self.searching.take().unwrap();
+                self.search_result = Some((key, task.handle.join().unwrap()));
+                self.sorted = None;
+                self.selection = None;
+            }
+        }
+
+        let Some(key) = self.search_key(version) else {
+            self.search_result = None;
+            self.searching = None;
+            return;
+        };
+        let is_current = |current: &SearchKey| *current == key;
+        if self.search_result.as_ref().is_some_and(|(result_key, _)| is_current(result_key)) || self.searching.as_ref().is_some_and(|(task_key, _)| is_current(task_key)) {
+            return;
+        }
+
+        let mut found = self.filtered(data).clone();
+        let query_lower = key.0.to_lowercase();
+        self.searching = Some((key, ProgressTask::new(ctx, move |_| {
+            // Text columns repeat a few distinct values, so each is only compared once.
+            let mut matches: HashMap<String, bool> = HashMap::new();
+            found.filter_by_row(|_, row| {
+                row.iter().any(|item| match item {
+                    Data::Str(s) => match matches.get(s) {
+                        Some(&is_match) => is_match,
+                        None => *matches.entry(s.to_owned()).or_insert(contains_ignore_case(s, &query_lower))
+                    },
+                    _ => false
+                })
+            });
+            found
+        })));
+    }
+
+    /// Searches the shown rows for `query`, keeping only those where any text column contains it,
+    /// ignoring case. Rows of sensors that are turned off are left out.
+    fn run_search(&mut self, query: String) {
+        self.query = Some(query);
+    }
+
+    fn clear_search(&mut self) {
+        self.query = None;
+        self.search_result = None;
+        self.searching = None;
+        self.sorted = None;
+        self.selection = None;
+    }
+
+    /// The sorted, searched and filtered rows kept for the table, so that they can be carried over
+    /// to the grown data.
+    fn results_mut(&mut self) -> impl Iterator<Item = &mut DataFrameView> {
+        let sorted = self.sorted.as_mut().map(|(_, _, sorted)| sorted);
+        let searched = self.search_result.as_mut().map(|(_, found)| found);
+        let filtered = self.sensor_result.as_mut().map(|(_, found)| found);
+        sorted.into_iter().chain(searched).chain(filtered)
+    }
+
+    /// The rows of `data` found by the current search, or those of the sensors that are turned on if
+    /// nothing is being searched for. While a search is running, the rows found by the last one are
+    /// kept if they are of the same data.
+    fn searched<'a>(&'a self, data: &'a DataFrameView) -> &'a DataFrameView {
+        match &self.search_result {
+            Some((_, found)) if self.query.is_some() && Arc::ptr_eq(&found.df, &data.df) => found,
+            _ => self.filtered(data)
+        }
+    }
+
+    /// The rows to show in the table: the search results if there are any, or the rows of the
+    /// sensors that are turned on, in sorted order if the table is sorted.
+    fn shown<'a>(&'a self, data: &'a DataFrameView) -> &'a DataFrameView {
+        if let Some((_, _, sorted)) = &self.sorted {
+            return sorted;
+        }
+        self.searched(data)
+    }
+
+    /// The rows of `data` from the sensors that are turned on.
+    fn filtered<'a>(&'a self, data: &'a DataFrameView) -> &'a DataFrameView {
+        self.sensor_result.as_ref().map_or(data, |(_, found)| found)
+    }
+
+    /// Finds the sensors of newly loaded data, and filters the shown data by sensor again if it
+    /// changed since it was last filtered.
+    fn update_sensors(&mut self, shared: &DataShared) {
+        let complete = &shared.complete_data;
+        let is_current = |(df, rows): &SensorsKey| df.ptr_eq(&Arc::downgrade(&complete.df)) && *rows == complete.rows.len();
+        if !self.sensors.as_ref().is_some_and(|(key, _)| is_current(key)) {
+            let sensors = sensor_col(complete).map_or(vec![], |col_idx| complete.distinct_strs(col_idx).into_iter().map(str::to_string).collect());
+            self.sensors = Some(((Arc::downgrade(&complete.df), complete.rows.len()), sensors));
+        }
+
+        let data = &shared.shown_data;
+        let is_stale = match &self.sensor_result {
+            Some((version, found)) => *version != shared.version || !Arc::ptr_eq(&found.df, &data.df),
+            None => !self.hidden_sensors.is_empty() && sensor_col(data).is_some()
+        };
+        if is_stale {
+            self.filter_sensors(data, shared.version);
+        }
+    }
+
+    /// Turns the chip of `sensor` on or off.
+    fn toggle_sensor(&mut self, sensor: &str, data: &DataFrameView, version: u64) {
+        if !self.hidden_sensors.remove(sensor) {
+            self.hidden_sensors.insert(sensor.to_string());
+        }
+        self.filter_sensors(data, version);
+    }
+
+    /// Keeps the rows of `data` from the sensors that are turned on.
+    fn filter_sensors(&mut self, data: &DataFrameView, version: u64) {
+        self.sensor_result = sensor_col(data).filter(|_| !self.hidden_sensors.is_empty()).map(|col_idx| {
+            let mut found = data.clone();
+            found.filter_by(col_idx, |_, sensor| is_sensor_shown(sensor, &self.hidden_sensors));
+            (version, found)
+        });
+        self.sorted = None;
+        self.selection = None;
+    }
+
+    /// The offsets to scroll the pinned and scrolling parts of the table to, so that they stay on the
+    /// same rows, along with the offset they will both be at. Whichever part was scrolled since they
+    /// last matched is followed by the other.
+    fn scroll_sync(&mut self, has_pinned_table: bool) -> ([Option<f32>; 2], f32) {
+        let (pinned, scrolling) = self.scroll_offsets;
+        let target = match pinned {
+            Some(pinned) if has_pinned_table && scrolling == self.scroll_synced => pinned,
+            _ => scrolling
+        };
+        self.scroll_synced = target;
+        // A pinned part that wasn't drawn before starts at the top, so it is always scrolled.
+        let force_pinned = has_pinned_table && pinned != Some(target);
+        ([force_pinned.then_some(target), (scrolling != target).then_some(target)], target)
+    }
+
+    /// The selected rows and columns, if the selection still refers to the shown data.
+    fn selected(&self, version: u64) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        let ((anchor_row, anchor_col), (row, col), selection_version) = self.selection?;
+        if selection_version != version {
+            return None;
+        }
+
+        Some((anchor_row.min(row)..=anchor_row.max(row), anchor_col.min(col)..=anchor_col.max(col)))
+    }
+
+    /// Selects `cell`, or extends the current selection up to it.
+    fn click(&mut self, cell: TableCell, extend: bool, version: u64) {
+        self.selection = match self.selection {
+            Some((anchor, _, selection_version)) if extend && selection_version == version => Some((anchor, cell, version)),
+            _ => Some((cell, cell, version))
+        };
+    }
+
+    /// The selected cells as tab separated values, with a header line if whole rows are selected.
+    /// The selected columns are positions in `column_order`.
+    fn selection_tsv(&self, data: &DataFrameView, column_order: &[usize], version: u64) -> Option<String> {
+        let (rows, cols) = self.selected(version)?;
+        if *cols.end() >= column_order.len() {
+            return None;
+        }
+
+        // Tabs and line breaks inside a value would be read as cell boundaries.
+        let clean = |text: &str| text.replace(['\t', '\n', '\r'], " ");
 
+        let mut tsv = String::new();
+        if *cols.start() == 0 && *cols.end() == column_order.len() - 1 {
+            let header: Vec<String> = column_order.iter().map(|&col_idx| clean(data.col_name(col_idx))).collect();
+            tsv.push_str(&header.join("\t"));
+            tsv.push('\n');
         }
+        for row_idx in rows {
+            // The selection may be from before the data was filtered, so it can run past the end.
+            let row = data.try_row(row_idx)?;
+            let cells: Vec<String> = cols.clone().map(|pos| clean(&row.get_col(column_order[pos]).to_string())).collect();
+            tsv.push_str(&cells.join("\t"));
+            tsv.push('\n');
+        }
+        Some(tsv)
     }
 }
 
 
+/// Whether `haystack` contains `needle_lower`, which must already be lowercase.
+fn contains_ignore_case(haystack: &str, needle_lower: &str) -> bool {
+    haystack.to_lowercase().contains(needle_lower)
+}
+
+
+#[derive(Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct AxisOptions {
+    log: bool,
+    fixed: bool,
+    min: f64,
+    max: f64
+}
+
+impl AxisOptions {
+    fn show(&mut self, ui: &mut egui::Ui, name: &str, is_log_shown: bool) {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            ui.checkbox(&mut self.log, "Log Scale");
+            if self.log && !is_log_shown {
+                ui.colored_label(ui.visuals().warn_fg_color, "!")
+                    .on_hover_text("There are no positive values to show on a log scale, so this axis is linear.");
+            }
+            ui.checkbox(&mut self.fixed, "Fixed Range");
+        });
+
+        if self.fixed {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.min));
+                ui.label("to");
+                ui.add(egui::DragValue::new(&mut self.max));
+
+                if self.min >= self.max {
+                    ui.colored_label(Color32::RED, "!").on_hover_text("The start of the range must be less than its end.");
+                } else if self.log && self.min <= 0.0 {
+                    ui.colored_label(Color32::RED, "!").on_hover_text("A log scale range must start above zero.");
+                }
+            });
+        }
+    }
+
+    /// The fixed range of the axis in plot coordinates, if it has a valid one.
+    fn fixed_range(&self, is_log_shown: bool) -> Option<(f64, f64)> {
+        if !self.fixed || self.min >= self.max {
+            return None;
+        }
+
+        if is_log_shown {
+            (self.min > 0.0).then(|| (self.min.log10(), self.max.log10()))
+        } else {
+            Some((self.min, self.max))
+        }
+    }
+}
+
+/// A limit on how many rows are drawn, from the first, to keep very large data quick to look through.
+/// Unlike a filter it leaves the data alone, so processing and exports still see every row.
+#[derive(Copy, Clone, PartialEq)]
+struct RowCap {
+    enabled: bool,
+    max: usize
+}
+
+impl Default for RowCap {
+    fn default() -> Self {
+        RowCap { enabled: false, max: 100_000 }
+    }
+}
+
+impl RowCap {
+    /// How many of `total` rows are drawn.
+    fn rows(&self, total: usize) -> usize {
+        if self.enabled { total.min(self.max) } else { total }
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui, total: usize) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.enabled, "Show At Most")
+                .on_hover_text("Only draw the first rows, without filtering them out of processing or exports.");
+            ui.add_enabled(self.enabled, egui::DragValue::new(&mut self.max).clamp_range(1..=usize::MAX).speed(1000.0).suffix(" rows"));
+        });
+        let rows = self.rows(total);
+        if rows < total {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("Showing {} of {} rows", rows, total));
+        }
+    }
+}
+
 impl PlotTab {
-    fn new(_cc: &eframe::CreationContext) -> PlotTab {
+    fn new(cc: &eframe::CreationContext) -> PlotTab {
+        let (x_axis, y_axis) = cc.storage
+            .and_then(|store| store.get_string("plot-axes"))
+            .and_then(|axes| serde_json::from_str(&axes).ok())
+            .unwrap_or_default();
+
         PlotTab {
             x_idx: None,
             y_idx: None,
+            color_idx: None,
             resolution: 4.0,
+            style: PlotStyle::Line,
+            bins: 50,
+            x_axis,
+            y_axis,
+
+            overview: false,
+            window: None,
+            window_drag: None,
+
+            cache: None,
+            detail_cache: None,
+            histogram_cache: None,
 
-            cache: None
+            spectrum_millis: true,
+            spectrum_rows: (0, usize::MAX),
+            spectrum_cache: None,
+            spectrum_task: None,
+
+            visible_range: None,
+
+            row_cap: RowCap::default()
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        if let Ok(axes) = serde_json::to_string(&(self.x_axis, self.y_axis)) {
+            storage.set_string("plot-axes", axes);
         }
     }
+
+    /// Starts computing the spectrum for `key` in the background unless it is cached or already
+    /// being computed, and caches the result of one that has finished.
+    fn update_spectrum(&mut self, ctx: &Context, data: &DataFrameView, key: SpectrumKey) {
+        if let Some((_, task)) = &self.spectrum_task {
+            if task.is_finished() {
+                let (key, task) = self.spectrum_task.take().unwrap();
+                self.spectrum_cache = Some((key, task.handle.join().unwrap()));
+            }
+        }
+
+        let is_current = |current: &SpectrumKey| *current == key;
+        if self.spectrum_cache.as_ref().is_some_and(|(cached_key, _)| is_current(cached_key)) || self.spectrum_task.as_ref().is_some_and(|(task_key, _)| is_current(task_key)) {
+            return;
+        }
+
+        let data = data.clone();
+        let (_, x_idx, y_idx, millis, (start, end)) = key;
+        self.spectrum_task = Some((key, ProgressTask::new(ctx, move |_| spectrum_of(&data, x_idx, y_idx, millis, start..end))));
+    }
 }
 
+/// How many previously shown results are kept to undo back to.
+const HISTORY_DEPTH: usize = 10;
+
 struct DataShared {
     complete_data: DataFrameView,
     shown_data: DataFrameView,
+    undo_history: Vec<DataFrameView>,
+    redo_history: Vec<DataFrameView>,
+    /// The order the columns of the shown data are shown and exported in. This only changes how
+    /// they are presented, so the data itself is left as is.
+    column_order: Vec<usize>,
+    /// Whether the shown data compares two logs, so that cells that differ are highlighted.
+    highlight_changes: bool,
 
     version: u64
 }
@@ -84,12 +843,109 @@ struct DataShared {
 impl DataShared {
     fn new(data: DataFrameView) -> DataShared {
         DataShared {
+            column_order: (0..data.shape().cols).collect(),
+            highlight_changes: false,
             complete_data: data.clone(),
             shown_data: data,
+            undo_history: vec![],
+            redo_history: vec![],
 
             version: 0
         }
     }
+
+    /// Shows `data`, keeping the previous result to undo back to.
+    fn show(&mut self, data: DataFrameView) {
+        let previous = self.replace_shown(data);
+        self.undo_history.push(previous);
+        if self.undo_history.len() > HISTORY_DEPTH {
+            self.undo_history.remove(0);
+        }
+        self.redo_history.clear();
+    }
+
+    fn undo(&mut self) {
+        if let Some(previous) = self.undo_history.pop() {
+            let current = self.replace_shown(previous);
+            self.redo_history.push(current);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(next) = self.redo_history.pop() {
+            let current = self.replace_shown(next);
+            self.undo_history.push(current);
+        }
+    }
+
+    /// Shows a comparison of two logs from [`DataFrameView::diff`], highlighting the cells that
+    /// differ until other data is shown.
+    fn show_diff(&mut self, diff: DataFrameView) {
+        self.show(diff);
+        self.highlight_changes = true;
+    }
+
+    /// Appends `rows`, such as those newly read from a followed file, to the complete data. The
+    /// shown data follows along if it is the complete data, without adding to the undo history.
+    ///
+    /// The shown data, the history and the `held` views, such as results cached elsewhere, are let
+    /// go of the complete frame while it grows and given the grown one after, so that the rows are
+    /// added without copying it. They stay the same rows, as the rows already there don't move.
+    fn grow<'a>(&mut self, rows: &DataFrameView, held: impl IntoIterator<Item = &'a mut DataFrameView>) -> Result<(), String> {
+        let showing_complete = Arc::ptr_eq(&self.shown_data.df, &self.complete_data.df) && self.shown_data.rows == self.complete_data.rows;
+
+        let complete = &self.complete_data.df;
+        let mut sharing: Vec<&mut DataFrameView> = held.into_iter().collect();
+        sharing.push(&mut self.shown_data);
+        sharing.extend(&mut self.undo_history);
+        sharing.extend(&mut self.redo_history);
+        sharing.retain(|view| Arc::ptr_eq(&view.df, complete));
+        let placeholder = Arc::new(complete.empty_like(0));
+        for view in &mut sharing {
+            view.df = placeholder.clone();
+        }
+
+        let result = self.complete_data.extend(rows);
+        for view in sharing {
+            view.df = self.complete_data.df.clone();
+        }
+        if showing_complete {
+            self.shown_data = self.complete_data.clone();
+            self.highlight_changes = false;
+            self.version += 1;
+        }
+        result
+    }
+
+    /// Replaces the complete data with `data`, such as the loaded data with the rows of another
+    /// file appended or a column changed to another type, and shows all of it, keeping the previous
+    /// result to undo back to.
+    fn replace_complete(&mut self, data: DataFrameView) {
+        drop_in_background(std::mem::replace(&mut self.complete_data, data.clone()));
+        self.show(data);
+    }
+
+    /// Shows the complete data again, keeping the current result to undo back to.
+    fn reset(&mut self) {
+        self.show(self.complete_data.clone());
+    }
+
+    /// Swaps in `data` as the shown data. The column order is kept if `data` has the same columns,
+    /// and reset otherwise.
+    fn replace_shown(&mut self, data: DataFrameView) -> DataFrameView {
+        if !data.col_names().eq(self.shown_data.col_names()) {
+            self.column_order = (0..data.shape().cols).collect();
+        }
+        self.highlight_changes = false;
+        self.version += 1;
+        std::mem::replace(&mut self.shown_data, data)
+    }
+
+    /// Moves the column shown at position `from` to position `to`.
+    fn move_column(&mut self, from: usize, to: usize) {
+        let col_idx = self.column_order.remove(from);
+        self.column_order.insert(to, col_idx);
+    }
 }
 
 
@@ -110,7 +966,7 @@ struct App {
 
 impl App {
     fn new(cc: &eframe::CreationContext) -> App {
-        let was_maximized = cc.storage.and_then(|store| store.get_string("was-maximized")).map_or(false, |s| s == "true");
+        let was_maximized = cc.storage.and_then(|store| store.get_string("was-maximized")).is_some_and(|s| s == "true");
         if was_maximized {
             cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
         }
@@ -139,6 +995,12 @@ impl eframe::App for App {
 
         ctx.set_visuals(Visuals::light());
 
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect());
+        if !dropped.is_empty() {
+            self.import_tab.open_dropped(&dropped);
+            self.left_state = LeftState::Import;
+        }
+
         egui::SidePanel::new(Side::Left, "left-panel")
             .default_width(180.0)
             .min_width(240.0)
@@ -170,13 +1032,45 @@ impl eframe::App for App {
                         self.process_tab.show(ui, &mut self.shared);
                     }
                     LeftState::Export => {
-                        self.export_tab.show(ui, &mut self.shared);
+                        self.export_tab.show(ui, &mut self.shared, self.plot_tab.visible_range.as_ref());
                     }
                 };
         });
 
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop-overlay")));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, Color32::from_black_alpha(160));
+            painter.text(screen.center(), egui::Align2::CENTER_CENTER, "Drop .launch or .csv files to import them", egui::FontId::proportional(24.0), Color32::WHITE);
+        }
+
+        // A followed file keeps growing whichever tab is open.
+        self.import_tab.follow(&mut self.shared, |shared, rows| {
+            self.process_tab.forget_results();
+            shared.grow(rows, self.table_tab.results_mut().chain(self.process_tab.baseline_mut()))
+        });
 
         if let Some(shared) = &mut self.shared {
+            let reset_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::R);
+            // Left to a focused text field, which may use the shortcut itself.
+            let typing = ctx.memory(|m| m.focused().is_some());
+            if !typing && !self.process_tab.is_applying() && ctx.input_mut(|i| i.consume_shortcut(&reset_shortcut)) {
+                shared.reset();
+            }
+
+            // The shown columns change when steps such as Group By are applied or undone.
+            let cols = shared.shown_data.shape().cols;
+            for col_idx in [&mut self.plot_tab.x_idx, &mut self.plot_tab.y_idx, &mut self.plot_tab.color_idx] {
+                if col_idx.is_some_and(|idx| idx >= cols) {
+                    *col_idx = None;
+                }
+            }
+
+            // Filter and search again when the shown data changes, so the results stay current.
+            self.table_tab.update_sensors(shared);
+            self.table_tab.update_formats(shared);
+            self.table_tab.update_search(ctx, &shared.shown_data, shared.version);
+
             egui::SidePanel::right("plot-table-panel")
                 .resizable(true)
                 .default_width(180.0)
@@ -195,35 +1089,164 @@ impl eframe::App for App {
 
                     match self.visual_state {
                         VisualState::Table => {
-                            let _ = &self.table_tab;
-                        }
-                        VisualState::Plot => {
                             egui::Frame::group(ui.style())
                                 .show(ui, |ui| {
-                                    egui::ComboBox::new("x-axis-combo","X axis")
-                                        .selected_text(self.plot_tab.x_idx.map_or("<row number>", |n| shared.shown_data.col_name(n)))
-                                        .show_ui(ui, |ui| {
-                                            ui.selectable_value(&mut self.plot_tab.x_idx, None, "<row number>");
-                                            for (idx, col_name) in shared.shown_data.col_names().enumerate() {
-                                                ui.selectable_value(&mut self.plot_tab.x_idx, Some(idx), col_name);
+                                    ui.horizontal(|ui| {
+                                        ui.label("Search");
+
+                                        let response = ui.add(egui::TextEdit::singleline(&mut self.table_tab.search).hint_text("text in any column"));
+                                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                            if self.table_tab.search.is_empty() {
+                                                self.table_tab.clear_search();
+                                            } else {
+                                                let query = self.table_tab.search.clone();
+                                                self.table_tab.run_search(query);
+                                                self.table_tab.update_search(ui.ctx(), &shared.shown_data, shared.version);
                                             }
+                                        } else if response.changed() && self.table_tab.search.is_empty() {
+                                            self.table_tab.clear_search();
+                                        }
+                                    });
+
+                                    if self.table_tab.searching.is_some() {
+                                        ui.horizontal(|ui| {
+                                            ui.spinner();
+                                            ui.label("Searching...");
                                         });
+                                    } else if let Some((_, found)) = self.table_tab.search_result.as_ref().filter(|_| self.table_tab.query.is_some()) {
+                                        ui.label(format!("{} of {} rows match", found.shape().rows, shared.shown_data.shape().rows));
+                                    }
+                                });
 
-                                    egui::ComboBox::new("y-axis-combo","Y axis")
-                                        .selected_text(self.plot_tab.y_idx.map_or("<row number>", |n| shared.shown_data.col_name(n)))
-                                        .show_ui(ui, |ui| {
-                                            ui.selectable_value(&mut self.plot_tab.y_idx, None, "<row number>");
-                                            for (idx, col_name) in shared.shown_data.col_names().enumerate() {
-                                                ui.selectable_value(&mut self.plot_tab.y_idx, Some(idx), col_name);
-                                            }
+                            egui::Frame::group(ui.style())
+                                .show(ui, |ui| {
+                                    let total = self.table_tab.shown(&shared.shown_data).shape().rows;
+                                    self.table_tab.row_cap.show(ui, total);
+                                });
+
+                            let cols = shared.shown_data.shape().cols;
+                            if cols > 0 {
+                                egui::Frame::group(ui.style())
+                                    .show(ui, |ui| {
+                                        ui.label("Column Format");
+
+                                        let table_tab = &mut self.table_tab;
+                                        table_tab.format_col = table_tab.format_col.min(cols - 1);
+                                        column_combo(ui, "combo-format-col", &shared.shown_data, &mut table_tab.format_col);
+
+                                        let previous = shared.shown_data.col(table_tab.format_col).format();
+                                        let mut format = previous.clone();
+                                        ui.horizontal(|ui| {
+                                            ui.label("Unit");
+                                            ui.add(egui::TextEdit::singleline(&mut format.unit).hint_text("none"));
+                                        });
+                                        ui.horizontal(|ui| {
+                                            let mut has_precision = format.precision.is_some();
+                                            ui.checkbox(&mut has_precision, "Decimals");
+                                            let mut precision = format.precision.unwrap_or(2);
+                                            ui.add_enabled(has_precision, egui::DragValue::new(&mut precision).clamp_range(0..=9));
+                                            format.precision = has_precision.then_some(precision);
+                                        });
+                                        ui.horizontal(|ui| {
+                                            ui.selectable_value(&mut format.notation, Notation::Plain, "Plain");
+                                            ui.selectable_value(&mut format.notation, Notation::Thousands, "1,000s");
+                                            ui.selectable_value(&mut format.notation, Notation::Scientific, "Scientific");
                                         });
 
+                                        if format != previous {
+                                            let col_idx = table_tab.format_col;
+                                            table_tab.set_format(shared, col_idx, format);
+                                        }
+                                    });
+                            }
+                        }
+                        VisualState::Plot => {
+                            egui::Frame::group(ui.style())
+                                .show(ui, |ui| {
                                     ui.horizontal(|ui| {
-                                        ui.label("Resolution");
+                                        ui.selectable_value(&mut self.plot_tab.style, PlotStyle::Line, "Line");
+                                        ui.selectable_value(&mut self.plot_tab.style, PlotStyle::Scatter, "Scatter");
+                                        ui.selectable_value(&mut self.plot_tab.style, PlotStyle::Histogram, "Histogram");
+                                        ui.selectable_value(&mut self.plot_tab.style, PlotStyle::Spectrum, "Spectrum");
+                                    });
 
-                                        ui.add(egui::Slider::new(&mut self.plot_tab.resolution, 0.1..=100.0)
-                                            .logarithmic(true))
+                                    let is_histogram = self.plot_tab.style == PlotStyle::Histogram;
+                                    let is_spectrum = self.plot_tab.style == PlotStyle::Spectrum;
+                                    if !is_histogram {
+                                        ui.horizontal(|ui| {
+                                            optional_column_combo(ui, "x-axis-combo", &shared.shown_data, &mut self.plot_tab.x_idx, "<row number>");
+                                            ui.label(if is_spectrum { "Time" } else { "X axis" });
+                                        });
+                                    }
+
+                                    ui.horizontal(|ui| {
+                                        optional_column_combo(ui, "y-axis-combo", &shared.shown_data, &mut self.plot_tab.y_idx, "<row number>");
+                                        ui.label(if is_histogram || is_spectrum { "Column" } else { "Y axis" });
                                     });
+
+                                    if self.plot_tab.style == PlotStyle::Scatter {
+                                        ui.horizontal(|ui| {
+                                            optional_column_combo(ui, "color-combo", &shared.shown_data, &mut self.plot_tab.color_idx, "<none>");
+                                            ui.label("Color");
+
+                                            if let Some(color_idx) = self.plot_tab.color_idx {
+                                                if shared.shown_data.df.col(color_idx).data_type() == DataType::Intern {
+                                                    ui.colored_label(ui.visuals().warn_fg_color, "!")
+                                                        .on_hover_text(format!("'{}' holds text, so the points share one color.", shared.shown_data.col_name(color_idx)));
+                                                }
+                                            }
+                                        });
+                                    }
+
+                                    if is_histogram {
+                                        ui.horizontal(|ui| {
+                                            ui.label("Bins");
+
+                                            ui.add(egui::DragValue::new(&mut self.plot_tab.bins).clamp_range(1..=1000))
+                                        });
+                                    } else if is_spectrum {
+                                        ui.horizontal(|ui| {
+                                            ui.label("Time In");
+                                            ui.selectable_value(&mut self.plot_tab.spectrum_millis, true, "Milliseconds");
+                                            ui.selectable_value(&mut self.plot_tab.spectrum_millis, false, "Seconds");
+                                        });
+
+                                        let rows = shared.shown_data.shape().rows;
+                                        let (start, end) = &mut self.plot_tab.spectrum_rows;
+                                        *end = (*end).min(rows);
+                                        *start = (*start).min(*end);
+                                        ui.horizontal(|ui| {
+                                            ui.label("Rows");
+                                            ui.add(egui::DragValue::new(start).clamp_range(0..=*end));
+                                            ui.label("to");
+                                            ui.add(egui::DragValue::new(end).clamp_range(0..=rows));
+                                        });
+
+                                        if let Some(warning) = self.plot_tab.spectrum_cache.as_ref().and_then(|(_, spectrum)| spectrum.warning.as_ref()) {
+                                            ui.colored_label(ui.visuals().warn_fg_color, "! Not evenly sampled").on_hover_text(warning);
+                                        }
+                                    } else {
+                                        ui.horizontal(|ui| {
+                                            ui.label("Resolution");
+
+                                            ui.add(egui::Slider::new(&mut self.plot_tab.resolution, 0.1..=100.0)
+                                                .logarithmic(true))
+                                        });
+
+                                        // Before the first plot is drawn, assume log scales can be shown.
+                                        let [x_log_shown, y_log_shown] = self.plot_tab.cache.as_ref().map_or([true, true], |(_, _, log_shown)| *log_shown);
+                                        self.plot_tab.x_axis.show(ui, "X axis", x_log_shown);
+                                        self.plot_tab.y_axis.show(ui, "Y axis", y_log_shown);
+
+                                        ui.add_enabled(!self.plot_tab.x_axis.fixed, egui::Checkbox::new(&mut self.plot_tab.overview, "Overview"))
+                                            .on_hover_text("Show the whole plot in a strip below it. Drag across the strip to zoom in on that range, drag the range to move it, and double click to zoom back out.")
+                                            .on_disabled_hover_text("The X axis has a fixed range.");
+                                    }
+                                });
+
+                            egui::Frame::group(ui.style())
+                                .show(ui, |ui| {
+                                    self.plot_tab.row_cap.show(ui, shared.shown_data.shape().rows);
                                 });
                         }
                     }
@@ -241,73 +1264,332 @@ impl eframe::App for App {
             if let Some(data_shared) = &self.shared {
                 match self.visual_state {
                     VisualState::Table => {
-                        let data = &data_shared.shown_data;
-
-                        egui::ScrollArea::horizontal().show(ui, |ui| {
-                            egui_extras::TableBuilder::new(ui)
-                                .auto_shrink([false, false])
-                                .max_scroll_height(f32::INFINITY)
-                                .resizable(true)
-                                .striped(true)
-                                .columns(egui_extras::Column::auto().clip(true), data.shape().cols)
-                                .cell_layout(Layout::right_to_left(Align::Center))
-                                .header(28.0, |mut row| {
-                                    for col_name in data.col_names() {
-                                        row.col(|ui| {
-                                            egui::Label::new(RichText::new(col_name).family(FontFamily::Monospace).size(18.0)).truncate(true).ui(ui);
-                                        });
+                        // A chip for each sensor, to hide the rows of those that aren't of interest.
+                        let mut toggled = None;
+                        let sensors = self.table_tab.sensors.as_ref()
+                            .filter(|(_, sensors)| !sensors.is_empty() && sensor_col(&data_shared.shown_data).is_some());
+                        if let Some((_, sensors)) = sensors {
+                            ui.horizontal_wrapped(|ui| {
+                                for sensor in sensors {
+                                    let is_shown = !self.table_tab.hidden_sensors.contains(sensor);
+                                    if ui.selectable_label(is_shown, sensor).on_hover_text("Click to show or hide the rows of this sensor").clicked() {
+                                        toggled = Some(sensor.clone());
                                     }
-                                })
-                                .body(|body| {
-                                    let num_rows = data.shape().rows;
-                                    body.rows(28.0, num_rows, |mut row| {
-                                        let data_row = data.row(row.index()).iter();
-                                        for item in data_row {
-                                            row.col(|ui| {
-                                                let text = item.to_string();
-                                                ui.add(egui::Label::new(RichText::new(&text).size(15.0)).truncate(true));
-                                            });
-                                        }
-                                    });
-                                });
+                                }
+                            });
+                        }
+                        if let Some(sensor) = toggled {
+                            self.table_tab.toggle_sensor(&sensor, &data_shared.shown_data, data_shared.version);
+                            self.table_tab.update_search(ui.ctx(), &data_shared.shown_data, data_shared.version);
+                        }
+
+                        self.table_tab.update_sort(&data_shared.shown_data, data_shared.version);
+
+                        // Pinned columns are drawn in a table of their own, left of the one that scrolls
+                        // sideways. If every column is pinned, none are, so the table can still scroll.
+                        let column_order = &data_shared.column_order;
+                        let (mut pinned, mut unpinned): (Vec<usize>, Vec<usize>) = column_order.iter()
+                            .partition(|&&col_idx| self.table_tab.pinned.contains(data_shared.shown_data.col_name(col_idx)));
+                        if unpinned.is_empty() {
+                            std::mem::swap(&mut pinned, &mut unpinned);
+                        }
+                        let display_order: Vec<usize> = pinned.iter().chain(&unpinned).copied().collect();
+                        let has_pinned_table = !pinned.is_empty() || self.table_tab.row_numbers;
+                        let (force, synced) = self.table_tab.scroll_sync(has_pinned_table);
+
+                        let data = self.table_tab.shown(&data_shared.shown_data);
+                        let version = data_shared.version;
+                        let formats: Vec<ColumnFormat> = (0..data.shape().cols).map(|col_idx| data.col(col_idx).format()).collect();
+
+                        let look = TableLook {
+                            data,
+                            rows: self.table_tab.row_cap.rows(data.shape().rows),
+                            formats: &formats,
+                            sort: self.table_tab.sort,
+                            selected: self.table_tab.selected(version),
+                            highlight_changes: data_shared.highlight_changes,
+                            changed_color: ui.visuals().warn_fg_color.gamma_multiply(0.3),
+                            pinned: &self.table_tab.pinned,
+                            row_numbers: self.table_tab.row_numbers
+                        };
+                        let mut actions = TableActions::default();
+                        let mut offsets = (None, synced);
+                        // Each part is laid out top to bottom, or its header would sit beside its rows.
+                        ui.horizontal_top(|ui| {
+                            if has_pinned_table {
+                                ui.push_id("pinned-table", |ui| ui.vertical(|ui| {
+                                    offsets.0 = Some(look.show(ui, &pinned, 0, true, force[0], &mut actions));
+                                }));
+                            }
+                            egui::ScrollArea::horizontal().id_source("table-columns").show(ui, |ui| {
+                                ui.push_id("scrolling-table", |ui| ui.vertical(|ui| {
+                                    offsets.1 = look.show(ui, &unpinned, pinned.len(), false, force[1], &mut actions);
+                                }));
+                            });
                         });
+
+                        let copy_requested = ui.input(|i| i.events.iter().any(|event| matches!(event, egui::Event::Copy)));
+                        // Leave copying to a focused text field, such as a file path.
+                        if copy_requested && ui.memory(|mem| mem.focused().is_none()) {
+                            if let Some(tsv) = self.table_tab.selection_tsv(data, &display_order, version) {
+                                ui.ctx().copy_text(tsv);
+                            }
+                        }
+
+                        self.table_tab.scroll_offsets = offsets;
+                        if let Some(cell) = actions.clicked {
+                            let extend = ui.input(|i| i.modifiers.shift);
+                            self.table_tab.click(cell, extend, version);
+                        }
+                        if let Some(col_idx) = actions.clicked_header {
+                            self.table_tab.click_header(col_idx);
+                        }
+                        if let Some((name, pin)) = actions.pin {
+                            if pin {
+                                self.table_tab.pinned.insert(name);
+                            } else {
+                                self.table_tab.pinned.remove(&name);
+                            }
+                            self.table_tab.selection = None;
+                        }
+                        if let Some(row_numbers) = actions.row_numbers {
+                            self.table_tab.row_numbers = row_numbers;
+                        }
+                        if let (Some((from, to)), Some(data_shared)) = (actions.moved, &mut self.shared) {
+                            // The moved positions are in the shown order, with pinned columns first.
+                            let position = |pos: usize| data_shared.column_order.iter().position(|&col_idx| col_idx == display_order[pos]).unwrap();
+                            let (from, to) = (position(from), position(to));
+                            data_shared.move_column(from, to);
+                            self.table_tab.selection = None;
+                        }
+                        if let (Some((col_idx, value, keep_equal)), Some(data_shared)) = (actions.cell_filter, &self.shared) {
+                            // The step is added to the pipeline, whose tab shows it being applied.
+                            self.process_tab.filter_to_cell(ctx, data_shared, col_idx, value, keep_equal);
+                            self.left_state = LeftState::Filter;
+                        }
                     }
-                    VisualState::Plot => {
+                    VisualState::Plot if self.plot_tab.style == PlotStyle::Spectrum => {
                         let data = &data_shared.shown_data;
 
-                        let x_data = self.plot_tab.x_idx.map(|idx| data.col(idx));
-                        let y_data = self.plot_tab.y_idx.map(|idx| data.col(idx));
+                        let time_data = self.plot_tab.x_idx.map(|idx| data.col(idx));
+                        let col_data = self.plot_tab.y_idx.map(|idx| data.col(idx));
 
-                        let key = (data_shared.version, self.plot_tab.x_idx, self.plot_tab.y_idx, self.plot_tab.resolution);
-                        if !self.plot_tab.cache.as_ref().is_some_and(|(cached_key, _)| cached_key == &key) {
-                            let total_rows = data.shape().rows;
-                            let required_rows = ((ui.available_width() as f64 * self.plot_tab.resolution) as usize).min(total_rows);
-                            let modulus = (total_rows / required_rows).max(1);
-                            let mut points: Vec<[f64; 2]> = Vec::with_capacity(required_rows);
-                            points.extend((0..data.shape().rows).step_by(modulus).filter_map(|row_idx| {
-                                let x_point = x_data.as_ref().map_or(Data::Integer(row_idx as i32), |x_data| x_data.get_row(row_idx));
-                                let y_point = y_data.as_ref().map_or(Data::Integer(row_idx as i32), |y_data| y_data.get_row(row_idx));
-                                // let (x_point, y_point) = (x_data.get_row(row_idx), y_data.get_row(row_idx));
-                                if let (Some(x), Some(y)) = (x_point.as_float(), y_point.as_float()) {
-                                    Some([x as f64, y as f64])
-                                } else {
-                                    None
-                                }
-                            }));
+                        let (start, end) = self.plot_tab.spectrum_rows;
+                        let total_rows = self.plot_tab.row_cap.rows(data.shape().rows);
+                        let rows = start.min(total_rows)..end.min(total_rows);
+                        let key = (data_shared.version, self.plot_tab.x_idx, self.plot_tab.y_idx, self.plot_tab.spectrum_millis, (rows.start, rows.end));
+                        self.plot_tab.update_spectrum(ui.ctx(), data, key);
 
-                            self.plot_tab.cache = Some((key, points));
+                        if self.plot_tab.spectrum_task.is_some() {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label("Computing spectrum...");
+                            });
                         }
+                        // The previous spectrum stays up until the new one is ready.
+                        let points = self.plot_tab.spectrum_cache.as_ref().map_or(vec![], |(_, spectrum)| spectrum.points.clone());
+                        let line = plot::Line::new(points);
 
-                        let line = plot::Line::new(self.plot_tab.cache.as_ref().unwrap().1.clone());
-
-                        plot::Plot::new("plot")
+                        plot::Plot::new("spectrum")
                             .allow_drag(false)
-                            .x_axis_label(x_data.as_ref().map_or("<row number>", |x_data| x_data.name()))
-                            .y_axis_label(y_data.as_ref().map_or("<row number>", |x_data| x_data.name()))
+                            .x_axis_label(if time_data.is_some() { "frequency (Hz)" } else { "frequency (per row)" })
+                            .y_axis_label(col_data.as_ref().map_or("amplitude".to_string(), |col_data| format!("amplitude of {}", col_data.name())))
                             .show(ui, |plot_ui| {
                                 plot_ui.line(line);
                             });
                     }
+                    VisualState::Plot if self.plot_tab.style == PlotStyle::Histogram => {
+                        let data = &data_shared.shown_data;
+
+                        let col_data = self.plot_tab.y_idx.map(|idx| data.col(idx));
+
+                        let total_rows = self.plot_tab.row_cap.rows(data.shape().rows);
+                        let key = (data_shared.version, self.plot_tab.y_idx, self.plot_tab.bins, total_rows);
+                        if !self.plot_tab.histogram_cache.as_ref().is_some_and(|(cached_key, _)| cached_key == &key) {
+                            let values: Vec<f64> = (0..total_rows).filter_map(|row_idx| {
+                                let value = col_data.as_ref().map_or(Data::Integer(row_idx as i32), |col_data| col_data.get_row(row_idx));
+                                value.as_float().map(|value| value as f64).filter(|value| value.is_finite())
+                            }).collect();
+
+                            let bars = histogram(&values, self.plot_tab.bins).map_or(vec![], |(start, width, counts)| {
+                                counts.into_iter().enumerate().map(|(bin, count)| {
+                                    plot::Bar::new(start + (bin as f64 + 0.5) * width, count as f64).width(width)
+                                }).collect()
+                            });
+
+                            self.plot_tab.histogram_cache = Some((key, bars));
+                        }
+
+                        let chart = plot::BarChart::new(self.plot_tab.histogram_cache.as_ref().unwrap().1.clone());
+
+                        plot::Plot::new("histogram")
+                            .allow_drag(false)
+                            .x_axis_label(col_data.as_ref().map_or("<row number>".to_string(), |col_data| col_data.format().label(col_data.name())))
+                            .y_axis_label("count")
+                            .show(ui, |plot_ui| {
+                                plot_ui.bar_chart(chart);
+                            });
+                    }
+                    VisualState::Plot => {
+                        let data = &data_shared.shown_data;
+
+                        let is_scatter = self.plot_tab.style == PlotStyle::Scatter;
+                        let color_idx = self.plot_tab.color_idx.filter(|_| is_scatter);
+                        let x_data = self.plot_tab.x_idx.map(|idx| data.col(idx));
+                        let y_data = self.plot_tab.y_idx.map(|idx| data.col(idx));
+                        let color_data = color_idx.map(|idx| data.col(idx));
+
+                        let log = [self.plot_tab.x_axis.log, self.plot_tab.y_axis.log];
+                        let total_rows = self.plot_tab.row_cap.rows(data.shape().rows);
+                        let key = (data_shared.version, self.plot_tab.x_idx, self.plot_tab.y_idx, color_idx, self.plot_tab.resolution, log, total_rows);
+                        let required_rows = (ui.available_width() as f64 * self.plot_tab.resolution) as usize;
+                        if !self.plot_tab.cache.as_ref().is_some_and(|(cached_key, _, _)| cached_key == &key) {
+                            // A range picked on the overview only makes sense along the same x axis.
+                            if !self.plot_tab.cache.as_ref().is_some_and(|((_, x_idx, _, _, _, [x_log, _], _), _, _)| (*x_idx, *x_log) == (key.1, key.5[0])) {
+                                self.plot_tab.window = None;
+                            }
+
+                            let modulus = total_rows.checked_div(required_rows.min(total_rows)).unwrap_or(1).max(1);
+                            let mut points = plot_points(x_data.as_ref(), y_data.as_ref(), color_data.as_ref(), total_rows, modulus, None);
+
+                            // An axis without positive values falls back to linear, rather than showing nothing.
+                            let log_shown = [0, 1].map(|axis| log[axis] && points.iter().any(|point| point[axis] > 0.0));
+                            to_log_scale(&mut points, log_shown);
+
+                            self.plot_tab.cache = Some((key, points, log_shown));
+                        }
+
+                        let (_, overview_points, log_shown) = self.plot_tab.cache.as_ref().unwrap();
+                        let [x_log_shown, y_log_shown] = *log_shown;
+                        let show_overview = self.plot_tab.overview && !self.plot_tab.x_axis.fixed && !overview_points.is_empty();
+                        let window = self.plot_tab.window.filter(|_| show_overview);
+
+                        // Zoomed in on the overview, the rows in range are decimated on their own, so
+                        // they are shown at full resolution.
+                        if let Some(window) = window {
+                            if !self.plot_tab.detail_cache.as_ref().is_some_and(|(cached_key, cached_window, _)| cached_key == &key && *cached_window == window) {
+                                let x_range = if x_log_shown { (10f64.powf(window.0), 10f64.powf(window.1)) } else { window };
+                                let in_range = plot_points(x_data.as_ref(), y_data.as_ref(), color_data.as_ref(), total_rows, 1, Some(x_range));
+                                let modulus = in_range.len().checked_div(required_rows.min(in_range.len())).unwrap_or(1).max(1);
+                                let mut points = min_max_decimate(in_range.into_iter(), modulus);
+                                to_log_scale(&mut points, *log_shown);
+
+                                self.plot_tab.detail_cache = Some((key, window, points));
+                            }
+                        }
+                        let points = match (window, &self.plot_tab.detail_cache) {
+                            (Some(_), Some((_, _, detail_points))) => detail_points,
+                            _ => overview_points
+                        };
+                        let line = plot::Line::new(points.iter().map(|point| [point[0], point[1]]).collect::<Vec<_>>());
+                        let color_groups = if is_scatter {
+                            color_groups(points, color_data.as_ref().map_or("", |color_data| color_data.name()))
+                        } else {
+                            vec![]
+                        };
+
+                        let x_range = self.plot_tab.x_axis.fixed_range(x_log_shown);
+                        let y_range = self.plot_tab.y_axis.fixed_range(y_log_shown);
+                        let data_range = |axis: usize| data_range_of(points, axis);
+
+                        let mut plot = plot::Plot::new("plot")
+                            .allow_drag(false)
+                            .x_axis_label(x_data.as_ref().map_or("<row number>".to_string(), |x_data| x_data.format().label(x_data.name())))
+                            .y_axis_label(y_data.as_ref().map_or("<row number>".to_string(), |y_data| y_data.format().label(y_data.name())))
+                            .label_formatter(move |name, point| {
+                                let x = if x_log_shown { 10f64.powf(point.x) } else { point.x };
+                                let y = if y_log_shown { 10f64.powf(point.y) } else { point.y };
+                                let name = if name.is_empty() { String::new() } else { format!("{name}\n") };
+                                format!("{name}x = {x:.3}\ny = {y:.3}")
+                            });
+                        if x_log_shown {
+                            plot = plot.x_axis_formatter(|mark, _, _| log_tick_label(mark.value));
+                        }
+                        if y_log_shown {
+                            plot = plot.y_axis_formatter(|mark, _, _| log_tick_label(mark.value));
+                        }
+
+                        if show_overview {
+                            plot = plot.height((ui.available_height() - OVERVIEW_HEIGHT - ui.spacing().item_spacing.y).max(0.0));
+                        }
+
+                        let shown = plot.show(ui, |plot_ui| {
+                            let x_range = x_range.or(window);
+                            if (x_range.is_some() || y_range.is_some()) && !points.is_empty() {
+                                let (x_min, x_max) = x_range.unwrap_or_else(|| data_range(0));
+                                let (y_min, y_max) = y_range.unwrap_or_else(|| data_range(1));
+                                plot_ui.set_plot_bounds(plot::PlotBounds::from_min_max([x_min, y_min], [x_max, y_max]));
+                            }
+                            if is_scatter {
+                                for (color, name, group) in color_groups {
+                                    let mut scatter = plot::Points::new(group).radius(2.0).name(name);
+                                    if let Some(color) = color {
+                                        scatter = scatter.color(color);
+                                    }
+                                    plot_ui.points(scatter);
+                                }
+                            } else {
+                                plot_ui.line(line);
+                            }
+                        });
+
+                        let bounds = shown.transform.bounds();
+                        let [min, max] = [bounds.min()[0], bounds.max()[0]].map(|x| if x_log_shown { 10f64.powf(x) } else { x });
+                        self.plot_tab.visible_range = Some(VisibleRange {
+                            x_idx: self.plot_tab.x_idx,
+                            x_name: x_data.as_ref().map_or("<row number>".to_string(), |x_data| x_data.name().to_string()),
+                            min,
+                            max
+                        });
+
+                        if show_overview {
+                            let full_x = data_range_of(overview_points, 0);
+                            let (y_min, y_max) = data_range_of(overview_points, 1);
+                            let overview = plot::Plot::new("overview-plot")
+                                .height(OVERVIEW_HEIGHT)
+                                .allow_drag(false)
+                                .allow_zoom(false)
+                                .allow_scroll(false)
+                                .allow_boxed_zoom(false)
+                                .allow_double_click_reset(false)
+                                .show_axes([true, false])
+                                .show_x(false)
+                                .show_y(false)
+                                .show(ui, |plot_ui| {
+                                    plot_ui.set_plot_bounds(plot::PlotBounds::from_min_max([full_x.0, y_min], [full_x.1, y_max]));
+                                    plot_ui.line(plot::Line::new(overview_points.iter().map(|point| [point[0], point[1]]).collect::<Vec<_>>()));
+                                    if let Some((start, end)) = window {
+                                        plot_ui.polygon(plot::Polygon::new(vec![[start, y_min], [end, y_min], [end, y_max], [start, y_max]]));
+                                    }
+                                });
+
+                            let response = &overview.response;
+                            let pointer_x = response.interact_pointer_pos()
+                                .map(|pos| overview.transform.value_from_position(pos).x.clamp(full_x.0, full_x.1));
+                            if response.double_clicked() {
+                                self.plot_tab.window = None;
+                            } else if let Some(pointer_x) = pointer_x {
+                                if response.drag_started() {
+                                    self.plot_tab.window_drag = Some(match window {
+                                        Some((start, end)) if (start..=end).contains(&pointer_x) => WindowDrag::Move(pointer_x - start),
+                                        _ => WindowDrag::Select(pointer_x)
+                                    });
+                                }
+                                match (self.plot_tab.window_drag, window) {
+                                    (Some(WindowDrag::Move(offset)), Some(window)) if response.dragged() => {
+                                        self.plot_tab.window = Some(move_window(window, pointer_x - offset, full_x));
+                                    }
+                                    (Some(WindowDrag::Select(anchor)), _) if response.dragged() && anchor != pointer_x => {
+                                        self.plot_tab.window = Some((anchor.min(pointer_x), anchor.max(pointer_x)));
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            if response.drag_stopped() {
+                                self.plot_tab.window_drag = None;
+                            }
+                        }
+                    }
                 }
             } else {
                 ui.centered_and_justified(|ui| {
@@ -323,16 +1605,284 @@ impl eframe::App for App {
         self.import_tab.save(storage);
         self.process_tab.save(storage);
         self.export_tab.save(storage);
+        self.table_tab.save(storage);
+        self.plot_tab.save(storage);
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        let current_data = self.shared.take();
-        std::mem::forget(current_data);
+        // The process ends before a large frame would be freed, so exiting doesn't wait on it.
+        drop_in_background(self.shared.take());
     }
 
     fn persist_egui_memory(&self) -> bool { false }
 }
 
+/// The points to plot, with the x and y value of each row and its value of `color_data`, which is
+/// NaN if it has none. Missing x or y columns use the row number, and rows without numbers for both
+/// are left out, as are those with an x value outside of `x_range` if there is one. The points are
+/// decimated to the extremes of every `bucket` rows.
+fn plot_points(x_data: Option<&ColumnView>, y_data: Option<&ColumnView>, color_data: Option<&ColumnView>, rows: usize, bucket: usize, x_range: Option<(f64, f64)>) -> Vec<[f64; 3]> {
+    min_max_decimate((0..rows).filter_map(|row_idx| {
+        let x_point = x_data.map_or(Data::Integer(row_idx as i32), |x_data| x_data.get_row(row_idx));
+        let y_point = y_data.map_or(Data::Integer(row_idx as i32), |y_data| y_data.get_row(row_idx));
+        let color = color_data.and_then(|color_data| color_data.get_row(row_idx).as_float()).map_or(f64::NAN, |color| color as f64);
+        let in_range = |x: f32| x_range.map_or(true, |(min, max)| (min..=max).contains(&(x as f64)));
+        if let (Some(x), Some(y)) = (x_point.as_float().filter(|&x| in_range(x)), y_point.as_float()) {
+            Some([x as f64, y as f64, color])
+        } else {
+            None
+        }
+    }), bucket)
+}
+
+/// The lowest and highest value of `axis` among `points`, widened if they are equal so that the
+/// range can be shown.
+fn data_range_of(points: &[[f64; 3]], axis: usize) -> (f64, f64) {
+    let min = points.iter().map(|point| point[axis]).fold(f64::INFINITY, f64::min);
+    let max = points.iter().map(|point| point[axis]).fold(f64::NEG_INFINITY, f64::max);
+    if min < max { (min, max) } else { (min - 0.5, min + 0.5) }
+}
+
+/// Puts the axes of `points` that are `log_shown` on a log scale, dropping points that aren't
+/// positive along them.
+fn to_log_scale(points: &mut Vec<[f64; 3]>, log_shown: [bool; 2]) {
+    for axis in 0..2 {
+        if log_shown[axis] {
+            points.retain(|point| point[axis] > 0.0);
+            points.iter_mut().for_each(|point| point[axis] = point[axis].log10());
+        }
+    }
+}
+
+/// How tall the overview strip below a plot is.
+const OVERVIEW_HEIGHT: f32 = 80.0;
+
+/// `window` moved to start at `start`, but kept the same width and within `full`, the range of the
+/// whole plot.
+fn move_window(window: PlotWindow, start: f64, full: PlotWindow) -> PlotWindow {
+    let width = (window.1 - window.0).min(full.1 - full.0);
+    // Not `clamp`, which panics when rounding puts `full.1 - width` just below `full.0`.
+    let start = start.min(full.1 - width).max(full.0);
+    (start, start + width)
+}
+
+/// How many colors the range of the color column of a scatter plot is split into.
+const COLOR_BINS: usize = 16;
+
+/// A color, if the points aren't left to the default, a name for the range of values it covers,
+/// and the points with that color.
+type ColorGroup = (Option<Color32>, String, Vec<[f64; 2]>);
+
+/// Groups scatter `points` by their color value, splitting the range of values into [`COLOR_BINS`]
+/// colors from low to high. If no point has a color value, such as for text columns, they all keep
+/// the default color. Otherwise points without one are gray.
+fn color_groups(points: &[[f64; 3]], color_name: &str) -> Vec<ColorGroup> {
+    let colors = || points.iter().map(|point| point[2]).filter(|color| color.is_finite());
+    let (Some(min), Some(max)) = (colors().reduce(f64::min), colors().reduce(f64::max)) else {
+        return vec![(None, String::new(), points.iter().map(|point| [point[0], point[1]]).collect())];
+    };
+
+    let width = (max - min) / COLOR_BINS as f64;
+    let mut bins: Vec<Vec<[f64; 2]>> = vec![vec![]; COLOR_BINS];
+    let mut uncolored = vec![];
+    for point in points {
+        if point[2].is_finite() {
+            // The highest value belongs to the last bin rather than one past it.
+            let bin = if width > 0.0 { (((point[2] - min) / width) as usize).min(COLOR_BINS - 1) } else { 0 };
+            bins[bin].push([point[0], point[1]]);
+        } else {
+            uncolored.push([point[0], point[1]]);
+        }
+    }
+
+    let mut groups: Vec<ColorGroup> = bins.into_iter().enumerate()
+        .filter(|(_, bin)| !bin.is_empty())
+        .map(|(bin, group)| {
+            let color = colormap((bin as f32 + 0.5) / COLOR_BINS as f32);
+            let (low, high) = (min + bin as f64 * width, min + (bin + 1) as f64 * width);
+            (Some(color), format!("{color_name} {low:.3} to {high:.3}"), group)
+        })
+        .collect();
+    if !uncolored.is_empty() {
+        groups.push((Some(Color32::GRAY), format!("{color_name} empty"), uncolored));
+    }
+    groups
+}
+
+/// A color from dark purple through teal to yellow for `t` from 0 to 1, like the viridis colormap.
+fn colormap(t: f32) -> Color32 {
+    const STOPS: [[f32; 3]; 5] = [[68.0, 1.0, 84.0], [59.0, 82.0, 139.0], [33.0, 145.0, 140.0], [94.0, 201.0, 98.0], [253.0, 231.0, 37.0]];
+
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let idx = (position as usize).min(STOPS.len() - 2);
+    let frac = position - idx as f32;
+    let [r, g, b] = [0, 1, 2].map(|channel| {
+        (STOPS[idx][channel] + (STOPS[idx + 1][channel] - STOPS[idx][channel]) * frac).round() as u8
+    });
+    Color32::from_rgb(r, g, b)
+}
+
+/// The index and point with the lowest and highest y value in a bucket of points.
+type Extremes<const N: usize> = ((usize, [f64; N]), (usize, [f64; N]));
+
+/// Thins out `points` by keeping only the lowest and highest point of every `bucket` points, in their
+/// original order. Unlike keeping every nth point, this never drops a short spike. Points may carry
+/// values past x and y, which are kept along with them.
+fn min_max_decimate<const N: usize>(points: impl Iterator<Item=[f64; N]>, bucket: usize) -> Vec<[f64; N]> {
+    fn push_extremes<const N: usize>(decimated: &mut Vec<[f64; N]>, extremes: Option<Extremes<N>>) {
+        let Some(((low_idx, low), (high_idx, high))) = extremes else { return; };
+        match low_idx.cmp(&high_idx) {
+            std::cmp::Ordering::Less => decimated.extend([low, high]),
+            std::cmp::Ordering::Greater => decimated.extend([high, low]),
+            std::cmp::Ordering::Equal => decimated.push(low)
+        }
+    }
+
+    let mut decimated = vec![];
+    let mut extremes: Option<Extremes<N>> = None;
+    for (idx, point) in points.enumerate() {
+        let (lowest, highest) = extremes.get_or_insert(((idx, point), (idx, point)));
+        if point[1] < lowest.1[1] {
+            *lowest = (idx, point);
+        }
+        if point[1] > highest.1[1] {
+            *highest = (idx, point);
+        }
+
+        if (idx + 1) % bucket == 0 {
+            push_extremes(&mut decimated, extremes.take());
+        }
+    }
+    push_extremes(&mut decimated, extremes.take());
+
+    decimated
+}
+
+/// Labels a tick on a log scale axis, which is placed at the exponent of its value.
+fn log_tick_label(exponent: f64) -> String {
+    let value = 10f64.powf(exponent);
+    if (1e-3..1e6).contains(&value) {
+        format!("{}", (value * 1000.0).round() / 1000.0)
+    } else {
+        format!("{value:.2e}")
+    }
+}
+
+/// The spectrum of column `y_idx` over `rows` of `data`, against time column `x_idx`, or against the
+/// row number without one, in which case frequencies are in cycles per row.
+fn spectrum_of(data: &DataFrameView, x_idx: Option<usize>, y_idx: Option<usize>, millis: bool, rows: Range<usize>) -> Spectrum {
+    let time_data = x_idx.map(|idx| data.col(idx));
+    let col_data = y_idx.map(|idx| data.col(idx));
+
+    let mut times = vec![];
+    let mut values = vec![];
+    for row_idx in rows {
+        let time = time_data.as_ref().map_or(Some(row_idx as f64), |time_data| as_f64(time_data.get_row(row_idx)));
+        let value = col_data.as_ref().map_or(Some(row_idx as f64), |col_data| as_f64(col_data.get_row(row_idx)));
+        if let (Some(time), Some(value)) = (time, value.filter(|value| value.is_finite())) {
+            times.push(time);
+            values.push(value);
+        }
+    }
+
+    let (sample_rate, evenly_sampled) = sample_rate(&times).unwrap_or((1.0, true));
+    let sample_rate = if millis && time_data.is_some() { sample_rate * 1000.0 } else { sample_rate };
+    let warning = (!evenly_sampled).then(|| "The times are not evenly spaced, so the frequencies are off. Apply a Resample step first.".to_string());
+
+    Spectrum { points: spectrum(&values, sample_rate), warning }
+}
+
+/// The samples per unit of time of `times`, and whether they are evenly spaced, which they are if
+/// every interval is within 1% of the average. There is none without at least two increasing times.
+fn sample_rate(times: &[f64]) -> Option<(f64, bool)> {
+    let (&first, &last) = (times.first()?, times.last()?);
+    let interval = Some((last - first) / (times.len() - 1) as f64).filter(|&interval| interval > 0.0)?;
+    let evenly_sampled = times.windows(2).all(|pair| ((pair[1] - pair[0]) - interval).abs() <= 0.01 * interval);
+    Some((1.0 / interval, evenly_sampled))
+}
+
+/// The amplitude of each frequency in `values`, sampled `sample_rate` times per second, from 0 up to
+/// half the sample rate. The mean is taken out first, and a Hann window keeps frequencies between
+/// those measured from leaking into the rest. The values are padded with zeros to a power of two.
+fn spectrum(values: &[f64], sample_rate: f64) -> Vec<[f64; 2]> {
+    if values.len() < 2 {
+        return vec![];
+    }
+
+    let n = values.len();
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let window = |i: usize| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64).cos();
+    let mut buf = vec![[0.0; 2]; n.next_power_of_two()];
+    for (i, &value) in values.iter().enumerate() {
+        buf[i][0] = (value - mean) * window(i);
+    }
+    fft(&mut buf);
+
+    // Scaled so a sine wave's peak has its amplitude, making up for the window's lost area.
+    let window_sum: f64 = (0..n).map(window).sum();
+    let len = buf.len();
+    buf[..=len / 2].iter().enumerate().map(|(k, [re, im])| {
+        let scale = if k == 0 || k == len / 2 { 1.0 } else { 2.0 };
+        [k as f64 * sample_rate / len as f64, scale * re.hypot(*im) / window_sum]
+    }).collect()
+}
+
+/// Replaces `buf`, as pairs of real and imaginary parts, with its discrete Fourier transform. The
+/// length of `buf` must be a power of two.
+fn fft(buf: &mut [[f64; 2]]) {
+    let n = buf.len();
+
+    // Put the values in bit-reversed order, so each pass combines neighbouring halves.
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let [a_re, a_im] = buf[start + k];
+                let [b_re, b_im] = buf[start + k + len / 2];
+                let (t_re, t_im) = (b_re * cos - b_im * sin, b_re * sin + b_im * cos);
+                buf[start + k] = [a_re + t_re, a_im + t_im];
+                buf[start + k + len / 2] = [a_re - t_re, a_im - t_im];
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Splits the range of `values` into `bins` equal bins, returning the start and width of the bins
+/// along with how many values fall in each. If all the values are equal, they share a single bin.
+fn histogram(values: &[f64], bins: usize) -> Option<(f64, f64, Vec<usize>)> {
+    let min = values.iter().copied().reduce(f64::min)?;
+    let max = values.iter().copied().reduce(f64::max)?;
+    if min == max {
+        return Some((min - 0.5, 1.0, vec![values.len()]));
+    }
+
+    let bins = bins.max(1);
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for &value in values {
+        // The maximum lands exactly on the end of the last bin.
+        let bin = (((value - min) / width) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    Some((min, width, counts))
+}
+
 #[derive(Clone)]
 struct Progress {
     context: Context,
@@ -341,6 +1891,16 @@ struct Progress {
 }
 
 impl Progress {
+    /// Progress that isn't shown anywhere, for running tasks in tests.
+    #[cfg(test)]
+    fn detached() -> Progress {
+        Progress {
+            context: Context::default(),
+            contents: Arc::new((0.into(), Mutex::new(String::new()))),
+            local_progress: Cell::new(0.0)
+        }
+    }
+
     fn set_text(&self, text: String) {
         let mut lock = self.contents.1.lock().unwrap();
         *lock = text;
@@ -356,6 +1916,116 @@ impl Progress {
     }
 }
 
+/// Combo boxes that list columns, with a box at the top of the list to type part of a column's name
+/// into, so that one can be found among hundreds without scrolling.
+trait ColumnSearch {
+    /// Shows the combo box, giving `add_contents` the columns of `data` whose names contain what's
+    /// typed. Anything else `add_contents` lists, such as `<row number>`, is always shown.
+    fn show_columns<'a>(self, ui: &mut Ui, data: &'a DataFrameView, add_contents: impl FnOnce(&mut Ui, Vec<(usize, &'a str)>)) -> egui::InnerResponse<Option<()>>;
+}
+
+impl ColumnSearch for egui::ComboBox {
+    fn show_columns<'a>(self, ui: &mut Ui, data: &'a DataFrameView, add_contents: impl FnOnce(&mut Ui, Vec<(usize, &'a str)>)) -> egui::InnerResponse<Option<()>> {
+        let mut reopen = None;
+        let response = self.show_ui(ui, |ui| {
+            // The list is its own layer, named after the popup.
+            let popup_id = ui.layer_id().id;
+            let search_id = popup_id.with("column-search");
+            let frame = ui.ctx().frame_nr();
+
+            // What was typed is kept only while the list stays open, so it starts empty each time.
+            let last = ui.data_mut(|data| data.get_temp::<(u64, String)>(search_id));
+            let just_opened = !last.as_ref().is_some_and(|(last_frame, _)| last_frame + 1 >= frame);
+            let mut search = last.filter(|_| !just_opened).map(|(_, search)| search).unwrap_or_default();
+
+            let search_response = ui.add(egui::TextEdit::singleline(&mut search).hint_text("Search columns").desired_width(f32::INFINITY));
+            if just_opened {
+                search_response.request_focus();
+            }
+            // Any click outside the combo box closes its list, including one on the search box.
+            if search_response.clicked() {
+                reopen = Some(popup_id);
+            }
+
+            let columns = data.col_names().enumerate().filter(|(_, name)| column_matches(name, &search)).collect();
+            ui.data_mut(|data| data.insert_temp(search_id, (frame, search)));
+            add_contents(ui, columns);
+        });
+
+        if let Some(popup_id) = reopen {
+            ui.memory_mut(|memory| memory.open_popup(popup_id));
+        }
+        response
+    }
+}
+
+/// A combo box choosing one of the columns of `data` as `selected`, which can be searched by name.
+fn column_combo(ui: &mut Ui, id: impl Hash, data: &DataFrameView, selected: &mut usize) {
+    egui::ComboBox::from_id_source(id)
+        .wrap(true)
+        .selected_text(col_name_or_missing(data, *selected))
+        .show_columns(ui, data, |ui, columns| {
+            for (idx, col_name) in columns {
+                ui.selectable_value(selected, idx, col_name);
+            }
+        });
+}
+
+/// Like [`column_combo`], but also listing `none`, such as `<row number>`, for no column.
+fn optional_column_combo(ui: &mut Ui, id: impl Hash, data: &DataFrameView, selected: &mut Option<usize>, none: &str) {
+    egui::ComboBox::from_id_source(id)
+        .wrap(true)
+        .selected_text(selected.map_or(none, |idx| col_name_or_missing(data, idx)))
+        .show_columns(ui, data, |ui, columns| {
+            ui.selectable_value(selected, None, none);
+            for (idx, col_name) in columns {
+                ui.selectable_value(selected, Some(idx), col_name);
+            }
+        });
+}
+
+/// A combo box adding the column picked from those of `data` to the end of `cols`. Columns already
+/// in `cols` aren't listed.
+fn add_column_combo(ui: &mut Ui, id: impl Hash, data: &DataFrameView, cols: &mut Vec<usize>) {
+    egui::ComboBox::from_id_source(id)
+        .wrap(true)
+        .selected_text("Add Column")
+        .show_columns(ui, data, |ui, columns| {
+            for (idx, col_name) in columns {
+                if !cols.contains(&idx) && ui.selectable_label(false, col_name).clicked() {
+                    cols.push(idx);
+                }
+            }
+        });
+}
+
+/// The name of column `idx` of `data`, or a note that there is no such column, such as for a step
+/// of the pipeline whose column was dropped by a step before it.
+fn col_name_or_missing(data: &DataFrameView, idx: usize) -> &str {
+    if idx < data.shape().cols { data.col_name(idx) } else { "<missing column>" }
+}
+
+/// Whether a column called `name` is listed when `search` is typed into a column's combo box. Case
+/// is ignored, so `gyro` finds `imu.Gyro[2].z`.
+fn column_matches(name: &str, search: &str) -> bool {
+    name.to_lowercase().contains(&search.trim().to_lowercase())
+}
+
+/// Where [`drop_in_background`] sends values, to be dropped one after another on a thread that
+/// lives as long as the app.
+static DROPPER: OnceLock<mpsc::Sender<Box<dyn Send>>> = OnceLock::new();
+
+/// Drops `value` on another thread, since freeing a large frame can take long enough to freeze the
+/// window, such as when loading a new file over the current one.
+fn drop_in_background<T: Send + 'static>(value: T) {
+    let dropper = DROPPER.get_or_init(|| {
+        let (sender, values) = mpsc::channel::<Box<dyn Send>>();
+        std::thread::spawn(move || values.into_iter().for_each(drop));
+        sender
+    });
+    let _ = dropper.send(Box::new(value));
+}
+
 struct ProgressTask<T> {
     handle: JoinHandle<T>,
     progress: Progress
@@ -412,3 +2082,335 @@ fn main() -> eframe::Result<()> {
     };
     eframe::run_native("MIDAS Launch", options, Box::new(|cc| Box::new(App::new(cc))))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::{Arc, mpsc};
+    use std::thread::ThreadId;
+
+    use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+
+    use egui::Color32;
+
+    use super::{COLOR_BINS, DataShared, HISTORY_DEPTH, RowCap, TableTab, color_groups, column_matches, contains_ignore_case, drop_in_background, histogram, is_sensor_shown, min_max_decimate, move_window, plot_points, spectrum};
+
+    /// A frame with one integer column holding `0..rows`, so that frames are told apart by their rows.
+    fn frame(rows: usize) -> DataFrameView {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("value", DataType::Integer);
+        let mut df = builder.build();
+        for row in 0..rows {
+            df.add_row(&[Data::Integer(row as i32)]);
+        }
+        DataFrameView::from_dataframe(df)
+    }
+
+    /// Sends the thread it was dropped on.
+    struct DroppedOn(mpsc::Sender<ThreadId>);
+
+    impl Drop for DroppedOn {
+        fn drop(&mut self) {
+            let _ = self.0.send(std::thread::current().id());
+        }
+    }
+
+    #[test]
+    fn finds_columns_by_any_part_of_their_name_ignoring_case() {
+        assert!(column_matches("imu.Gyro[2].z", "gyro"));
+        assert!(column_matches("imu.Gyro[2].z", " [2].Z "));
+        assert!(column_matches("imu.Gyro[2].z", ""));
+        assert!(!column_matches("imu.Gyro[2].z", "accel"));
+        assert!(!column_matches("imu.Gyro[2].z", "gyro z"));
+    }
+
+    #[test]
+    fn drops_values_on_one_other_thread() {
+        let (sender, dropped_on) = mpsc::channel();
+        drop_in_background(DroppedOn(sender.clone()));
+        drop_in_background(DroppedOn(sender));
+
+        let first = dropped_on.recv().unwrap();
+        let second = dropped_on.recv().unwrap();
+        assert_ne!(first, std::thread::current().id());
+        assert_eq!(first, second);
+    }
+
+    /// The frequency and amplitude of the tallest point of `points`.
+    fn peak(points: &[[f64; 2]]) -> [f64; 2] {
+        *points.iter().max_by(|a, b| a[1].total_cmp(&b[1])).unwrap()
+    }
+
+    #[test]
+    fn peaks_at_the_frequency_of_a_sine() {
+        // 8 cycles over 64 samples at 64 per second lands exactly on the bin for 8 Hz.
+        let values: Vec<f64> = (0..64).map(|i| 1.0 + 2.0 * (2.0 * std::f64::consts::PI * 8.0 * i as f64 / 64.0).sin()).collect();
+        let points = spectrum(&values, 64.0);
+
+        assert_eq!(points.len(), 33);
+        assert_eq!(points[32][0], 32.0);
+        let [frequency, amplitude] = peak(&points);
+        assert_eq!(frequency, 8.0);
+        assert!((amplitude - 2.0).abs() < 0.1, "amplitude {}", amplitude);
+        // The mean is taken out, so the offset of 1 leaves almost nothing at 0 Hz.
+        assert!(points[0][1] < 0.1, "at 0 Hz {}", points[0][1]);
+    }
+
+    #[test]
+    fn pads_lengths_that_are_not_a_power_of_two() {
+        let values: Vec<f64> = (0..100).map(|i| (2.0 * std::f64::consts::PI * 10.0 * i as f64 / 100.0).sin()).collect();
+        let points = spectrum(&values, 100.0);
+
+        // Padded to 128 samples, so the bins are 100 / 128 Hz apart.
+        assert_eq!(points.len(), 65);
+        let [frequency, _] = peak(&points);
+        assert!((frequency - 10.0).abs() <= 100.0 / 128.0, "frequency {}", frequency);
+
+        assert!(spectrum(&[1.0], 1.0).is_empty());
+    }
+
+    #[test]
+    fn undoes_and_redoes_shown_results_up_to_the_history_depth() {
+        let mut shared = DataShared::new(frame(100));
+        shared.show(frame(1));
+        shared.show(frame(2));
+        shared.undo();
+        assert_eq!(shared.shown_data.shape().rows, 1);
+        shared.redo();
+        assert_eq!(shared.shown_data.shape().rows, 2);
+        shared.undo();
+        shared.undo();
+        assert_eq!(shared.shown_data.shape().rows, 100);
+        // Nothing is left to undo, and showing a new result drops what could be redone.
+        shared.undo();
+        assert_eq!(shared.shown_data.shape().rows, 100);
+        shared.show(frame(3));
+        shared.redo();
+        assert_eq!(shared.shown_data.shape().rows, 3);
+
+        for rows in 4..4 + HISTORY_DEPTH + 5 {
+            shared.show(frame(rows));
+        }
+        // Only the results shown just before the last are kept.
+        let mut undone = 0;
+        while !shared.undo_history.is_empty() {
+            shared.undo();
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_DEPTH);
+        assert_eq!(shared.shown_data.shape().rows, 4 + 5 - 1);
+    }
+
+    #[test]
+    fn grows_the_complete_frame_in_place_while_results_and_history_share_it() {
+        let mut shared = DataShared::new(frame(5));
+        let mut filtered = shared.complete_data.clone();
+        filtered.rows = vec![1, 3];
+        shared.show(filtered);
+        let mut held = shared.complete_data.clone();
+        let before = Arc::as_ptr(&shared.complete_data.df);
+
+        shared.grow(&frame(3), [&mut held]).unwrap();
+        assert_eq!(Arc::as_ptr(&shared.complete_data.df), before);
+        assert_eq!(shared.complete_data.shape().rows, 8);
+        // The views sharing the frame are of the grown one, with the rows they had.
+        assert_eq!(Arc::as_ptr(&held.df), before);
+        assert_eq!(held.shape().rows, 5);
+        assert_eq!(Arc::as_ptr(&shared.shown_data.df), before);
+        assert_eq!(shared.shown_data.rows, [1, 3]);
+        shared.undo();
+        assert_eq!(Arc::as_ptr(&shared.shown_data.df), before);
+        assert_eq!(shared.shown_data.shape().rows, 5);
+
+        // The complete data, when shown, follows along.
+        shared.reset();
+        let version = shared.version;
+        shared.grow(&frame(2), [&mut held]).unwrap();
+        assert_eq!(Arc::as_ptr(&shared.complete_data.df), before);
+        assert_eq!(shared.shown_data.shape().rows, 10);
+        assert_eq!(shared.version, version + 1);
+    }
+
+    #[test]
+    fn resets_to_the_complete_data_and_its_column_order() {
+        let mut shared = DataShared::new(frame(5).with_column("name", DataType::Intern, std::iter::empty()));
+        shared.move_column(1, 0);
+        shared.show(frame(2));
+        assert_eq!(shared.column_order, [0]);
+
+        shared.reset();
+        assert_eq!((shared.shown_data.shape().rows, shared.shown_data.shape().cols), (5, 2));
+        assert!(std::sync::Arc::ptr_eq(&shared.shown_data.df, &shared.complete_data.df));
+        assert_eq!(shared.column_order, [0, 1]);
+        // The result shown before resetting can be gone back to.
+        shared.undo();
+        assert_eq!(shared.shown_data.shape().rows, 2);
+    }
+
+    #[test]
+    fn counts_values_into_equal_bins_with_the_maximum_in_the_last() {
+        let values = [0.0, 1.0, 1.5, 2.5, 2.5, 3.9, 4.0];
+        let (start, width, counts) = histogram(&values, 4).unwrap();
+        assert_eq!((start, width), (0.0, 1.0));
+        // 4 is the end of the last bin, so it is counted in it rather than one past it.
+        assert_eq!(counts, [1, 2, 2, 2]);
+        assert_eq!(counts.iter().sum::<usize>(), values.len());
+
+        assert_eq!(histogram(&[2.0, 2.0, 2.0], 10), Some((1.5, 1.0, vec![3])));
+        assert_eq!(histogram(&[], 10), None);
+    }
+
+    #[test]
+    fn keeps_a_one_sample_spike_when_decimating() {
+        let mut points: Vec<[f64; 2]> = (0..1000).map(|i| [i as f64, (i % 7) as f64 * 0.1]).collect();
+        points[437][1] = 50.0;
+        points[802][1] = -50.0;
+        let decimated = min_max_decimate(points.iter().copied(), 100);
+
+        // The lowest and highest of each bucket of 100, so at most 2 per bucket.
+        assert!(decimated.len() <= 20);
+        assert!(decimated.contains(&[437.0, 50.0]));
+        assert!(decimated.contains(&[802.0, -50.0]));
+        assert!(decimated.windows(2).all(|pair| pair[0][0] < pair[1][0]), "points out of order");
+    }
+
+    #[test]
+    fn plots_the_rows_with_numbers_for_both_axes() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("time", DataType::Integer);
+        builder.add_column("altitude", DataType::Float);
+        builder.add_column("speed", DataType::Float);
+        let mut df = builder.build();
+        df.add_row(&[Data::Integer(10), Data::Float(1.0), Data::Float(5.0)]);
+        df.add_row(&[Data::Integer(20), Data::Null, Data::Float(6.0)]);
+        df.add_row(&[Data::Null, Data::Float(3.0), Data::Float(7.0)]);
+        df.add_row(&[Data::Integer(40), Data::Float(4.0), Data::Null]);
+        let view = DataFrameView::from_dataframe(df);
+        let (time, altitude, speed) = (view.col(0), view.col(1), view.col(2));
+
+        let points = plot_points(Some(&time), Some(&altitude), Some(&speed), 4, 1, None);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0], [10.0, 1.0, 5.0]);
+        // A row without a color value still gets a point.
+        assert_eq!(points[1][..2], [40.0, 4.0]);
+        assert!(points[1][2].is_nan());
+
+        // Without an x column, the row number is used.
+        let points = plot_points(None, Some(&altitude), None, 4, 1, None);
+        assert_eq!(points.iter().map(|point| [point[0], point[1]]).collect::<Vec<_>>(), [[0.0, 1.0], [2.0, 3.0], [3.0, 4.0]]);
+        let points = plot_points(Some(&time), Some(&altitude), None, 4, 1, Some((15.0, 40.0)));
+        assert_eq!(points.iter().map(|point| point[0]).collect::<Vec<_>>(), [40.0]);
+    }
+
+    #[test]
+    fn colors_points_from_low_to_high_and_grays_those_without_a_value() {
+        let mut points: Vec<[f64; 3]> = (0..=32).map(|i| [i as f64, 0.0, i as f64]).collect();
+        points.push([99.0, 0.0, f64::NAN]);
+        let groups = color_groups(&points, "speed");
+
+        assert_eq!(groups.len(), COLOR_BINS + 1);
+        assert_eq!(groups[0].1, "speed 0.000 to 2.000");
+        assert_eq!(groups[0].2, [[0.0, 0.0], [1.0, 0.0]]);
+        // The highest value is in the last bin rather than one past it.
+        assert_eq!(groups[COLOR_BINS - 1].2, [[30.0, 0.0], [31.0, 0.0], [32.0, 0.0]]);
+        assert_ne!(groups[0].0, groups[COLOR_BINS - 1].0);
+        assert_eq!((groups[COLOR_BINS].0, groups[COLOR_BINS].1.as_str()), (Some(Color32::GRAY), "speed empty"));
+        assert_eq!(groups[COLOR_BINS].2, [[99.0, 0.0]]);
+
+        let uncolored = color_groups(&[[1.0, 2.0, f64::NAN]], "name");
+        assert_eq!(uncolored, [(None, String::new(), vec![[1.0, 2.0]])]);
+        let same = color_groups(&[[1.0, 2.0, 5.0], [2.0, 3.0, 5.0]], "speed");
+        assert_eq!(same.len(), 1);
+        assert_eq!(same[0].2.len(), 2);
+    }
+
+    #[test]
+    fn moves_a_window_within_the_whole_plot() {
+        assert_eq!(move_window((0.0, 2.0), 5.0, (0.0, 10.0)), (5.0, 7.0));
+        assert_eq!(move_window((0.0, 2.0), 9.0, (0.0, 10.0)), (8.0, 10.0));
+        assert_eq!(move_window((0.0, 2.0), -3.0, (0.0, 10.0)), (0.0, 2.0));
+        // A window wider than the plot shrinks to it.
+        assert_eq!(move_window((0.0, 20.0), 3.0, (0.0, 10.0)), (0.0, 10.0));
+        // 0.7 - (0.7 - 0.1) rounds to just below 0.1.
+        let (start, end) = move_window((0.0, 1.0), 0.5, (0.1, 0.7));
+        assert_eq!(start, 0.1);
+        assert!((end - 0.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn clicking_a_header_sorts_up_then_down_then_not_at_all() {
+        let data = frame(3).with_column("reversed", DataType::Integer, [2, 1, 0].map(Data::Integer));
+        let mut table = TableTab::from_storage(None);
+        let shown = |table: &TableTab| table.shown(&data).col(0).iter().map(|value| value.to_string()).collect::<Vec<_>>();
+
+        table.click_header(1);
+        table.update_sort(&data, 0);
+        assert_eq!(table.sort, Some((1, true)));
+        assert_eq!(shown(&table), ["2", "1", "0"]);
+
+        table.click_header(1);
+        table.update_sort(&data, 0);
+        assert_eq!(table.sort, Some((1, false)));
+        assert_eq!(shown(&table), ["0", "1", "2"]);
+
+        table.click_header(1);
+        table.update_sort(&data, 0);
+        assert_eq!(table.sort, None);
+        assert_eq!(shown(&table), ["0", "1", "2"]);
+
+        // Clicking another column starts it over from ascending.
+        table.click_header(1);
+        table.click_header(0);
+        assert_eq!(table.sort, Some((0, true)));
+    }
+
+    #[test]
+    fn searches_text_ignoring_case() {
+        assert!(contains_ignore_case("Apogee Detected", "apogee"));
+        assert!(contains_ignore_case("STATE_BURNOUT", "burn"));
+        assert!(contains_ignore_case("Ünïcode ÄLT", "äl"));
+        assert!(contains_ignore_case("anything", ""));
+        assert!(!contains_ignore_case("Apogee", "perigee"));
+        // The needle is expected to be lowercase already.
+        assert!(!contains_ignore_case("apogee", "APOGEE"));
+    }
+
+    #[test]
+    fn hides_the_rows_of_sensors_turned_off() {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("sensor", DataType::Intern);
+        builder.add_column("value", DataType::Integer);
+        let mut df = builder.build();
+        for (sensor, value) in [(Data::Str("Imu"), 1), (Data::Str("Gps"), 2), (Data::Str("Baro"), 3), (Data::Null, 4), (Data::Str("Imu"), 5)] {
+            df.add_row(&[sensor, Data::Integer(value)]);
+        }
+        let data = DataFrameView::from_dataframe(df);
+        let hidden: HashSet<String> = ["Imu".to_string(), "Baro".to_string()].into();
+        assert!(!is_sensor_shown(&Data::Str("Imu"), &hidden));
+        assert!(is_sensor_shown(&Data::Str("Gps"), &hidden));
+        assert!(is_sensor_shown(&Data::Null, &hidden));
+
+        let mut table = TableTab::from_storage(None);
+        let shown = |table: &TableTab| table.shown(&data).col(1).iter().map(|value| value.to_string()).collect::<Vec<_>>();
+        table.toggle_sensor("Imu", &data, 0);
+        assert_eq!(shown(&table), ["2", "3", "4"]);
+        table.toggle_sensor("Baro", &data, 0);
+        // Rows without a sensor are always shown.
+        assert_eq!(shown(&table), ["2", "4"]);
+        table.toggle_sensor("Imu", &data, 0);
+        table.toggle_sensor("Baro", &data, 0);
+        assert_eq!(shown(&table), ["1", "2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn caps_the_rows_drawn_only_when_on() {
+        let cap = RowCap { enabled: true, max: 1000 };
+        assert_eq!(cap.rows(250_000), 1000);
+        assert_eq!(cap.rows(1000), 1000);
+        assert_eq!(cap.rows(10), 10);
+        assert_eq!(cap.rows(0), 0);
+        assert_eq!(RowCap { enabled: false, ..cap }.rows(250_000), 250_000);
+        assert_eq!(RowCap::default().rows(250_000), 250_000);
+    }
+}
+
@@ -1,13 +1,19 @@
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::ops::Bound;
+use std::sync::{Arc, Mutex, Weak};
 
-use egui::Ui;
+use egui::{Color32, Context, RichText, Ui};
 use eframe::Storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use dataframe::DataFrameView;
+use dataframe::{Data, DataFrame, DataFrameBuilder, DataFrameView, DataType};
 
-use crate::DataShared;
+use crate::{add_column_combo, col_name_or_missing, column_combo, drop_in_background, optional_column_combo, DataShared};
 use crate::{ProgressTask, Progress};
+use crate::file_picker::FilePicker;
+use crate::query;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum StepType {
@@ -16,6 +22,16 @@ enum StepType {
     Within,
     Sort,
     Decimate,
+    DropNull,
+    GroupBy,
+    SelectCols,
+    Derivative,
+    Resample,
+    Clip,
+    Scale,
+    Coalesce,
+    Query,
+    DecimateTime,
 }
 
 impl StepType {
@@ -25,65 +41,719 @@ impl StepType {
             StepType::ColEq => "Select",
             StepType::Within => "Within",
             StepType::Sort => "Sort",
-            StepType::Decimate => "Decimate"
+            StepType::Decimate => "Decimate",
+            StepType::DropNull => "Drop Nulls",
+            StepType::GroupBy => "Group By",
+            StepType::SelectCols => "Select Columns",
+            StepType::Derivative => "Derivative",
+            StepType::Resample => "Resample",
+            StepType::Clip => "Clip",
+            StepType::Scale => "Scale",
+            StepType::Coalesce => "Merge Columns",
+            StepType::Query => "Query",
+            StepType::DecimateTime => "Decimate By Time"
         }
     }
 
-    fn to_step(&self, id: u64) -> Step {
+    fn to_step(self, id: u64) -> Step {
         match self {
-            StepType::Fill => Step::Fill(id, true, true),
-            StepType::ColEq => Step::ColEq(id, 0, "".to_string()),
+            StepType::Fill => Step::Fill(id, true, true, FillMode::Closest),
+            StepType::ColEq => Step::ColEq(id, 0, "".to_string(), None, false),
             StepType::Within => Step::Within(id, 0, false, "".to_string(), false, "".to_string()),
-            StepType::Sort => Step::Sort(id, false, 0),
-            StepType::Decimate => Step::Decimate(id, 2)
+            StepType::Sort => Step::Sort(id, vec![(false, 0)]),
+            StepType::Decimate => Step::Decimate(id, 2),
+            StepType::DropNull => Step::DropNull(id, None),
+            StepType::GroupBy => Step::GroupBy(id, 0, vec![]),
+            StepType::SelectCols => Step::SelectCols(id, vec![]),
+            StepType::Derivative => Step::Derivative(id, 0, 1),
+            StepType::Resample => Step::Resample(id, 1, 100.0, true),
+            StepType::Clip => Step::Clip(id, 0, None, None, ClipMode::Clamp),
+            StepType::Scale => Step::Scale(id, 0, 1.0, 0.0),
+            StepType::Coalesce => Step::Coalesce(id, vec![], "".to_string()),
+            StepType::Query => Step::Query(id, "".to_string()),
+            StepType::DecimateTime => Step::DecimateTime(id, 1, 100, None)
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+enum Aggregate {
+    Count,
+    Mean,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    const ALL: [Aggregate; 4] = [Aggregate::Count, Aggregate::Mean, Aggregate::Min, Aggregate::Max];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Mean => "mean",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max"
+        }
+    }
+
+    fn data_type(&self, col_ty: DataType) -> DataType {
+        match self {
+            Aggregate::Count => DataType::Integer,
+            Aggregate::Mean => DataType::Float,
+            Aggregate::Min | Aggregate::Max => col_ty
+        }
+    }
+
+    /// Aggregates the non-empty values in `values`. Mean is empty for text columns, and every
+    /// aggregate other than count is empty if there are no values.
+    fn apply<'a>(&self, values: impl Iterator<Item=Data<'a>>) -> Data<'a> {
+        let values = values.filter(|value| !value.is_null());
+        match self {
+            Aggregate::Count => Data::Integer(values.count() as i32),
+            Aggregate::Mean => {
+                let mut count = 0;
+                let mut sum = 0.0;
+                for value in values {
+                    let (Data::Integer(_) | Data::Unsigned(_) | Data::Float(_)) = value else {
+                        return Data::Null;
+                    };
+                    sum += value.as_float().unwrap() as f64;
+                    count += 1;
+                }
+                if count == 0 { Data::Null } else { Data::Float((sum / count as f64) as f32) }
+            }
+            Aggregate::Min => values.reduce(|a, b| if b.compare(&a).is_some_and(|ord| ord.is_lt()) { b } else { a }).unwrap_or_default(),
+            Aggregate::Max => values.reduce(|a, b| if b.compare(&a).is_some_and(|ord| ord.is_gt()) { b } else { a }).unwrap_or_default()
+        }
+    }
+}
+
+/// What a Fill step puts in the empty cells.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+enum FillMode {
+    /// The closest non-empty cell in the direction of the fill.
+    #[default]
+    Closest,
+    /// A typed out value, put in each number column it can be read as.
+    Constant(String),
+    /// The value linearly interpolated over the given column between the closest non-empty cells
+    /// before and after.
+    Linear(usize),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+enum ClipMode {
+    /// Values outside the range are set to the bound they passed.
+    Clamp,
+    /// Values outside the range are emptied.
+    Nullify,
+}
+
+/// Clips the values of `col_idx` to the range from `lower` to `upper`, either of which may be left
+/// open. Values on a bound are kept, as are empty cells.
+fn clip(mut df: DataFrameView, col_idx: usize, lower: Option<f64>, upper: Option<f64>, mode: ClipMode, progress: &Progress) -> Result<DataFrameView, String> {
+    if let (Some(lower), Some(upper)) = (lower, upper) {
+        if lower > upper {
+            return Err(format!("Clip range starts at {} but ends below it at {}.", lower, upper));
+        }
+    }
+
+    let ty = df.df.col(col_idx).data_type();
+    if ty == DataType::Intern {
+        return Err(format!("Clip needs a number column, but '{}' holds text.", df.col_name(col_idx)));
+    }
+    // The bounds as values of the column. For whole numbers, they are rounded into the range.
+    let bound = |bound: f64, is_lower: bool| match ty {
+        DataType::Integer => Data::Integer(if is_lower { bound.ceil() } else { bound.floor() } as i32),
+        DataType::Unsigned => Data::Unsigned(if is_lower { bound.ceil() } else { bound.floor() } as u32),
+        DataType::Float | DataType::Intern => Data::Float(bound as f32)
+    };
+
+    let rows = df.shape().rows;
+    for row_idx in 0..rows {
+        let Some(value) = as_f64(df.get_by_index(col_idx, row_idx)) else {
+            continue;
+        };
+        let clipped = match (lower, upper) {
+            (Some(lower), _) if value < lower => Some(bound(lower, true)),
+            (_, Some(upper)) if value > upper => Some(bound(upper, false)),
+            _ => None
+        };
+        if let Some(clipped) = clipped {
+            let clipped = if mode == ClipMode::Clamp { clipped } else { Data::Null };
+            df.set_by_index(col_idx, row_idx, clipped);
+        }
+
+        if row_idx % 3000 == 0 {
+            progress.set(row_idx as f32 / rows as f32);
+        }
+    }
+    progress.set(1.0);
+
+    Ok(df)
+}
+
+/// Fills the empty cells of each number column that `value` can be read as the type of.
+fn fill_constant(mut df: DataFrameView, value: &str, progress: &Progress) -> Result<DataFrameView, String> {
+    if value.trim().parse::<f64>().is_err() {
+        return Err(format!("Fill value '{}' isn't a number.", value));
+    }
+
+    let shape = df.shape();
+    let values: Vec<Data> = (0..shape.cols).map(|col_idx| match df.df.col(col_idx).data_type() {
+        DataType::Intern => Data::Null,
+        ty => ty.parse_str(value.trim())
+    }).collect();
+
+    for row_idx in 0..shape.rows {
+        let mut row = df.row_mut(row_idx);
+        for (col_idx, value) in values.iter().enumerate() {
+            if !value.is_null() && row.get_col_raw(col_idx).is_none() {
+                row.set_col(col_idx, *value);
+            }
+        }
+        progress.set(row_idx as f32 / shape.rows as f32);
+    }
+
+    Ok(df)
+}
+
+/// Fills the empty cells of each number column with the value linearly interpolated over
+/// `time_idx` between the closest non-empty cells before and after, rounded for whole number
+/// columns. Cells at the start or end of a column with no value on one side stay empty, as do
+/// cells in rows without a time and text columns.
+fn fill_linear(mut df: DataFrameView, time_idx: usize, progress: &Progress) -> Result<DataFrameView, String> {
+    let shape = df.shape();
+    let time_col = df.col(time_idx);
+    let times: Vec<Option<f64>> = (0..shape.rows).map(|row_idx| as_f64(time_col.get_row(row_idx))).collect();
+    let mut last_time = None;
+    for (row_idx, &time) in times.iter().enumerate() {
+        let Some(time) = time else { continue; };
+        if last_time.is_some_and(|last_time| time < last_time) {
+            return Err(format!("Fill needs '{}' to be sorted to interpolate over it, but it decreases at row {}.", time_col.name(), row_idx));
+        }
+        last_time = Some(time);
+    }
+
+    for col_idx in 0..shape.cols {
+        let ty = df.df.col(col_idx).data_type();
+        if col_idx == time_idx || ty == DataType::Intern {
+            continue;
+        }
+
+        // The time and value of the last non-empty cell, and the empty rows since it.
+        let mut before: Option<(f64, f64)> = None;
+        let mut gap = vec![];
+        for (row_idx, &time) in times.iter().enumerate() {
+            let Some(time) = time else { continue; };
+            let Some(value) = as_f64(df.get_by_index(col_idx, row_idx)) else {
+                if before.is_some() {
+                    gap.push(row_idx);
+                }
+                continue;
+            };
+
+            if let Some((before_time, before_value)) = before {
+                for gap_idx in gap.drain(..) {
+                    let gap_time = times[gap_idx].unwrap();
+                    let filled = if time == before_time {
+                        before_value
+                    } else {
+                        before_value + (value - before_value) * (gap_time - before_time) / (time - before_time)
+                    };
+                    // Values between two of the column's values always fit in its type.
+                    let filled = match ty {
+                        DataType::Integer => Data::Integer(filled.round() as i32),
+                        DataType::Unsigned => Data::Unsigned(filled.round() as u32),
+                        _ => Data::Float(filled as f32)
+                    };
+                    df.set_by_index(col_idx, gap_idx, filled);
+                }
+            }
+            before = Some((time, value));
+        }
+
+        progress.set((col_idx + 1) as f32 / shape.cols as f32);
+    }
+
+    Ok(df)
+}
+
+/// Replaces each value of `col_idx` with `gain * value + offset`, such as to convert units or apply a
+/// calibration. The column keeps its type, so whole numbers are rounded, and results that don't fit
+/// in the column are emptied. Empty cells are kept.
+fn scale(mut df: DataFrameView, col_idx: usize, gain: f64, offset: f64) -> Result<DataFrameView, String> {
+    let ty = df.df.col(col_idx).data_type();
+    if ty == DataType::Intern {
+        return Err(format!("Scale needs a number column, but '{}' holds text.", df.col_name(col_idx)));
+    }
+
+    df.map_column(col_idx, |value| {
+        let Some(scaled) = as_f64(value).map(|value| gain * value + offset) else {
+            return Data::Null;
+        };
+        match ty {
+            // Casting would saturate, so values out of range are checked for first.
+            DataType::Integer if (i32::MIN as f64..=i32::MAX as f64).contains(&scaled.round()) => Data::Integer(scaled.round() as i32),
+            DataType::Unsigned if (0.0..=u32::MAX as f64).contains(&scaled.round()) => Data::Unsigned(scaled.round() as u32),
+            DataType::Float => Data::Float(scaled as f32),
+            _ => Data::Null
+        }
+    });
+
+    Ok(df)
+}
+
+/// The type of a column merging columns of `types`. Columns of one type merge into that type,
+/// numbers of different types merge into floats, and text merged with numbers stays text.
+fn coalesced_type(types: impl IntoIterator<Item=DataType>) -> Option<DataType> {
+    types.into_iter().reduce(|merged, ty| match (merged, ty) {
+        (a, b) if a == b => a,
+        (DataType::Intern, _) | (_, DataType::Intern) => DataType::Intern,
+        _ => DataType::Float
+    })
+}
+
+/// The name and type of the column merging `cols`. Without a name, the column is named after the
+/// merged columns.
+fn coalesced_column(df: &DataFrameView, cols: &[usize], name: &str) -> Result<(String, DataType), String> {
+    let Some(ty) = coalesced_type(cols.iter().map(|&col_idx| df.df.col(col_idx).data_type())) else {
+        return Err("Merge Columns needs at least one column to merge.".to_string());
+    };
+    let name = if name.is_empty() {
+        format!("coalesce({})", cols.iter().map(|&col_idx| df.col_name(col_idx)).collect::<Vec<_>>().join(", "))
+    } else {
+        name.to_string()
+    };
+    Ok((name, ty))
+}
+
+/// Adds a column named `name` holding the first non-empty value of `cols` in each row, such as to
+/// join a quantity logged by several packet variants into one column. Rows empty in every column
+/// stay empty. See [`coalesced_column`] for its name and type.
+fn coalesce(df: &DataFrameView, cols: &[usize], name: &str, progress: &Progress) -> Result<DataFrameView, String> {
+    let (name, ty) = coalesced_column(df, cols, name)?;
+
+    let rows = df.shape().rows;
+    let cols: Vec<_> = cols.iter().map(|&col_idx| df.col(col_idx)).collect();
+    let mut merged = Vec::with_capacity(rows);
+    for row_idx in 0..rows {
+        merged.push(cols.iter().map(|col| col.get_row(row_idx)).find(|value| !value.is_null()).unwrap_or_default());
+
+        if row_idx % 3000 == 0 {
+            progress.set(0.5 * row_idx as f32 / rows as f32);
+        }
+    }
+
+    // Numbers merged into a text column are written out as text.
+    let merged = if ty == DataType::Intern {
+        let texts: Vec<Option<String>> = merged.iter().map(|value| (!value.is_null()).then(|| value.to_string())).collect();
+        df.with_column(name, ty, texts.iter().map(|text| text.as_deref().map_or(Data::Null, Data::Str)))
+    } else {
+        df.with_column(name, ty, merged)
+    };
+    progress.set(1.0);
+
+    Ok(merged)
+}
+
+/// Numeric values as `f64`, which unlike `as_float` keeps full precision for large timestamps.
+pub(crate) fn as_f64(data: Data) -> Option<f64> {
+    match data {
+        Data::Integer(num) => Some(num as f64),
+        Data::Unsigned(num) => Some(num as f64),
+        Data::Float(num) => Some(num as f64),
+        Data::Str(_) | Data::Null => None
+    }
+}
+
+/// Keeps a row only if its time is at least `interval` milliseconds past the last row kept, or the
+/// last row kept with the same value of `group_idx` if there is one, such as one per sensor. Rows
+/// without a time are kept. The times must not decrease, so sort by them first.
+fn decimate_time(df: &mut DataFrameView, time_idx: usize, interval: u32, group_idx: Option<usize>, progress: &Progress) {
+    let rows = df.shape().rows as f32;
+    // Groups are told apart by their raw cell, which is unique per value.
+    let mut last_kept: HashMap<Option<NonZeroU32>, f64> = HashMap::new();
+
+    progress.set(0.0);
+    df.filter_by_row(|i, row| {
+        if i % 3000 == 0 {
+            progress.set(i as f32 / rows);
+        }
+        let Some(time) = as_f64(row.get_col(time_idx)) else { return true; };
+        let group = group_idx.and_then(|group_idx| row.get_col_raw(group_idx));
+        match last_kept.get(&group) {
+            Some(&last) if time - last < interval as f64 => false,
+            _ => {
+                last_kept.insert(group, time);
+                true
+            }
+        }
+    });
+    progress.set(1.0);
+}
+
+/// The time between samples for a rate in samples per second, in milliseconds if `is_millis`.
+fn resample_interval(rate: f64, is_millis: bool) -> Result<f64, String> {
+    if rate <= 0.0 || rate.is_nan() {
+        return Err("Resample rate must be above zero.".to_string());
+    }
+    Ok(if is_millis { 1000.0 / rate } else { 1.0 / rate })
+}
+
+/// The type of each column once resampled at `interval` over `time_idx`.
+fn resampled_types(df: &DataFrameView, time_idx: usize, interval: f64) -> Vec<DataType> {
+    (0..df.shape().cols).map(|col_idx| {
+        match df.df.col(col_idx).data_type() {
+            ty if col_idx == time_idx && interval.fract() == 0.0 => ty,
+            DataType::Intern => DataType::Intern,
+            _ => DataType::Float
+        }
+    }).collect()
+}
+
+fn resampled_columns(df: &DataFrameView, data_types: &[DataType]) -> DataFrameBuilder {
+    let mut builder = DataFrameBuilder::new();
+    for (col_idx, ty) in data_types.iter().enumerate() {
+        builder.add_column(df.col_name(col_idx), *ty);
+    }
+    builder
+}
+
+/// Resampling stops short of filling all memory when the rate is far too high.
+const MAX_RESAMPLED_ROWS: usize = 50_000_000;
+
+/// Samples every column at times `interval` apart, from the first to the last time in `time_idx`.
+/// Numeric columns are interpolated linearly between the rows around each time, and text columns
+/// take the value of the nearest row. Times before the first or after the last value of a numeric
+/// column are left empty. The times must not decrease. The time column keeps its type if the times
+/// are whole numbers apart, and becomes a float column otherwise, so that the times aren't rounded
+/// off the grid.
+fn resample(df: &DataFrameView, time_idx: usize, interval: f64, progress: &Progress) -> Result<DataFrameView, String> {
+    let shape = df.shape();
+    let time_col = df.col(time_idx);
+
+    // The rows with a time, in order.
+    let mut samples: Vec<(f64, usize)> = Vec::with_capacity(shape.rows);
+    for row_idx in 0..shape.rows {
+        if let Some(time) = as_f64(time_col.get_row(row_idx)) {
+            if samples.last().is_some_and(|&(prev_time, _)| time < prev_time) {
+                return Err(format!("Resample needs '{}' to be sorted, but it decreases at row {}.", time_col.name(), row_idx));
+            }
+            samples.push((time, row_idx));
+        }
+    }
+    let (Some(&(start, _)), Some(&(end, _))) = (samples.first(), samples.last()) else {
+        return Err(format!("Resample needs '{}' to have values.", time_col.name()));
+    };
+
+    let len = ((end - start) / interval).floor() as usize + 1;
+    if len > MAX_RESAMPLED_ROWS {
+        return Err(format!("Resample would make {} rows, lower the rate.", len));
+    }
+    let time_at = |idx: usize| start + idx as f64 * interval;
+
+    let data_types = resampled_types(df, time_idx, interval);
+    let mut resampled = resampled_columns(df, &data_types).build_with_capacity(len);
+    for _ in 0..len {
+        resampled.add_null_row();
+    }
+
+    for (col_idx, &ty) in data_types.iter().enumerate() {
+        let col = df.col(col_idx);
+        if col_idx == time_idx {
+            for idx in 0..len {
+                let time = time_at(idx);
+                let value = match ty {
+                    DataType::Integer => Data::Integer(time.round() as i32),
+                    DataType::Unsigned => Data::Unsigned(time.round() as u32),
+                    _ => Data::Float(time as f32)
+                };
+                resampled.row_mut(idx).set_col(col_idx, value);
+            }
+        } else if ty == DataType::Intern {
+            let mut next = 0;
+            for idx in 0..len {
+                let time = time_at(idx);
+                while next < samples.len() && samples[next].0 < time {
+                    next += 1;
+                }
+                let nearest = match (next.checked_sub(1).map(|prev| samples[prev]), samples.get(next).copied()) {
+                    (Some(before), Some(after)) => if after.0 - time < time - before.0 { after } else { before },
+                    (before, after) => before.or(after).unwrap()
+                };
+                resampled.row_mut(idx).set_col(col_idx, col.get_row(nearest.1));
+            }
+        } else {
+            let points: Vec<(f64, f64)> = samples.iter()
+                .filter_map(|&(time, row_idx)| as_f64(col.get_row(row_idx)).map(|value| (time, value)))
+                .collect();
+            let mut next = 0;
+            for idx in 0..len {
+                let time = time_at(idx);
+                while next < points.len() && points[next].0 < time {
+                    next += 1;
+                }
+                let value = match (next.checked_sub(1).map(|prev| points[prev]), points.get(next)) {
+                    (_, Some(&(after_time, after_value))) if after_time == time => after_value,
+                    (Some((before_time, before_value)), Some(&(after_time, after_value))) => {
+                        before_value + (after_value - before_value) * (time - before_time) / (after_time - before_time)
+                    }
+                    _ => continue
+                };
+                resampled.row_mut(idx).set_col(col_idx, Data::Float(value as f32));
+            }
+        }
+
+        progress.set((col_idx + 1) as f32 / shape.cols as f32);
+    }
+
+    Ok(DataFrameView::from_dataframe(resampled))
+}
+
+/// Data without rows, with the columns of `df`, for when only its columns matter.
+fn columns_of(df: &DataFrameView) -> DataFrameView {
+    let mut builder = DataFrameBuilder::new();
+    for col_idx in 0..df.shape().cols {
+        builder.add_column(df.col_name(col_idx), df.df.col(col_idx).data_type());
+    }
+    DataFrameView::from_dataframe(builder.build())
+}
+
+/// The columns each of `steps` is applied to, which are those given by the enabled steps before it.
+fn step_inputs(steps: &[PipelineStep], data: &DataFrameView) -> Vec<DataFrameView> {
+    let mut columns = columns_of(data);
+    let mut inputs = Vec::with_capacity(steps.len());
+    for step in steps {
+        inputs.push(columns.clone());
+        if step.enabled {
+            columns = step.step.output_columns(&columns);
+        }
+    }
+    inputs
+}
+
+/// The columns of data grouped by `key_idx`: the key, the size of each group, then each aggregate.
+fn grouped_columns(df: &DataFrameView, key_idx: usize, aggregates: &[(Aggregate, usize)]) -> DataFrameBuilder {
+    // The builder gives names that are already taken, such as 'count' when the key column has that
+    // name, a '.1' suffix.
+    let mut builder = DataFrameBuilder::new();
+    builder.add_column(df.col_name(key_idx), df.df.col(key_idx).data_type());
+    builder.add_column("count", DataType::Integer);
+    for (aggregate, col_idx) in aggregates {
+        let name = format!("{}({})", aggregate.name(), df.col_name(*col_idx));
+        builder.add_column(name, aggregate.data_type(df.df.col(*col_idx).data_type()));
+    }
+    builder
+}
+
+fn derivative_name(df: &DataFrameView, value_idx: usize, time_idx: usize) -> String {
+    format!("d({})/d({})", df.col_name(value_idx), df.col_name(time_idx))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 enum Step {
-    Fill(u64, bool, bool),
-    ColEq(u64, usize, String),
+    /// Whether to fill downwards, whether to also fill the cells at the start (or end, upwards)
+    /// with no cell to fill from, and what to fill with. Pipelines from before the modes fill from
+    /// the closest cell.
+    Fill(u64, bool, bool, #[serde(default)] FillMode),
+    /// The column, the value to keep the rows equal to, for float columns how far off a value may
+    /// be and still count as equal, and whether to drop the equal rows instead of keeping them.
+    /// Pipelines from before the tolerance have none, and keep the equal rows.
+    ColEq(u64, usize, String, #[serde(default)] Option<f64>, #[serde(default)] bool),
     Within(u64, usize, bool, String, bool, String),
-    Sort(u64, bool, usize),
+    Sort(u64, Vec<(bool, usize)>),
     Decimate(u64, usize),
+    DropNull(u64, Option<usize>),
+    GroupBy(u64, usize, Vec<(Aggregate, usize)>),
+    SelectCols(u64, Vec<usize>),
+    Derivative(u64, usize, usize),
+    /// The time column, the rate in samples per second, and whether the times are in milliseconds
+    /// rather than seconds.
+    Resample(u64, usize, f64, bool),
+    /// The column, the lower and upper bounds if there are any, and what to do with values outside
+    /// of them.
+    Clip(u64, usize, Option<f64>, Option<f64>, ClipMode),
+    /// The column, and the gain and offset each value is replaced with `gain * value + offset` by.
+    Scale(u64, usize, f64, f64),
+    /// The columns to merge, in order of preference, and the name of the merged column.
+    Coalesce(u64, Vec<usize>, String),
+    /// A filter typed out as text, which refers to columns by name.
+    Query(u64, String),
+    /// The time column, in milliseconds, the least time between kept rows, and the column whose
+    /// values each keep their own rows, if any.
+    DecimateTime(u64, usize, u32, Option<usize>),
+}
+
+/// A step of the pipeline, which can be turned off to skip it without losing its settings. Steps
+/// that are on are saved as the bare step, as they were before steps could be turned off.
+#[derive(Clone, Serialize, Deserialize)]
+struct PipelineStep {
+    #[serde(flatten)]
+    step: Step,
+    #[serde(default = "PipelineStep::default_enabled", skip_serializing_if = "PipelineStep::is_enabled")]
+    enabled: bool
+}
+
+impl PipelineStep {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn is_enabled(enabled: &bool) -> bool {
+        *enabled
+    }
+}
+
+impl From<Step> for PipelineStep {
+    fn from(step: Step) -> Self {
+        PipelineStep { step, enabled: true }
+    }
 }
 
 impl Step {
     fn ty(&self) -> StepType {
         match self {
-            Step::Fill(_, _, _) => StepType::Fill,
-            Step::ColEq(_, _, _) => StepType::ColEq,
+            Step::Fill(..) => StepType::Fill,
+            Step::ColEq(..) => StepType::ColEq,
             Step::Within(_, _, _, _, _, _) => StepType::Within,
-            Step::Sort(_, _, _) => StepType::Sort,
+            Step::Sort(_, _) => StepType::Sort,
             Step::Decimate(_, _) => StepType::Decimate,
+            Step::DropNull(_, _) => StepType::DropNull,
+            Step::GroupBy(_, _, _) => StepType::GroupBy,
+            Step::SelectCols(_, _) => StepType::SelectCols,
+            Step::Derivative(_, _, _) => StepType::Derivative,
+            Step::Resample(_, _, _, _) => StepType::Resample,
+            Step::Clip(_, _, _, _, _) => StepType::Clip,
+            Step::Scale(_, _, _, _) => StepType::Scale,
+            Step::Coalesce(_, _, _) => StepType::Coalesce,
+            Step::Query(_, _) => StepType::Query,
+            Step::DecimateTime(_, _, _, _) => StepType::DecimateTime,
+        }
+    }
+
+    fn set_id(&mut self, new_id: u64) {
+        match self {
+            Step::Fill(id, ..) => *id = new_id,
+            Step::ColEq(id, ..) => *id = new_id,
+            Step::Within(id, _, _, _, _, _) => *id = new_id,
+            Step::Sort(id, _) => *id = new_id,
+            Step::Decimate(id, _) => *id = new_id,
+            Step::DropNull(id, _) => *id = new_id,
+            Step::GroupBy(id, _, _) => *id = new_id,
+            Step::SelectCols(id, _) => *id = new_id,
+            Step::Derivative(id, _, _) => *id = new_id,
+            Step::Resample(id, _, _, _) => *id = new_id,
+            Step::Clip(id, _, _, _, _) => *id = new_id,
+            Step::Scale(id, _, _, _) => *id = new_id,
+            Step::Coalesce(id, _, _) => *id = new_id,
+            Step::Query(id, _) => *id = new_id,
+            Step::DecimateTime(id, _, _, _) => *id = new_id,
         }
     }
 
     fn id(&self) -> u64 {
         match self {
-            Step::Fill(id, _, _) => *id,
-            Step::ColEq(id, _, _) => *id,
+            Step::Fill(id, ..) => *id,
+            Step::ColEq(id, ..) => *id,
             Step::Within(id, _, _, _, _, _) => *id,
-            Step::Sort(id, _, _) => *id,
+            Step::Sort(id, _) => *id,
             Step::Decimate(id, _) => *id,
+            Step::DropNull(id, _) => *id,
+            Step::GroupBy(id, _, _) => *id,
+            Step::SelectCols(id, _) => *id,
+            Step::Derivative(id, _, _) => *id,
+            Step::Resample(id, _, _, _) => *id,
+            Step::Clip(id, _, _, _, _) => *id,
+            Step::Scale(id, _, _, _) => *id,
+            Step::Coalesce(id, _, _) => *id,
+            Step::Query(id, _) => *id,
+            Step::DecimateTime(id, _, _, _) => *id,
         }
     }
 
-    fn apply(&self, mut df: DataFrameView, progress: &Progress) -> DataFrameView {
+    /// The step's settings as text, which is the same for two steps exactly when they do the same
+    /// thing, whatever their ids.
+    fn settings_key(&self) -> String {
+        let mut step = self.clone();
+        step.set_id(0);
+        serde_json::to_string(&step).unwrap()
+    }
+
+    fn columns(&self) -> Vec<usize> {
         match self {
-            &Step::Fill(_, _, and_before) => {
+            Step::Fill(_, _, _, FillMode::Linear(time_idx)) => vec![*time_idx],
+            Step::Fill(..) => vec![],
+            Step::ColEq(_, col_idx, ..) => vec![*col_idx],
+            Step::Within(_, col_idx, _, _, _, _) => vec![*col_idx],
+            Step::Sort(_, keys) => keys.iter().map(|(_, col_idx)| *col_idx).collect(),
+            Step::Decimate(_, _) => vec![],
+            Step::DropNull(_, col_idx) => col_idx.iter().copied().collect(),
+            Step::GroupBy(_, key_idx, aggregates) => {
+                std::iter::once(*key_idx).chain(aggregates.iter().map(|(_, col_idx)| *col_idx)).collect()
+            }
+            Step::SelectCols(_, cols) => cols.clone(),
+            Step::Derivative(_, value_idx, time_idx) => vec![*value_idx, *time_idx],
+            Step::Resample(_, time_idx, _, _) => vec![*time_idx],
+            Step::Clip(_, col_idx, _, _, _) => vec![*col_idx],
+            Step::Scale(_, col_idx, _, _) => vec![*col_idx],
+            Step::Coalesce(_, cols, _) => cols.clone(),
+            Step::Query(_, _) => vec![],
+            Step::DecimateTime(_, time_idx, _, group_idx) => std::iter::once(*time_idx).chain(*group_idx).collect(),
+        }
+    }
+
+    fn check_columns(&self, df: &DataFrameView) -> Result<(), String> {
+        let cols = df.shape().cols;
+        match self.columns().into_iter().find(|&col_idx| col_idx >= cols) {
+            Some(col_idx) => Err(format!("{} step uses column {}, but the data only has {} columns.", self.ty().name(), col_idx, cols)),
+            None => Ok(())
+        }
+    }
+
+    /// Data without rows, with the columns this step gives when applied to data with the columns of
+    /// `input`, so that the columns picked by the steps after it are listed as they will be. A step
+    /// that would fail gives the columns of `input`.
+    fn output_columns(&self, input: &DataFrameView) -> DataFrameView {
+        if self.check_columns(input).is_err() {
+            return input.clone();
+        }
+        match self {
+            Step::GroupBy(_, key_idx, aggregates) => DataFrameView::from_dataframe(grouped_columns(input, *key_idx, aggregates).build()),
+            Step::SelectCols(_, cols) => input.select_cols(cols),
+            Step::Derivative(_, value_idx, time_idx) => {
+                input.with_column(derivative_name(input, *value_idx, *time_idx), DataType::Float, std::iter::empty())
+            }
+            &Step::Resample(_, time_idx, rate, is_millis) => match resample_interval(rate, is_millis) {
+                Ok(interval) => DataFrameView::from_dataframe(resampled_columns(input, &resampled_types(input, time_idx, interval)).build()),
+                Err(_) => input.clone()
+            }
+            Step::Coalesce(_, cols, name) => match coalesced_column(input, cols, name) {
+                Ok((name, ty)) => input.with_column(name, ty, std::iter::empty()),
+                Err(_) => input.clone()
+            }
+            _ => input.clone()
+        }
+    }
+
+    fn apply(&self, mut df: DataFrameView, progress: &Progress) -> Result<DataFrameView, String> {
+        match self {
+            Step::Fill(_, _, _, FillMode::Constant(value)) => fill_constant(df, value, progress),
+            &Step::Fill(_, _, _, FillMode::Linear(time_idx)) => fill_linear(df, time_idx, progress),
+            &Step::Fill(_, is_down, and_before, FillMode::Closest) => {
                 let shape = df.shape();
+                // Filling upwards is filling downwards over the rows in reverse order.
+                let row_at = |i: usize| if is_down { i } else { shape.rows - 1 - i };
 
                 let mut prev_values: Vec<Option<NonZeroU32>> = vec![None; shape.cols];
                 if and_before {
-                    for row_idx in 0..shape.rows {
-                        let row = df.row(row_idx);
+                    for i in 0..shape.rows {
+                        let row = df.row(row_at(i));
                         let mut any_null = false;
-                        for (i, &value) in row.raw_slice().iter().enumerate() {
-                            if prev_values[i].is_none() {
+                        for (col_idx, &value) in row.raw_slice().iter().enumerate() {
+                            if prev_values[col_idx].is_none() {
                                 if let Some(value) = value {
-                                    prev_values[i] = Some(value);
+                                    prev_values[col_idx] = Some(value);
                                 } else {
                                     any_null = true;
                                 }
@@ -95,37 +765,43 @@ impl Step {
                     }
                 }
 
-                for row_idx in 0..shape.rows {
-                    let mut row = df.row_mut(row_idx);
+                for i in 0..shape.rows {
+                    let mut row = df.row_mut(row_at(i));
 
-                    for (i, prev_value) in prev_values.iter_mut().enumerate() {
-                        if let Some(value) = row.get_col_raw(i) {
+                    for (col_idx, prev_value) in prev_values.iter_mut().enumerate() {
+                        if let Some(value) = row.get_col_raw(col_idx) {
                             *prev_value = Some(value);
                         } else {
-                            row.set_col_raw(i, *prev_value);
+                            row.set_col_raw(col_idx, *prev_value);
                         }
                     }
 
-                    progress.set(row_idx as f32 / shape.rows as f32);
+                    progress.set(i as f32 / shape.rows as f32);
                 }
 
-                df
+                Ok(df)
             }
-            Step::ColEq(_, col_idx, value) => {
-                let equal_to = df.df.col(*col_idx).data_type().parse_str(value);
+            Step::ColEq(_, col_idx, value, tolerance, exclude) => {
+                let dtype = df.df.col(*col_idx).data_type();
+                let equal_to = dtype.parse_str(value);
+                // Measured floats rarely equal a typed out value exactly.
+                let tolerance = tolerance.filter(|_| dtype == DataType::Float);
                 let rows = df.shape().rows as f32;
 
                 progress.set(0.0);
                 df.filter_by(*col_idx, |i, data| {
-                    let ret = data.eq(&equal_to);
+                    let ret = match (tolerance, as_f64(*data), as_f64(equal_to)) {
+                        (Some(tolerance), Some(a), Some(b)) => (a - b).abs() <= tolerance,
+                        _ => data.eq(&equal_to)
+                    };
                     if i % 3000 == 0 {
                         progress.set(i as f32 / rows);
                     }
-                    ret
+                    ret != *exclude
                 });
                 progress.set(1.0);
 
-                df
+                Ok(df)
             }
             Step::Within(_, col_idx, has_lower_bound, lower_bound, has_upper_bound, upper_bound) => {
                 let dtype = df.df.col(*col_idx).data_type();
@@ -146,17 +822,14 @@ impl Step {
                 });
                 progress.set(1.0);
 
-                df
+                Ok(df)
             }
-            Step::Sort(_, descending, col_idx) => {
+            Step::Sort(_, keys) => {
                 progress.set(0.0);
-                if *descending {
-                    df.sort_by_desc(*col_idx);
-                } else {
-                    df.sort_by_asc(*col_idx);
-                }
+                let keys: Vec<(usize, bool)> = keys.iter().map(|&(is_desc, col_idx)| (col_idx, !is_desc)).collect();
+                df.sort_by_columns(&keys, |amount| progress.set(amount));
                 progress.set(1.0);
-                df
+                Ok(df)
             }
             Step::Decimate(_, factor) => {
                 let rows = df.shape().rows as f32;
@@ -170,39 +843,351 @@ impl Step {
                 });
                 progress.set(1.0);
 
-                df
+                Ok(df)
+            }
+            Step::DropNull(_, col_idx) => {
+                let rows = df.shape().rows as f32;
+
+                progress.set(0.0);
+                if let Some(col_idx) = *col_idx {
+                    df.filter_by(col_idx, |i, data| {
+                        if i % 3000 == 0 {
+                            progress.set(i as f32 / rows);
+                        }
+                        !data.is_null()
+                    });
+                } else {
+                    df.filter_by_row(|i, row| {
+                        if i % 3000 == 0 {
+                            progress.set(i as f32 / rows);
+                        }
+                        row.raw_slice().iter().all(|value| value.is_some())
+                    });
+                }
+                progress.set(1.0);
+
+                Ok(df)
+            }
+            Step::GroupBy(_, key_idx, aggregates) => {
+                let shape = df.shape();
+
+                // Rows are grouped by the raw key cell, which is unique per value. Empty keys form
+                // their own group. Groups are ordered by their first row.
+                let mut group_of_key: HashMap<Option<NonZeroU32>, usize> = HashMap::new();
+                let mut groups: Vec<Vec<usize>> = vec![];
+                for row_idx in 0..shape.rows {
+                    let key = df.row(row_idx).get_col_raw(*key_idx);
+                    let group_idx = *group_of_key.entry(key).or_insert_with(|| {
+                        groups.push(vec![]);
+                        groups.len() - 1
+                    });
+                    groups[group_idx].push(row_idx);
+
+                    if row_idx % 3000 == 0 {
+                        progress.set(0.5 * row_idx as f32 / shape.rows as f32);
+                    }
+                }
+
+                let mut grouped = grouped_columns(&df, *key_idx, aggregates).build_with_capacity(groups.len());
+
+                let key_col = df.col(*key_idx);
+                let agg_cols: Vec<_> = aggregates.iter().map(|(aggregate, col_idx)| (aggregate, df.col(*col_idx))).collect();
+                let mut row_data = Vec::with_capacity(agg_cols.len() + 2);
+                for (group_idx, rows) in groups.iter().enumerate() {
+                    row_data.clear();
+                    row_data.push(key_col.get_row(rows[0]));
+                    row_data.push(Data::Integer(rows.len() as i32));
+                    for (aggregate, col) in &agg_cols {
+                        row_data.push(aggregate.apply(rows.iter().map(|&row_idx| col.get_row(row_idx))));
+                    }
+                    grouped.add_row(&row_data);
+
+                    progress.set(0.5 + 0.5 * group_idx as f32 / groups.len() as f32);
+                }
+
+                Ok(DataFrameView::from_dataframe(grouped))
+            }
+            Step::SelectCols(_, cols) => {
+                let selected = df.select_cols(cols);
+                progress.set(1.0);
+
+                Ok(selected)
+            }
+            Step::Derivative(_, value_idx, time_idx) => {
+                let rows = df.shape().rows;
+                let value_col = df.col(*value_idx);
+                let time_col = df.col(*time_idx);
+
+                // Each row's value is the change from the previous row, so the first row is empty, as
+                // is any row where either row is missing a value or the time doesn't change.
+                let mut previous: Option<(f64, f64)> = None;
+                let mut derivatives = Vec::with_capacity(rows);
+                for row_idx in 0..rows {
+                    let current = as_f64(value_col.get_row(row_idx)).zip(as_f64(time_col.get_row(row_idx)));
+                    let derivative = match (previous, current) {
+                        (Some((prev_value, prev_time)), Some((value, time))) if time != prev_time => {
+                            Data::Float(((value - prev_value) / (time - prev_time)) as f32)
+                        }
+                        _ => Data::Null
+                    };
+                    derivatives.push(derivative);
+                    previous = current;
+
+                    if row_idx % 3000 == 0 {
+                        progress.set(0.5 * row_idx as f32 / rows as f32);
+                    }
+                }
+
+                let derived = df.with_column(derivative_name(&df, *value_idx, *time_idx), DataType::Float, derivatives);
+                progress.set(1.0);
+
+                Ok(derived)
+            }
+            &Step::Resample(_, time_idx, rate, is_millis) => resample(&df, time_idx, resample_interval(rate, is_millis)?, progress),
+            &Step::Clip(_, col_idx, lower, upper, mode) => clip(df, col_idx, lower, upper, mode, progress),
+            &Step::Scale(_, col_idx, gain, offset) => scale(df, col_idx, gain, offset),
+            Step::Coalesce(_, cols, name) => coalesce(&df, cols, name, progress),
+            Step::Query(_, query) => {
+                if let Some(expr) = query::parse(query)? {
+                    expr.filter(&mut df, progress)?;
+                }
+                Ok(df)
+            }
+            &Step::DecimateTime(_, time_idx, interval, group_idx) => {
+                decimate_time(&mut df, time_idx, interval, group_idx, progress);
+                Ok(df)
             }
         }
     }
 }
 
+/// How many step results [`StepCache`] holds on to. Each can be a frame as large as the data, so
+/// only those of the last steps are kept, which are the ones most often changed or added to.
+const STEP_CACHE_RESULTS: usize = 4;
+
+/// The data the last steps of the last applied pipeline returned, so that applying it again only
+/// runs the steps from the first one that changed.
+#[derive(Default)]
+struct StepCache {
+    /// The data the steps were applied to.
+    source: Option<DataFrameView>,
+    /// The settings of each step that was run, in order, with the data it returned if that is
+    /// still kept. Results are shared with the task that ran them, rather than copied.
+    results: Vec<(String, Option<Arc<DataFrameView>>)>
+}
+
+impl StepCache {
+    /// Whether the steps were applied to `data`.
+    fn is_from(&self, data: &DataFrameView) -> bool {
+        self.source.as_ref().is_some_and(|source| Arc::ptr_eq(&source.df, &data.df) && source.rows == data.rows)
+    }
+
+    /// Forgets the results of every step from the first that isn't in `keys` in the same place, or
+    /// all of them if the steps weren't applied to `source`. Steps after the last kept result of the
+    /// unchanged ones are forgotten too, since they have to be run again. Returns the data to run
+    /// the remaining steps on.
+    fn reuse(&mut self, source: &DataFrameView, keys: &[String]) -> DataFrameView {
+        if !self.is_from(source) {
+            self.source = Some(source.clone());
+            drop_in_background(std::mem::take(&mut self.results));
+        }
+
+        let unchanged = self.results.iter().zip(keys).take_while(|((old, _), new)| old == *new).count();
+        let kept = self.results[..unchanged].iter().rposition(|(_, data)| data.is_some()).map_or(0, |idx| idx + 1);
+        drop_in_background(self.results.split_off(kept));
+        self.results.last().and_then(|(_, data)| data.as_deref()).map_or_else(|| source.clone(), DataFrameView::clone)
+    }
+
+    /// Adds the result of the next step, and lets go of the oldest kept result if there are too many.
+    fn push(&mut self, key: String, data: Arc<DataFrameView>) {
+        self.results.push((key, Some(data)));
+        if let Some(oldest) = self.results.iter_mut().rev().filter(|(_, data)| data.is_some()).nth(STEP_CACHE_RESULTS) {
+            drop_in_background(oldest.1.take());
+        }
+    }
+
+    fn clear(&mut self) {
+        self.source = None;
+        drop_in_background(std::mem::take(&mut self.results));
+    }
+}
+
+/// The steps of the pipeline that are on, which are the ones run when it is applied.
+fn enabled_steps(steps: &[PipelineStep]) -> Vec<Step> {
+    steps.iter().filter(|step| step.enabled).map(|step| step.step.clone()).collect()
+}
+
+/// Runs `steps` on `data`, reusing the results in `cache` of the leading steps that haven't changed
+/// since they were last run on it, and adding the results of the rest.
+fn apply_steps(steps: &[Step], data: &DataFrameView, cache: &Mutex<StepCache>, progress: &Progress) -> Result<DataFrameView, String> {
+    let keys: Vec<String> = steps.iter().map(Step::settings_key).collect();
+    let mut cache = cache.lock().unwrap();
+    let mut data = cache.reuse(data, &keys);
+
+    let reused = cache.results.len();
+    for (i, (step, key)) in steps.iter().zip(keys).enumerate().skip(reused) {
+        progress.set_text(format!("Step {}/{}", i+1, steps.len()));
+        progress.set(0.0);
+        step.check_columns(&data)?;
+        data = step.apply(data, progress)?;
+        cache.push(key, Arc::new(data.clone()));
+    }
+
+    Ok(data)
+}
+
+/// The frame of the complete data and how many rows it has, which change whenever it does.
+type DataKey = (Weak<DataFrame>, usize);
+
 pub struct ProcessTab {
-    steps: Vec<Step>,
+    steps: Vec<PipelineStep>,
     step_id: u64,
     add_step_type: StepType,
 
-    task: Option<ProgressTask<Result<DataFrameView, String>>>
+    pipeline_path: String,
+
+    /// Data kept to compare logs loaded later against.
+    baseline: Option<DataFrameView>,
+    /// The column rows are matched by when comparing, or `None` to match them by position.
+    compare_key: Option<usize>,
+    /// Whether the running task compares with the baseline, rather than applying the steps.
+    comparing: bool,
+
+    /// Shared with the applying task, which adds the result of each step as it's run.
+    step_cache: Arc<Mutex<StepCache>>,
+    /// The distinct strings of each column listed by an 'Equals' box, for the frame and number of
+    /// rows of the complete data they were found in.
+    distinct_strs: Option<(DataKey, HashMap<usize, Vec<String>>)>,
+
+    task: Option<ProgressTask<Result<DataFrameView, String>>>,
+    message: Option<String>
 }
 
 impl ProcessTab {
-    pub fn new(_cc: &eframe::CreationContext) -> ProcessTab {
-        ProcessTab {
+    pub fn new(cc: &eframe::CreationContext) -> ProcessTab {
+        ProcessTab::from_storage(cc.storage)
+    }
+
+    /// The tab with the steps and pipeline path saved in `storage`, or the default steps without.
+    fn from_storage(storage: Option<&dyn Storage>) -> ProcessTab {
+        let pipeline_path = storage.and_then(|storage| storage.get_string("process-pipeline-path")).unwrap_or("".to_string());
+
+        let mut tab = ProcessTab {
             steps: vec![
-                Step::Sort(0, false, 1),
-                Step::Fill(1, true, true),
+                Step::Sort(0, vec![(false, 1)]).into(),
+                Step::Fill(1, true, true, FillMode::Closest).into(),
             ],
             step_id: 2,
             add_step_type: StepType::Fill,
 
-            task: None
+            pipeline_path,
+
+            baseline: None,
+            compare_key: None,
+            comparing: false,
+
+            step_cache: Arc::default(),
+            distinct_strs: None,
+
+            task: None,
+            message: None
+        };
+
+        if let Some(steps) = storage.and_then(|storage| storage.get_string("process-steps")) {
+            let _ = tab.load_steps(&steps);
+        }
+        tab
+    }
+
+    /// Whether steps are being applied, during which the shown data shouldn't be changed.
+    pub fn is_applying(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Forgets the results of the last applied steps, which were run on data that is about to grow,
+    /// so that they don't hold on to its frame. Left alone while steps are being applied.
+    pub fn forget_results(&mut self) {
+        if self.task.is_none() {
+            self.step_cache.lock().unwrap().clear();
+        }
+    }
+
+    /// The baseline, if one was set, so that it can be carried over to the grown data.
+    pub fn baseline_mut(&mut self) -> Option<&mut DataFrameView> {
+        self.baseline.as_mut()
+    }
+
+    /// Adds a step keeping only the rows whose value in column `col_idx` is `value`, or if not
+    /// `keep_equal`, dropping them, then applies the steps. The column is one of the shown data,
+    /// which is what the steps gave when they were last applied.
+    pub fn filter_to_cell(&mut self, ctx: &Context, shared: &DataShared, col_idx: usize, value: String, keep_equal: bool) {
+        if self.is_applying() {
+            return;
         }
+        self.steps.push(Step::ColEq(self.step_id, col_idx, value, None, !keep_equal).into());
+        self.step_id += 1;
+        self.apply(ctx, shared);
     }
 
-    pub fn save(&self, _storage: &mut dyn Storage) { }
+    fn apply(&mut self, ctx: &Context, shared: &DataShared) {
+        self.message = None;
+        self.comparing = false;
+        let steps = enabled_steps(&self.steps);
+        let old_data = shared.complete_data.clone();
+        let cache = self.step_cache.clone();
+
+        self.task = Some(ProgressTask::new(ctx, move |progress| {
+            apply_steps(&steps, &old_data, &cache, progress)
+        }));
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set_string("process-steps", self.steps_json());
+        storage.set_string("process-pipeline-path", self.pipeline_path.clone());
+    }
+
+    fn steps_json(&self) -> String {
+        serde_json::to_string_pretty(&self.steps).unwrap()
+    }
+
+    /// Replaces the steps with those in `json`. Steps that can't be read, such as ones from a newer
+    /// version, are skipped and counted in the error.
+    fn load_steps(&mut self, json: &str) -> Result<(), String> {
+        let values: Vec<Value> = serde_json::from_str(json).map_err(|e| format!("Could not read pipeline: {}", e))?;
+
+        let total = values.len();
+        self.steps = values.into_iter().filter_map(|value| serde_json::from_value::<PipelineStep>(value).ok()).collect();
+        let skipped = total - self.steps.len();
+
+        for (id, step) in self.steps.iter_mut().enumerate() {
+            step.step.set_id(id as u64);
+        }
+        self.step_id = self.steps.len() as u64;
+
+        if skipped > 0 {
+            Err(format!("Skipped {} steps that could not be read.", skipped))
+        } else {
+            Ok(())
+        }
+    }
 
     pub fn show(&mut self, ui: &mut Ui, shared: &mut Option<DataShared>) {
+        // Cached results are only of use while the data they came from is loaded.
+        if self.task.is_none() {
+            let loaded = shared.as_ref().map(|shared| &shared.complete_data.df);
+            let mut cache = self.step_cache.lock().unwrap();
+            if cache.source.as_ref().is_some_and(|source| !loaded.is_some_and(|df| Arc::ptr_eq(&source.df, df))) {
+                cache.clear();
+            }
+        }
+
         let Some(shared) = shared else { return; };
 
+        let distinct_key = (Arc::downgrade(&shared.complete_data.df), shared.complete_data.rows.len());
+        if !self.distinct_strs.as_ref().is_some_and(|((df, rows), _)| df.ptr_eq(&distinct_key.0) && *rows == distinct_key.1) {
+            self.distinct_strs = Some((distinct_key, HashMap::new()));
+        }
+
         ui.add_space(3.0);
 
         ui.allocate_ui(ui.available_size(), |ui| {
@@ -215,13 +1200,17 @@ impl ProcessTab {
                         let mut swaps = vec![];
                         let mut dels = vec![];
 
-                        for i in 0..self.steps.len() {
-                            let step = &self.steps[i];
-
-                            let id = ui.make_persistent_id(format!("step-{}", step.id()));
+                        let len = self.steps.len();
+                        let inputs = step_inputs(&self.steps, &shared.complete_data);
+                        for (i, input) in inputs.iter().enumerate() {
+                            let id = ui.make_persistent_id(format!("step-{}", self.steps[i].step.id()));
                             egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
                                 .show_header(ui, |ui| {
-                                    ui.label(format!("{}", step.ty().name()));
+                                    let PipelineStep { step, enabled } = &mut self.steps[i];
+                                    ui.add(egui::Checkbox::without_text(enabled))
+                                        .on_hover_text("Uncheck to skip this step when applying, while keeping its settings.");
+                                    let name = RichText::new(step.ty().name());
+                                    ui.label(if *enabled { name } else { name.weak() });
 
                                     if ui.add_enabled(true, egui::Button::new("-").frame(false)).clicked() {
                                         dels.push(i);
@@ -229,50 +1218,126 @@ impl ProcessTab {
                                     if ui.add_enabled(i > 0, egui::Button::new("^").frame(false)).clicked() {
                                         swaps.push((i, i-1));
                                     }
-                                    if ui.add_enabled(i < self.steps.len()-1, egui::Button::new("v").frame(false)).clicked() {
+                                    if ui.add_enabled(i < len-1, egui::Button::new("v").frame(false)).clicked() {
                                         swaps.push((i, i+1));
                                     }
                                 })
                                 .body(|ui| {
-                                    let step = &mut self.steps[i];
+                                    let step = &mut self.steps[i].step;
+                                    let col_type = |col_idx: usize| (col_idx < input.shape().cols).then(|| input.df.col(col_idx).data_type());
                                     match step {
-                                        Step::Fill(_, is_down, and_before) => {
+                                        Step::Fill(id, is_down, and_before, mode) => {
                                             ui.horizontal(|ui| {
-                                                ui.label("Direction (todo)");
-                                                ui.selectable_value(is_down, true, "Down");
-                                                ui.selectable_value(is_down, false, "Up");
+                                                ui.label("With");
+                                                let is_closest = *mode == FillMode::Closest;
+                                                if ui.selectable_label(is_closest && *is_down, "Previous")
+                                                    .on_hover_text("Fill empty cells with the previous non-empty cell.")
+                                                    .clicked() {
+                                                    *mode = FillMode::Closest;
+                                                    *is_down = true;
+                                                }
+                                                if ui.selectable_label(is_closest && !*is_down, "Next")
+                                                    .on_hover_text("Fill empty cells with the next non-empty cell.")
+                                                    .clicked() {
+                                                    *mode = FillMode::Closest;
+                                                    *is_down = false;
+                                                }
+                                                if ui.selectable_label(matches!(mode, FillMode::Constant(_)), "Constant")
+                                                    .on_hover_text("Fill the empty cells of number columns with a value.")
+                                                    .clicked() && !matches!(mode, FillMode::Constant(_)) {
+                                                    *mode = FillMode::Constant("0".to_string());
+                                                }
+                                                if ui.selectable_label(matches!(mode, FillMode::Linear(_)), "Interpolation")
+                                                    .on_hover_text("Fill the empty cells of number columns with values on the line between the cells around them.")
+                                                    .clicked() && !matches!(mode, FillMode::Linear(_)) {
+                                                    *mode = FillMode::Linear(0);
+                                                }
                                             });
 
-                                            ui.horizontal(|ui| {
-                                                ui.label("Backfill");
-                                                ui.add(egui::Checkbox::without_text(and_before));
-                                            });
+                                            match mode {
+                                                FillMode::Closest => {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Backfill");
+                                                        ui.add(egui::Checkbox::without_text(and_before))
+                                                            .on_hover_text(if *is_down {
+                                                                "Also fill the empty cells at the start of the table."
+                                                            } else {
+                                                                "Also fill the empty cells at the end of the table."
+                                                            });
+                                                    });
+                                                }
+                                                FillMode::Constant(value) => {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Value");
+                                                        ui.text_edit_singleline(value);
+                                                    });
+                                                }
+                                                FillMode::Linear(time_idx) => {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Over");
+
+                                                        column_combo(ui, format!("combo-fill-time-{id}"), input, time_idx);
+                                                    });
+                                                }
+                                            }
                                         }
-                                        Step::ColEq(id, col_idx, eq_value) => {
+                                        Step::ColEq(id, col_idx, eq_value, tolerance, exclude) => {
                                             ui.horizontal(|ui| {
                                                 ui.label("Where");
 
-                                                egui::ComboBox::from_id_source(format!("combo-where-{id}"))
-                                                    .wrap(true)
-                                                    .show_index(ui, col_idx, shared.complete_data.shape().cols, |idx| shared.complete_data.col_name(idx));
+                                                column_combo(ui, format!("combo-where-{id}"), input, col_idx);
                                             });
 
                                             ui.horizontal(|ui| {
                                                 ui.label("Equals");
 
-                                                ui.add(egui::TextEdit::singleline(eq_value)
-                                                    .id_source(format!("text-{id}"))
-                                                    .hint_text("...")
-                                                    .clip_text(true));
+                                                // The strings of the column in the complete data, which the steps can only
+                                                // have fewer of, if it is still there.
+                                                let source_idx = shared.complete_data.col_names()
+                                                    .position(|name| name == col_name_or_missing(input, *col_idx))
+                                                    .filter(|&source_idx| shared.complete_data.df.col(source_idx).data_type() == DataType::Intern);
+                                                if let (Some(DataType::Intern), Some(source_idx)) = (col_type(*col_idx), source_idx) {
+                                                    egui::ComboBox::from_id_source(format!("combo-equals-{id}"))
+                                                        .wrap(true)
+                                                        .selected_text(eq_value.as_str())
+                                                        .show_ui(ui, |ui| {
+                                                            let (_, distinct_strs) = self.distinct_strs.as_mut().unwrap();
+                                                            let values = distinct_strs.entry(source_idx).or_insert_with(|| {
+                                                                shared.complete_data.distinct_strs(source_idx).into_iter().map(str::to_string).collect()
+                                                            });
+                                                            for value in values.iter() {
+                                                                if ui.selectable_label(eq_value == value, value).clicked() {
+                                                                    eq_value.clone_from(value);
+                                                                }
+                                                            }
+                                                        });
+                                                } else {
+                                                    ui.add(egui::TextEdit::singleline(eq_value)
+                                                        .id_source(format!("text-{id}"))
+                                                        .hint_text("...")
+                                                        .clip_text(true));
+                                                }
                                             });
+
+                                            if col_type(*col_idx) == Some(DataType::Float) {
+                                                ui.horizontal(|ui| {
+                                                    let mut has_tolerance = tolerance.is_some();
+                                                    ui.checkbox(&mut has_tolerance, "Within ±")
+                                                        .on_hover_text("Also keep values this close to the given value, since measured decimals are rarely exact.");
+                                                    let mut value = tolerance.unwrap_or(0.01);
+                                                    ui.add_enabled(has_tolerance, egui::DragValue::new(&mut value).speed(0.001).clamp_range(0.0..=f64::MAX));
+                                                    *tolerance = has_tolerance.then_some(value);
+                                                });
+                                            }
+
+                                            ui.checkbox(exclude, "Drop these rows")
+                                                .on_hover_text("Keep every other row instead, including empty ones.");
                                         }
                                         Step::Within(id, col_idx, has_lower_bound, lower_bound, has_upper_bound, upper_bound) => {
                                             ui.horizontal(|ui| {
                                                 ui.label("Where");
 
-                                                egui::ComboBox::from_id_source(format!("combo-within-{id}"))
-                                                    .wrap(true)
-                                                    .show_index(ui, col_idx, shared.complete_data.shape().cols, |idx| shared.complete_data.col_name(idx));
+                                                column_combo(ui, format!("combo-within-{id}"), input, col_idx);
                                             });
 
                                             ui.horizontal(|ui| {
@@ -289,37 +1354,237 @@ impl ProcessTab {
                                                 ui.text_edit_singleline(upper_bound);
                                             });
                                         }
-                                        Step::Sort(id, is_desc, col_idx) => {
+                                        Step::Sort(id, keys) => {
+                                            let can_delete = keys.len() > 1;
+                                            let mut key_del = None;
+                                            for (key_idx, (is_desc, col_idx)) in keys.iter_mut().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    ui.label(if key_idx == 0 { "By" } else { "Then By" });
+
+                                                    column_combo(ui, format!("combo-by-{id}-{key_idx}"), input, col_idx);
+
+                                                    egui::ComboBox::from_id_source(format!("combo-sort-{id}-{key_idx}"))
+                                                        .width(90.0)
+                                                        .selected_text(if *is_desc { "Descending" } else { "Ascending" })
+                                                        .show_ui(ui, |ui| {
+                                                            ui.selectable_value(is_desc, false, "Ascending");
+                                                            ui.selectable_value(is_desc, true, "Descending");
+                                                        });
+
+                                                    if ui.add_enabled(can_delete, egui::Button::new("-").frame(false)).clicked() {
+                                                        key_del = Some(key_idx);
+                                                    }
+                                                });
+                                            }
+                                            if let Some(key_idx) = key_del {
+                                                keys.remove(key_idx);
+                                            }
+
+                                            if ui.button("Add Key").clicked() {
+                                                keys.push((false, 0));
+                                            }
+                                        }
+                                        Step::Decimate(_, factor) => {
+                                            ui.horizontal(|ui| {
+                                                ui.label("Factor");
+                                                ui.add(egui::DragValue::new(factor).clamp_range(1..=50000))
+                                            });
+                                        }
+                                        Step::DecimateTime(id, time_idx, interval, group_idx) => {
                                             ui.horizontal(|ui| {
-                                                ui.label("Sort");
-                                                egui::ComboBox::from_id_source(format!("combo-sort-{id}"))
-                                                    .selected_text(if *is_desc { "Descending" } else { "Ascending" })
-                                                    .show_ui(ui, |ui| {
-                                                        ui.selectable_value(is_desc, false, "Ascending");
-                                                        ui.selectable_value(is_desc, true, "Descending");
-                                                    });
+                                                ui.label("Time");
+
+                                                column_combo(ui, format!("combo-decimate-time-{id}"), input, time_idx);
+                                            });
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("At Most Every");
+                                                ui.add(egui::DragValue::new(interval).clamp_range(1..=3_600_000).suffix(" ms"));
                                             });
 
+                                            ui.horizontal(|ui| {
+                                                ui.label("For Each");
+
+                                                optional_column_combo(ui, format!("combo-decimate-group-{id}"), input, group_idx, "<all rows>");
+                                            });
+                                        }
+                                        Step::DropNull(id, col_idx) => {
+                                            ui.horizontal(|ui| {
+                                                ui.label("Where");
+
+                                                optional_column_combo(ui, format!("combo-drop-null-{id}"), input, col_idx, "<any column>");
+                                            });
+
+                                            ui.label("Is empty");
+                                        }
+                                        Step::GroupBy(id, key_idx, aggregates) => {
                                             ui.horizontal(|ui| {
                                                 ui.label("By");
 
-                                                egui::ComboBox::from_id_source(format!("combo-by-{id}"))
-                                                    .show_index(ui, col_idx, shared.complete_data.shape().cols, |idx| shared.complete_data.col_name(idx));
+                                                column_combo(ui, format!("combo-group-by-{id}"), input, key_idx);
+                                            });
+
+                                            let mut agg_del = None;
+                                            for (agg_idx, (aggregate, col_idx)) in aggregates.iter_mut().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    egui::ComboBox::from_id_source(format!("combo-aggregate-{id}-{agg_idx}"))
+                                                        .width(60.0)
+                                                        .selected_text(aggregate.name())
+                                                        .show_ui(ui, |ui| {
+                                                            for option in Aggregate::ALL {
+                                                                ui.selectable_value(aggregate, option, option.name());
+                                                            }
+                                                        });
+
+                                                    column_combo(ui, format!("combo-aggregate-col-{id}-{agg_idx}"), input, col_idx);
+
+                                                    if ui.add(egui::Button::new("-").frame(false)).clicked() {
+                                                        agg_del = Some(agg_idx);
+                                                    }
+                                                });
+                                            }
+                                            if let Some(agg_idx) = agg_del {
+                                                aggregates.remove(agg_idx);
+                                            }
+
+                                            if ui.button("Add Aggregate").clicked() {
+                                                aggregates.push((Aggregate::Mean, 0));
+                                            }
+                                        }
+                                        Step::Derivative(id, value_idx, time_idx) => {
+                                            ui.horizontal(|ui| {
+                                                ui.label("Of");
+
+                                                column_combo(ui, format!("combo-derivative-value-{id}"), input, value_idx);
+                                            });
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Over");
+
+                                                column_combo(ui, format!("combo-derivative-time-{id}"), input, time_idx);
                                             });
                                         }
-                                        Step::Decimate(_, factor) => {
+                                        Step::Resample(id, time_idx, rate, is_millis) => {
                                             ui.horizontal(|ui| {
-                                                ui.label("Factor");
-                                                ui.add(egui::DragValue::new(factor).clamp_range(1..=50000))
+                                                ui.label("Time");
+
+                                                column_combo(ui, format!("combo-resample-time-{id}"), input, time_idx);
+                                            });
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("In");
+                                                ui.selectable_value(is_millis, true, "Milliseconds");
+                                                ui.selectable_value(is_millis, false, "Seconds");
+                                            });
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Rate");
+                                                ui.add(egui::DragValue::new(rate).clamp_range(0.001..=100000.0).suffix(" Hz"));
+                                            });
+                                        }
+                                        Step::Clip(id, col_idx, lower, upper, mode) => {
+                                            ui.horizontal(|ui| {
+                                                ui.label("Column");
+
+                                                column_combo(ui, format!("combo-clip-{id}"), input, col_idx);
+                                            });
+
+                                            for (bound, name) in [(lower, "Lower"), (upper, "Upper")] {
+                                                ui.horizontal(|ui| {
+                                                    let mut has_bound = bound.is_some();
+                                                    ui.checkbox(&mut has_bound, name);
+                                                    let mut value = bound.unwrap_or(0.0);
+                                                    ui.add_enabled(has_bound, egui::DragValue::new(&mut value));
+                                                    *bound = has_bound.then_some(value);
+                                                });
+                                            }
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Outside");
+                                                ui.selectable_value(mode, ClipMode::Clamp, "Clamp")
+                                                    .on_hover_text("Set values outside the range to the bound they passed.");
+                                                ui.selectable_value(mode, ClipMode::Nullify, "Empty")
+                                                    .on_hover_text("Empty the cells with values outside the range.");
+                                            });
+                                        }
+                                        Step::Scale(id, col_idx, gain, offset) => {
+                                            ui.horizontal(|ui| {
+                                                ui.label("Column");
+
+                                                column_combo(ui, format!("combo-scale-{id}"), input, col_idx);
+                                            });
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Gain");
+                                                ui.add(egui::DragValue::new(gain).speed(0.01));
+                                                ui.label("Offset");
+                                                ui.add(egui::DragValue::new(offset).speed(0.01));
+                                            }).response.on_hover_text("Each value becomes gain × value + offset.");
+                                        }
+                                        Step::Coalesce(id, cols, name) => {
+                                            let mut col_del = None;
+                                            for (sel_idx, col_idx) in cols.iter().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    ui.label(if sel_idx == 0 { "First" } else { "Else" });
+                                                    ui.label(col_name_or_missing(input, *col_idx));
+
+                                                    if ui.add(egui::Button::new("-").frame(false)).clicked() {
+                                                        col_del = Some(sel_idx);
+                                                    }
+                                                });
+                                            }
+                                            if let Some(sel_idx) = col_del {
+                                                cols.remove(sel_idx);
+                                            }
+
+                                            add_column_combo(ui, format!("combo-coalesce-{id}"), input, cols);
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Name");
+                                                ui.add(egui::TextEdit::singleline(name)
+                                                    .id_source(format!("text-coalesce-{id}"))
+                                                    .hint_text("coalesce(...)")
+                                                    .clip_text(true));
+                                            });
+                                        }
+                                        Step::Query(id, query) => {
+                                            ui.horizontal(|ui| {
+                                                ui.add(egui::TextEdit::singleline(query)
+                                                    .id_source(format!("text-query-{id}"))
+                                                    .hint_text("timestamp > 1000 AND sensor == \"IMU\"")
+                                                    .code_editor());
+
+                                                let checked = query::parse(query)
+                                                    .and_then(|expr| expr.map_or(Ok(()), |expr| expr.check(input)));
+                                                if let Err(msg) = checked {
+                                                    ui.colored_label(Color32::RED, "!").on_hover_text(msg);
+                                                }
                                             });
                                         }
+                                        Step::SelectCols(id, cols) => {
+                                            let mut col_del = None;
+                                            for (sel_idx, col_idx) in cols.iter().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    ui.label(col_name_or_missing(input, *col_idx));
+
+                                                    if ui.add(egui::Button::new("-").frame(false)).clicked() {
+                                                        col_del = Some(sel_idx);
+                                                    }
+                                                });
+                                            }
+                                            if let Some(sel_idx) = col_del {
+                                                cols.remove(sel_idx);
+                                            }
+
+                                            add_column_combo(ui, format!("combo-select-cols-{id}"), input, cols);
+                                        }
                                     }
                                 });
                         }
 
                         ui.horizontal(|ui| {
                             if ui.button("Add").clicked() {
-                                self.steps.push(self.add_step_type.to_step(self.step_id));
+                                self.steps.push(self.add_step_type.to_step(self.step_id).into());
                                 self.step_id +=1;
                             }
 
@@ -330,6 +1595,16 @@ impl ProcessTab {
                                     ui.selectable_value(&mut self.add_step_type, StepType::ColEq, StepType::ColEq.name());
                                     ui.selectable_value(&mut self.add_step_type, StepType::Within, StepType::Within.name());
                                     ui.selectable_value(&mut self.add_step_type, StepType::Sort, StepType::Sort.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::DropNull, StepType::DropNull.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::GroupBy, StepType::GroupBy.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::SelectCols, StepType::SelectCols.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Derivative, StepType::Derivative.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Resample, StepType::Resample.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Clip, StepType::Clip.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Scale, StepType::Scale.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Coalesce, StepType::Coalesce.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::Query, StepType::Query.name());
+                                    ui.selectable_value(&mut self.add_step_type, StepType::DecimateTime, StepType::DecimateTime.name());
                                 });
                         });
 
@@ -349,11 +1624,15 @@ impl ProcessTab {
                     if task.is_finished() {
                         let result = self.task.take().unwrap().handle.join().unwrap();
                         match result {
+                            Ok(dataframe) if self.comparing => {
+                                shared.show_diff(dataframe);
+                            }
                             Ok(dataframe) => {
-                                shared.shown_data = dataframe;
-                                shared.version += 1;
+                                shared.show(dataframe);
+                            }
+                            Err(msg) => {
+                                self.message = Some(msg);
                             }
-                            Err(_) => { }
                         }
                     }
                 }
@@ -371,22 +1650,550 @@ impl ProcessTab {
                     ui.add(egui::ProgressBar::new(task.progress()).text(text));
                 } else {
                     if ui.button("Apply").clicked() {
-                        let steps = self.steps.clone();
-                        let old_data = shared.complete_data.clone();
-
-                        self.task = Some(ProgressTask::new(ui.ctx(), move |progress| {
-                            let mut data = old_data;
-                            for (i, step) in steps.iter().enumerate() {
-                                progress.set_text(format!("Step {}/{}", i+1, steps.len()));
-                                progress.set(0.0);
-                                data = step.apply(data, progress);
-                            }
+                        self.apply(ui.ctx(), shared);
+                    }
+                }
+
+                if ui.add_enabled(self.task.is_none() && !shared.undo_history.is_empty(), egui::Button::new("Undo"))
+                    .on_hover_text("Show the previously applied result.")
+                    .clicked() {
+                    shared.undo();
+                }
+                if ui.add_enabled(self.task.is_none() && !shared.redo_history.is_empty(), egui::Button::new("Redo")).clicked() {
+                    shared.redo();
+                }
+                if ui.add_enabled(self.task.is_none(), egui::Button::new("Reset View"))
+                    .on_hover_text("Show the complete data again, without any steps applied. (Ctrl+R)")
+                    .clicked() {
+                    shared.reset();
+                }
+
+                if let Some(msg) = &self.message {
+                    ui.colored_label(Color32::RED, "!").on_hover_text(msg);
+                }
+            });
+
+            ui.add_space(3.0);
 
-                            Ok(data)
-                        }));
+            egui::CollapsingHeader::new("Pipeline File").id_source("pipeline-file-header").show(ui, |ui| {
+                ui.add(FilePicker::new("pipeline-picker", &mut self.pipeline_path)
+                    .dialog_title("Pipeline")
+                    .add_filter("JSON", &["json"])
+                    .set_is_save(true));
+
+                ui.horizontal(|ui| {
+                    let has_path = !self.pipeline_path.is_empty();
+                    if ui.add_enabled(has_path, egui::Button::new("Save Pipeline")).clicked() {
+                        self.message = std::fs::write(&self.pipeline_path, self.steps_json())
+                            .err()
+                            .map(|e| format!("Could not save pipeline: {}", e));
+                    }
+                    if ui.add_enabled(has_path, egui::Button::new("Load Pipeline")).clicked() {
+                        self.message = std::fs::read_to_string(&self.pipeline_path)
+                            .map_err(|e| format!("Could not read pipeline: {}", e))
+                            .and_then(|json| self.load_steps(&json))
+                            .err();
                     }
+                });
+            });
+
+            egui::CollapsingHeader::new("Compare Logs").id_source("compare-logs-header").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.task.is_none(), egui::Button::new("Set Baseline"))
+                        .on_hover_text("Keep the shown data to compare data loaded later against.")
+                        .clicked() {
+                        self.baseline = Some(shared.shown_data.clone());
+                    }
+                    if let Some(baseline) = &self.baseline {
+                        ui.label(format!("{} rows", baseline.shape().rows));
+                    }
+                });
+
+                if self.compare_key.is_some_and(|idx| idx >= shared.shown_data.shape().cols) {
+                    self.compare_key = None;
+                }
+                ui.horizontal(|ui| {
+                    optional_column_combo(ui, "compare-key-combo", &shared.shown_data, &mut self.compare_key, "<row number>");
+                    ui.label("Match Rows By");
+                });
+
+                let can_compare = self.task.is_none() && self.baseline.is_some();
+                if ui.add_enabled(can_compare, egui::Button::new("Compare"))
+                    .on_hover_text("Show how the shown data differs from the baseline.")
+                    .on_disabled_hover_text("Set a baseline first.")
+                    .clicked() {
+                    self.message = None;
+                    self.comparing = true;
+                    let baseline = self.baseline.clone().unwrap();
+                    let current = shared.shown_data.clone();
+                    let key = self.compare_key;
+
+                    self.task = Some(ProgressTask::new(ui.ctx(), move |progress| {
+                        let diff = current.diff(&baseline, key);
+                        progress.set(1.0);
+                        diff
+                    }));
                 }
             });
         });
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+
+    use crate::Progress;
+    use super::{Aggregate, ClipMode, FillMode, PipelineStep, ProcessTab, Step, StepCache, STEP_CACHE_RESULTS, apply_steps, enabled_steps, step_inputs};
+
+    fn frame(columns: &[(&str, DataType)], rows: &[&[Data]]) -> DataFrameView {
+        let mut builder = DataFrameBuilder::new();
+        for (name, ty) in columns {
+            builder.add_column(*name, *ty);
+        }
+        let mut df = builder.build();
+        for row in rows {
+            df.add_row(row);
+        }
+        DataFrameView::from_dataframe(df)
+    }
+
+    /// Each cell of column `col`, as shown in the table.
+    fn cells(df: &DataFrameView, col: usize) -> Vec<String> {
+        df.col(col).iter().map(|data| data.to_string()).collect()
+    }
+
+    /// The results `cache` holds on to, by step.
+    fn cached(cache: &Mutex<StepCache>) -> Vec<Option<Arc<DataFrameView>>> {
+        cache.lock().unwrap().results.iter().map(|(_, data)| data.clone()).collect()
+    }
+
+    fn same(a: &Option<Arc<DataFrameView>>, b: &Option<Arc<DataFrameView>>) -> bool {
+        matches!((a, b), (Some(a), Some(b)) if Arc::ptr_eq(a, b))
+    }
+
+    #[test]
+    fn changing_a_step_runs_only_it_and_the_steps_after() {
+        let df = frame(&[("value", DataType::Integer)], &[&[Data::Integer(3)], &[Data::Integer(1)], &[Data::Integer(2)], &[Data::Integer(4)]]);
+        let cache = Mutex::default();
+        let mut steps = vec![Step::Sort(0, vec![(false, 0)]), Step::Decimate(1, 1), Step::Sort(2, vec![(true, 0)])];
+        apply_steps(&steps, &df, &cache, &Progress::detached()).unwrap();
+        let before = cached(&cache);
+
+        steps[1] = Step::Decimate(1, 2);
+        let result = apply_steps(&steps, &df, &cache, &Progress::detached()).unwrap();
+        let after = cached(&cache);
+        assert_eq!(cells(&result, 0), ["3", "1"]);
+        assert!(same(&before[0], &after[0]));
+        assert!(!same(&before[1], &after[1]) && !same(&before[2], &after[2]));
+    }
+
+    #[test]
+    fn keeps_only_the_results_of_the_last_steps() {
+        let df = frame(&[("value", DataType::Integer)], &[&[Data::Integer(1)], &[Data::Integer(2)]]);
+        let cache = Mutex::default();
+        let len = STEP_CACHE_RESULTS + 2;
+        let mut steps: Vec<Step> = (0..len as u64).map(|id| Step::Decimate(id, 1)).collect();
+        apply_steps(&steps, &df, &cache, &Progress::detached()).unwrap();
+        let before = cached(&cache);
+        assert!(before[..2].iter().all(Option::is_none) && before[2..].iter().all(Option::is_some));
+
+        // Changing the last step starts from the kept result of the one before it.
+        steps[len - 1] = Step::Decimate(len as u64 - 1, 2);
+        apply_steps(&steps, &df, &cache, &Progress::detached()).unwrap();
+        let after = cached(&cache);
+        assert!(same(&before[len - 2], &after[len - 2]) && !same(&before[len - 1], &after[len - 1]));
+
+        // Changing the first runs every step again from the source data.
+        steps[0] = Step::Decimate(0, 2);
+        assert_eq!(cells(&apply_steps(&steps, &df, &cache, &Progress::detached()).unwrap(), 0), ["1"]);
+        assert!(!same(&after[len - 2], &cached(&cache)[len - 2]));
+    }
+
+    #[test]
+    fn fills_with_a_constant_the_columns_it_can_be_read_as() {
+        let df = frame(&[("whole", DataType::Integer), ("value", DataType::Float), ("name", DataType::Intern)], &[
+            &[Data::Null, Data::Null, Data::Null],
+            &[Data::Integer(1), Data::Float(2.0), Data::Str("a")],
+            &[Data::Null, Data::Null, Data::Null],
+        ]);
+        let filled = Step::Fill(0, true, true, FillMode::Constant("1.5".to_string())).apply(df.clone(), &Progress::detached()).unwrap();
+        // `1.5` isn't a whole number, and text columns are never filled.
+        assert_eq!(cells(&filled, 0), ["", "1", ""]);
+        assert_eq!(cells(&filled, 1), ["1.5", "2", "1.5"]);
+        assert_eq!(cells(&filled, 2), ["", "a", ""]);
+
+        let filled = Step::Fill(0, true, true, FillMode::Constant(" -3 ".to_string())).apply(df.clone(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&filled, 0), ["-3", "1", "-3"]);
+        assert!(Step::Fill(0, true, true, FillMode::Constant("abc".to_string())).apply(df, &Progress::detached()).is_err());
+    }
+
+    #[test]
+    fn fills_linearly_only_between_two_values() {
+        let df = frame(&[("time", DataType::Float), ("whole", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Float(0.0), Data::Null, Data::Null],
+            &[Data::Float(1.0), Data::Integer(0), Data::Float(1.0)],
+            &[Data::Float(2.0), Data::Null, Data::Null],
+            &[Data::Float(4.0), Data::Integer(3), Data::Null],
+            &[Data::Null, Data::Null, Data::Float(9.0)],
+            &[Data::Float(5.0), Data::Null, Data::Null],
+        ]);
+        let filled = Step::Fill(0, true, true, FillMode::Linear(0)).apply(df, &Progress::detached()).unwrap();
+        // The first and last cells have a value on one side only, so they stay empty, as does the
+        // row without a time. Whole numbers are rounded.
+        assert_eq!(cells(&filled, 1), ["", "0", "1", "3", "", ""]);
+        // The only other value of this column is in the row without a time, so there is none after.
+        assert_eq!(cells(&filled, 2), ["", "1", "", "", "9", ""]);
+    }
+
+    #[test]
+    fn linear_fill_needs_sorted_times() {
+        let df = frame(&[("time", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Integer(2), Data::Float(1.0)],
+            &[Data::Integer(1), Data::Null],
+        ]);
+        assert!(Step::Fill(0, true, true, FillMode::Linear(0)).apply(df, &Progress::detached()).is_err());
+    }
+
+    #[test]
+    fn group_by_count_gets_a_unique_name() {
+        let df = frame(&[("count", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Integer(1), Data::Float(2.0)],
+            &[Data::Integer(1), Data::Float(4.0)],
+            &[Data::Integer(5), Data::Float(1.0)],
+        ]);
+        let step = Step::GroupBy(0, 0, vec![(Aggregate::Count, 1), (Aggregate::Mean, 1)]);
+        let grouped = step.apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!(grouped.col_names().collect::<Vec<_>>(), ["count", "count.1", "count(value)", "mean(value)"]);
+        assert_eq!(cells(&grouped, 0), ["1", "5"]);
+        assert_eq!(cells(&grouped, 1), ["2", "1"]);
+        assert_eq!(cells(&grouped, 3), ["3", "1"]);
+    }
+
+    #[test]
+    fn groups_by_a_text_column_in_order_of_first_row() {
+        let df = frame(&[("sensor", DataType::Intern), ("value", DataType::Float), ("count", DataType::Integer)], &[
+            &[Data::Str("Imu"), Data::Float(1.0), Data::Integer(1)],
+            &[Data::Str("Gps"), Data::Float(10.0), Data::Integer(2)],
+            &[Data::Str("Imu"), Data::Float(2.0), Data::Integer(3)],
+            &[Data::Null, Data::Float(5.0), Data::Integer(4)],
+            &[Data::Str("Imu"), Data::Null, Data::Integer(5)],
+        ]);
+        let step = Step::GroupBy(0, 0, vec![(Aggregate::Mean, 1), (Aggregate::Count, 1), (Aggregate::Max, 2)]);
+        let columns = step.output_columns(&df);
+        let grouped = step.apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!(grouped.col_names().collect::<Vec<_>>(), ["sensor", "count", "mean(value)", "count(value)", "max(count)"]);
+        assert_eq!(columns.col_names().collect::<Vec<_>>(), grouped.col_names().collect::<Vec<_>>());
+        // Empty keys form a group of their own.
+        assert_eq!(cells(&grouped, 0), ["Imu", "Gps", ""]);
+        assert_eq!(cells(&grouped, 1), ["3", "1", "1"]);
+        // The empty value is left out of the mean and count.
+        assert_eq!(cells(&grouped, 2), ["1.5", "10", "5"]);
+        assert_eq!(cells(&grouped, 3), ["2", "1", "1"]);
+        assert_eq!(cells(&grouped, 4), ["5", "2", "4"]);
+    }
+
+    #[test]
+    fn select_keeps_or_drops_the_equal_rows() {
+        let df = || frame(&[("sensor", DataType::Intern), ("value", DataType::Integer)], &[
+            &[Data::Str("Imu"), Data::Integer(1)],
+            &[Data::Str("Gps"), Data::Integer(2)],
+            &[Data::Null, Data::Integer(3)],
+        ]);
+        let kept = Step::ColEq(0, 0, "Gps".to_string(), None, false).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&kept, 1), ["2"]);
+        // Empty cells aren't equal, so they are kept when the equal rows are dropped.
+        let dropped = Step::ColEq(0, 0, "Gps".to_string(), None, true).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&dropped, 1), ["1", "3"]);
+
+        let old: Step = serde_json::from_str(r#"{"ColEq": [0, 0, "Gps"]}"#).unwrap();
+        assert!(matches!(old, Step::ColEq(_, _, _, None, false)));
+    }
+
+    #[test]
+    fn resample_keeps_an_integer_time_column_on_a_whole_grid() {
+        let df = frame(&[("time", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Integer(0), Data::Float(0.0)],
+            &[Data::Integer(10), Data::Float(1.0)],
+        ]);
+        let resampled = Step::Resample(0, 0, 200.0, true).apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!(resampled.df.col(0).data_type().name(), "Integer");
+        assert_eq!(cells(&resampled, 0), ["0", "5", "10"]);
+        assert_eq!(cells(&resampled, 1), ["0", "0.5", "1"]);
+    }
+
+    #[test]
+    fn resample_makes_the_time_column_float_on_a_fractional_grid() {
+        let df = frame(&[("time", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Integer(0), Data::Float(0.0)],
+            &[Data::Integer(5), Data::Float(1.0)],
+        ]);
+        let resampled = Step::Resample(0, 0, 400.0, true).apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!(resampled.df.col(0).data_type().name(), "Float");
+        assert_eq!(cells(&resampled, 0), ["0", "2.5", "5"]);
+        assert_eq!(cells(&resampled, 1), ["0", "0.5", "1"]);
+    }
+
+    #[test]
+    fn selects_columns_in_the_order_picked() {
+        let df = frame(&[("time", DataType::Integer), ("sensor", DataType::Intern), ("value", DataType::Float)], &[
+            &[Data::Integer(2), Data::Str("Imu"), Data::Float(1.5)],
+            &[Data::Integer(1), Data::Str("Gps"), Data::Null],
+        ]);
+        let selected = Step::SelectCols(0, vec![2, 0]).apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!((selected.shape().rows, selected.shape().cols), (2, 2));
+        assert_eq!(selected.col_names().collect::<Vec<_>>(), ["value", "time"]);
+        assert_eq!(cells(&selected, 0), ["1.5", ""]);
+        assert_eq!(cells(&selected, 1), ["2", "1"]);
+    }
+
+    #[test]
+    fn steps_pick_from_the_columns_of_the_steps_before() {
+        let df = frame(&[("time", DataType::Integer), ("sensor", DataType::Intern), ("value", DataType::Float)], &[
+            &[Data::Integer(1), Data::Str("Imu"), Data::Float(3.0)],
+            &[Data::Integer(2), Data::Str("Gps"), Data::Float(1.0)],
+        ]);
+        let steps: Vec<PipelineStep> = vec![
+            Step::SelectCols(0, vec![2, 0]).into(),
+            Step::Derivative(1, 0, 1).into(),
+            Step::Sort(2, vec![(false, 0)]).into(),
+        ];
+        let inputs = step_inputs(&steps, &df);
+        let names = |input: &DataFrameView| input.col_names().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(names(&inputs[0]), ["time", "sensor", "value"]);
+        assert_eq!(names(&inputs[1]), ["value", "time"]);
+        assert_eq!(names(&inputs[2]), ["value", "time", "d(value)/d(time)"]);
+        assert!(inputs.iter().all(|input| input.shape().rows == 0));
+
+        // Column 0 of the sort is 'value', as listed for it, rather than 'time' of the complete data.
+        let steps: Vec<Step> = steps.into_iter().map(|step| step.step).collect();
+        let result = apply_steps(&steps, &df, &Mutex::default(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&result, 1), ["2", "1"]);
+    }
+
+    #[test]
+    fn scale_keeps_the_column_type_rounding_or_emptying_what_doesnt_fit() {
+        let df = || frame(&[("whole", DataType::Integer), ("count", DataType::Unsigned), ("value", DataType::Float), ("name", DataType::Intern)], &[
+            &[Data::Integer(1), Data::Unsigned(5), Data::Float(0.5), Data::Str("a")],
+            &[Data::Integer(-2), Data::Unsigned(0), Data::Float(-1.0), Data::Str("b")],
+            &[Data::Null, Data::Null, Data::Null, Data::Null],
+            &[Data::Integer(1_000_000_000), Data::Unsigned(1), Data::Float(4.0), Data::Str("c")],
+        ]);
+        let scaled = |col_idx, gain, offset| {
+            let scaled = Step::Scale(0, col_idx, gain, offset).apply(df(), &Progress::detached()).unwrap();
+            assert_eq!(scaled.df.col(col_idx).data_type().name(), df().df.col(col_idx).data_type().name());
+            cells(&scaled, col_idx)
+        };
+
+        // 2.5 * 1 + 1 rounds up to 4, and 2.5e9 is past the largest integer.
+        assert_eq!(scaled(0, 2.5, 1.0), ["4", "-4", "", ""]);
+        assert_eq!(scaled(1, -1.0, 3.0), ["", "3", "", "2"]);
+        assert_eq!(scaled(2, 2.0, -0.5), ["0.5", "-2.5", "", "7.5"]);
+        assert!(Step::Scale(0, 3, 2.0, 0.0).apply(df(), &Progress::detached()).is_err());
+    }
+
+    #[test]
+    fn fills_from_the_closest_cell_either_way_and_optionally_the_ends() {
+        let df = frame(&[("value", DataType::Integer), ("name", DataType::Intern)], &[
+            &[Data::Null, Data::Null],
+            &[Data::Integer(1), Data::Null],
+            &[Data::Null, Data::Str("a")],
+            &[Data::Integer(3), Data::Null],
+            &[Data::Null, Data::Str("b")],
+            &[Data::Null, Data::Null],
+        ]);
+        let filled = |is_down, and_before| {
+            let filled = Step::Fill(0, is_down, and_before, FillMode::Closest).apply(df.clone(), &Progress::detached()).unwrap();
+            [cells(&filled, 0), cells(&filled, 1)]
+        };
+
+        // The cells at the start have nothing above them to fill from, unless the ends are filled too.
+        assert_eq!(filled(true, false), [["", "1", "1", "3", "3", "3"], ["", "", "a", "a", "b", "b"]]);
+        assert_eq!(filled(true, true), [["1", "1", "1", "3", "3", "3"], ["a", "a", "a", "a", "b", "b"]]);
+        // Filling upwards, it is the cells at the end that have nothing below them.
+        assert_eq!(filled(false, false), [["1", "1", "3", "3", "", ""], ["a", "a", "a", "b", "b", ""]]);
+        assert_eq!(filled(false, true), [["1", "1", "3", "3", "3", "3"], ["a", "a", "a", "b", "b", "b"]]);
+    }
+
+    #[test]
+    fn drops_rows_empty_in_one_column_or_any() {
+        let df = || frame(&[("time", DataType::Integer), ("value", DataType::Float), ("name", DataType::Intern)], &[
+            &[Data::Integer(1), Data::Float(1.0), Data::Str("a")],
+            &[Data::Integer(2), Data::Null, Data::Str("b")],
+            &[Data::Integer(3), Data::Float(3.0), Data::Null],
+            &[Data::Null, Data::Float(4.0), Data::Str("d")],
+        ]);
+
+        let dropped = Step::DropNull(0, Some(1)).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&dropped, 1), ["1", "3", "4"]);
+        let dropped = Step::DropNull(0, Some(0)).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&dropped, 0), ["1", "2", "3"]);
+        let dropped = Step::DropNull(0, None).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&dropped, 0), ["1"]);
+    }
+
+    #[test]
+    fn derives_over_time_leaving_out_repeated_times_and_missing_values() {
+        let df = frame(&[("time", DataType::Integer), ("altitude", DataType::Float)], &[
+            &[Data::Integer(0), Data::Float(100.0)],
+            &[Data::Integer(2), Data::Float(110.0)],
+            &[Data::Integer(2), Data::Float(111.0)],
+            &[Data::Integer(4), Data::Float(115.0)],
+            &[Data::Integer(5), Data::Null],
+            &[Data::Integer(6), Data::Float(105.0)],
+            &[Data::Integer(8), Data::Float(101.0)],
+        ]);
+        let derived = Step::Derivative(0, 1, 0).apply(df, &Progress::detached()).unwrap();
+
+        assert_eq!(derived.col_names().collect::<Vec<_>>(), ["time", "altitude", "d(altitude)/d(time)"]);
+        assert_eq!(derived.df.col(2).data_type().name(), "Float");
+        // The first row has no row before it, the third has the same time as the one before, and
+        // the rows on either side of the missing altitude have nothing to compare with.
+        assert_eq!(cells(&derived, 2), ["", "5", "", "2", "", "", "-2"]);
+    }
+
+    #[test]
+    fn saves_and_loads_steps_including_those_turned_off() {
+        let mut tab = ProcessTab::from_storage(None);
+        tab.steps = vec![
+            Step::Sort(0, vec![(true, 1)]).into(),
+            PipelineStep { step: Step::Decimate(1, 4), enabled: false },
+            Step::ColEq(2, 0, "Imu".to_string(), Some(0.5), true).into(),
+        ];
+        let json = tab.steps_json();
+        // Steps that are on are saved as they were before steps could be turned off.
+        assert!(!json.contains("\"enabled\": true"));
+
+        let mut loaded = ProcessTab::from_storage(None);
+        loaded.load_steps(&json).unwrap();
+        assert_eq!(loaded.steps_json(), json);
+        assert_eq!(loaded.steps.iter().map(|step| step.enabled).collect::<Vec<_>>(), [true, false, true]);
+        assert_eq!(loaded.step_id, 3);
+
+        // A step from a newer version is skipped, and the steps after it get new ids.
+        let mut values: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        values.insert(1, serde_json::json!({"Smooth": [5, 0, 3]}));
+        let error = loaded.load_steps(&serde_json::to_string(&values).unwrap()).unwrap_err();
+        assert_eq!(error, "Skipped 1 steps that could not be read.");
+        assert_eq!(loaded.steps_json(), json);
+        assert!(loaded.load_steps("not a pipeline").is_err());
+    }
+
+    #[test]
+    fn clips_to_the_bounds_or_empties_values_past_them() {
+        let df = || frame(&[("whole", DataType::Integer), ("value", DataType::Float)], &[
+            &[Data::Integer(-5), Data::Float(-0.5)],
+            &[Data::Integer(1), Data::Float(1.0)],
+            &[Data::Integer(3), Data::Float(2.25)],
+            &[Data::Null, Data::Null],
+            &[Data::Integer(8), Data::Float(4.0)],
+        ]);
+        let clipped = |col_idx, lower, upper, mode| {
+            cells(&Step::Clip(0, col_idx, lower, upper, mode).apply(df(), &Progress::detached()).unwrap(), col_idx)
+        };
+
+        // Values on a bound are kept, and empty cells stay empty.
+        assert_eq!(clipped(1, Some(1.0), Some(2.25), ClipMode::Clamp), ["1", "1", "2.25", "", "2.25"]);
+        assert_eq!(clipped(1, Some(1.0), Some(2.25), ClipMode::Nullify), ["", "1", "2.25", "", ""]);
+        assert_eq!(clipped(1, None, Some(0.0), ClipMode::Clamp), ["-0.5", "0", "0", "", "0"]);
+        // Whole number columns get their bounds rounded inwards, to 1 and 3.
+        assert_eq!(clipped(0, Some(0.5), Some(3.5), ClipMode::Clamp), ["1", "1", "3", "", "3"]);
+        assert_eq!(clipped(0, Some(0.5), Some(3.5), ClipMode::Nullify), ["", "1", "3", "", ""]);
+
+        assert!(Step::Clip(0, 1, Some(2.0), Some(1.0), ClipMode::Clamp).apply(df(), &Progress::detached()).is_err());
+    }
+
+    #[test]
+    fn merges_sparse_columns_into_the_first_value_of_each_row() {
+        let df = || frame(&[("Imu.alt", DataType::Float), ("Baro.alt", DataType::Integer), ("Gps.alt", DataType::Intern)], &[
+            &[Data::Float(1.5), Data::Null, Data::Null],
+            &[Data::Null, Data::Integer(2), Data::Null],
+            &[Data::Null, Data::Null, Data::Str("3m")],
+            &[Data::Float(4.5), Data::Integer(5), Data::Null],
+            &[Data::Null, Data::Null, Data::Null],
+        ]);
+
+        let merged = Step::Coalesce(0, vec![0, 1], String::new()).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(merged.col_name(3), "coalesce(Imu.alt, Baro.alt)");
+        // Integers merged with floats become floats, and the first column is preferred.
+        assert_eq!(merged.df.col(3).data_type().name(), "Float");
+        assert_eq!(cells(&merged, 3), ["1.5", "2", "", "4.5", ""]);
+
+        let merged = Step::Coalesce(0, vec![1, 2, 0], "altitude".to_string()).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(merged.col_name(3), "altitude");
+        // Numbers merged with text are written out as text.
+        assert_eq!(merged.df.col(3).data_type().name(), "String");
+        assert_eq!(cells(&merged, 3), ["1.5", "2", "3m", "5", ""]);
+
+        assert!(Step::Coalesce(0, vec![], String::new()).apply(df(), &Progress::detached()).is_err());
+    }
+
+    #[test]
+    fn decimates_an_irregular_series_by_time_overall_or_per_group() {
+        let df = || frame(&[("time", DataType::Unsigned), ("sensor", DataType::Intern)], &[
+            &[Data::Unsigned(0), Data::Str("Imu")],
+            &[Data::Unsigned(3), Data::Str("Gps")],
+            &[Data::Unsigned(7), Data::Str("Imu")],
+            &[Data::Unsigned(10), Data::Str("Imu")],
+            &[Data::Null, Data::Str("Gps")],
+            &[Data::Unsigned(12), Data::Str("Gps")],
+            &[Data::Unsigned(19), Data::Str("Imu")],
+            &[Data::Unsigned(21), Data::Str("Gps")],
+        ]);
+
+        // Rows without a time are always kept.
+        let decimated = Step::DecimateTime(0, 0, 10, None).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&decimated, 0), ["0", "10", "", "21"]);
+
+        let decimated = Step::DecimateTime(0, 0, 10, Some(1)).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&decimated, 0), ["0", "3", "10", "", "21"]);
+        assert_eq!(cells(&decimated, 1), ["Imu", "Gps", "Imu", "Gps", "Gps"]);
+    }
+
+    #[test]
+    fn keeps_floats_within_the_tolerance_of_the_value() {
+        let df = || frame(&[("value", DataType::Float), ("whole", DataType::Integer)], &[
+            &[Data::Float(0.98), Data::Integer(1)],
+            &[Data::Float(1.0), Data::Integer(2)],
+            &[Data::Float(1.04), Data::Integer(1)],
+            &[Data::Float(1.2), Data::Integer(3)],
+            &[Data::Null, Data::Integer(1)],
+        ]);
+
+        let kept = Step::ColEq(0, 0, "1".to_string(), Some(0.05), false).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&kept, 0), ["0.98", "1", "1.04"]);
+        let dropped = Step::ColEq(0, 0, "1".to_string(), Some(0.05), true).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&dropped, 0), ["1.2", ""]);
+        // Without a tolerance, only an exact match is equal.
+        let exact = Step::ColEq(0, 0, "1".to_string(), None, false).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&exact, 0), ["1"]);
+        // Whole numbers are always compared exactly.
+        let whole = Step::ColEq(0, 1, "2".to_string(), Some(1.5), false).apply(df(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&whole, 1), ["2"]);
+    }
+
+    #[test]
+    fn steps_turned_off_are_not_run() {
+        let df = frame(&[("value", DataType::Integer)], &[&[Data::Integer(3)], &[Data::Integer(1)], &[Data::Integer(2)]]);
+        let steps: Vec<PipelineStep> = vec![
+            Step::Sort(0, vec![(false, 0)]).into(),
+            PipelineStep { step: Step::Decimate(1, 2), enabled: false },
+            PipelineStep { step: Step::SelectCols(2, vec![5]), enabled: false },
+        ];
+        let enabled = enabled_steps(&steps);
+        assert_eq!(enabled.iter().map(Step::id).collect::<Vec<_>>(), [0]);
+
+        // The step picking a column that doesn't exist would fail if it were run.
+        let result = apply_steps(&enabled, &df, &Mutex::default(), &Progress::detached()).unwrap();
+        assert_eq!(cells(&result, 0), ["1", "2", "3"]);
+    }
+}
+
@@ -0,0 +1,446 @@
+use std::cmp::Ordering;
+
+use dataframe::{Data, DataFrameView, DataType, Row};
+
+use crate::Progress;
+
+/// A filter typed out as text, such as `timestamp > 1000 AND sensor == "IMU"`.
+///
+/// Columns are referred to by name, in backticks if the name has spaces or symbols in it. Values
+/// are numbers, text in double or single quotes, or `null` for empty cells. Comparisons are joined
+/// with `AND`, `OR` and `NOT`, which bind in that order from loosest to tightest, and can be grouped
+/// with parentheses.
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+}
+
+pub enum Operand {
+    Column(String),
+    Number(String),
+    Str(String),
+    Null
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge
+}
+
+impl CompareOp {
+    fn test(self, a: &Data, b: &Data) -> bool {
+        // Numbers of different types, such as an integer column and a decimal, are compared as floats.
+        let ordering = a.compare(b).or_else(|| a.as_float()?.partial_cmp(&b.as_float()?));
+        match self {
+            CompareOp::Eq => a.eq(b) || ordering.is_some_and(Ordering::is_eq),
+            CompareOp::Ne => !(a.eq(b) || ordering.is_some_and(Ordering::is_eq)),
+            CompareOp::Lt => ordering.is_some_and(Ordering::is_lt),
+            CompareOp::Le => ordering.is_some_and(Ordering::is_le),
+            CompareOp::Gt => ordering.is_some_and(Ordering::is_gt),
+            CompareOp::Ge => ordering.is_some_and(Ordering::is_ge),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(CompareOp),
+    Column(String),
+    Number(String),
+    Str(String),
+    Null
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::Op(_) => "a comparison".to_string(),
+            Token::Column(name) => format!("'{}'", name),
+            Token::Number(num) => num.clone(),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::Null => "null".to_string(),
+        }
+    }
+}
+
+/// Characters that end a bare column name or number.
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "()=!<>&|\"'`".contains(c)
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = query.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let token = match c {
+            '(' => { chars.next(); Token::LParen }
+            ')' => { chars.next(); Token::RParen }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.next_if(|&(_, c)| c == '=').is_some();
+                Token::Op(match (c, followed_by_eq) {
+                    ('=', _) => CompareOp::Eq,
+                    ('!', true) => CompareOp::Ne,
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    ('>', true) => CompareOp::Ge,
+                    _ => return Err("'!' must be followed by '=', use NOT to negate.".to_string())
+                })
+            }
+            '&' | '|' => {
+                chars.next();
+                let (token, word) = if c == '&' { (Token::And, "AND") } else { (Token::Or, "OR") };
+                if chars.next_if(|&(_, next)| next == c).is_none() {
+                    return Err(format!("'{}' must be doubled, or use {}.", c, word));
+                }
+                token
+            }
+            '"' | '\'' | '`' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => {
+                            if let Some((_, escaped)) = chars.next() {
+                                text.push(escaped);
+                            }
+                        }
+                        Some((_, end)) if end == c => break,
+                        Some((_, other)) => text.push(other),
+                        None => return Err(format!("The text starting at character {} is missing its closing {}.", start + 1, c))
+                    }
+                }
+                if c == '`' { Token::Column(text) } else { Token::Str(text) }
+            }
+            _ => {
+                let mut end = query.len();
+                while let Some(&(idx, c)) = chars.peek() {
+                    if is_delimiter(c) {
+                        end = idx;
+                        break;
+                    }
+                    chars.next();
+                }
+                let word = &query[start..end];
+
+                if word.eq_ignore_ascii_case("and") {
+                    Token::And
+                } else if word.eq_ignore_ascii_case("or") {
+                    Token::Or
+                } else if word.eq_ignore_ascii_case("not") {
+                    Token::Not
+                } else if word.eq_ignore_ascii_case("null") {
+                    Token::Null
+                } else if word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+                    if word.parse::<f64>().is_err() {
+                        return Err(format!("'{}' is not a number. Put column names that start with a digit in backticks.", word));
+                    }
+                    Token::Number(word.to_string())
+                } else {
+                    Token::Column(word.to_string())
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn found(&self) -> String {
+        self.peek().map_or("the end of the query".to_string(), Token::describe)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else if self.eat(&Token::LParen) {
+            let expr = self.or()?;
+            if !self.eat(&Token::RParen) {
+                return Err(format!("Expected ')' but found {}.", self.found()));
+            }
+            Ok(expr)
+        } else {
+            let lhs = self.operand()?;
+            let op = match self.peek() {
+                Some(&Token::Op(op)) => op,
+                _ => return Err(format!("Expected a comparison such as '==' or '>' but found {}.", self.found()))
+            };
+            self.pos += 1;
+            let rhs = self.operand()?;
+            Ok(Expr::Compare(lhs, op, rhs))
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        let operand = match self.peek() {
+            Some(Token::Column(name)) => Operand::Column(name.clone()),
+            Some(Token::Number(num)) => Operand::Number(num.clone()),
+            Some(Token::Str(s)) => Operand::Str(s.clone()),
+            Some(Token::Null) => Operand::Null,
+            _ => return Err(format!("Expected a column or value but found {}.", self.found()))
+        };
+        self.pos += 1;
+        Ok(operand)
+    }
+}
+
+/// Reads `query`. An empty query is `None`, and keeps every row.
+pub fn parse(query: &str) -> Result<Option<Expr>, String> {
+    let mut parser = Parser { tokens: tokenize(query)?, pos: 0 };
+    if parser.tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let expr = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(format!("Expected AND or OR but found {}.", parser.found()));
+    }
+    Ok(Some(expr))
+}
+
+/// An [`Expr`] with its columns found and its values read as the type of the column they are
+/// compared with.
+enum Compiled<'q> {
+    And(Box<Compiled<'q>>, Box<Compiled<'q>>),
+    Or(Box<Compiled<'q>>, Box<Compiled<'q>>),
+    Not(Box<Compiled<'q>>),
+    Compare(Term<'q>, CompareOp, Term<'q>),
+}
+
+enum Term<'q> {
+    Column(usize),
+    Value(Data<'q>)
+}
+
+impl Term<'_> {
+    fn get<'a>(&'a self, row: &Row<'a>) -> Data<'a> {
+        match self {
+            Term::Column(col_idx) => row.get_col(*col_idx),
+            Term::Value(value) => *value
+        }
+    }
+}
+
+impl Expr {
+    /// Checks that the columns exist in `df` and that the values can be compared with them.
+    pub fn check(&self, df: &DataFrameView) -> Result<(), String> {
+        self.compile(df).map(|_| ())
+    }
+
+    /// Keeps the rows of `df` that the query holds for.
+    pub fn filter(&self, df: &mut DataFrameView, progress: &Progress) -> Result<(), String> {
+        let compiled = self.compile(df)?;
+        let rows = df.shape().rows as f32;
+
+        progress.set(0.0);
+        df.filter_by_row(|i, row| {
+            if i % 3000 == 0 {
+                progress.set(i as f32 / rows);
+            }
+            compiled.test(row)
+        });
+        progress.set(1.0);
+
+        Ok(())
+    }
+
+    fn compile(&self, df: &DataFrameView) -> Result<Compiled<'_>, String> {
+        Ok(match self {
+            Expr::And(a, b) => Compiled::And(Box::new(a.compile(df)?), Box::new(b.compile(df)?)),
+            Expr::Or(a, b) => Compiled::Or(Box::new(a.compile(df)?), Box::new(b.compile(df)?)),
+            Expr::Not(a) => Compiled::Not(Box::new(a.compile(df)?)),
+            Expr::Compare(lhs, op, rhs) => {
+                let lhs_col = lhs.column(df)?;
+                let rhs_col = rhs.column(df)?;
+                let lhs_type = lhs_col.map(|col_idx| (df.col_name(col_idx), df.df.col(col_idx).data_type()));
+                let rhs_type = rhs_col.map(|col_idx| (df.col_name(col_idx), df.df.col(col_idx).data_type()));
+                Compiled::Compare(lhs.term(lhs_col, rhs_type)?, *op, rhs.term(rhs_col, lhs_type)?)
+            }
+        })
+    }
+}
+
+impl Operand {
+    fn column(&self, df: &DataFrameView) -> Result<Option<usize>, String> {
+        match self {
+            Operand::Column(name) => df.col_names().position(|col_name| col_name == name)
+                .map(Some)
+                .ok_or_else(|| format!("There is no column named '{}'.", name)),
+            _ => Ok(None)
+        }
+    }
+
+    /// The operand as a term, with its value read as the type of the column it is compared with.
+    fn term(&self, col_idx: Option<usize>, compared_with: Option<(&str, DataType)>) -> Result<Term<'_>, String> {
+        if let Some(col_idx) = col_idx {
+            return Ok(Term::Column(col_idx));
+        }
+
+        let value = match (self, compared_with) {
+            (Operand::Column(_), _) => unreachable!(),
+            (Operand::Null, _) => Data::Null,
+            (Operand::Number(num), Some((_, DataType::Intern))) => Data::Str(num),
+            (Operand::Number(num), Some((_, ty))) => match ty.parse_str(num) {
+                Data::Null => Data::Float(num.parse().unwrap()),
+                value => value
+            },
+            (Operand::Number(num), None) => Data::Float(num.parse().unwrap()),
+            (Operand::Str(s), Some((_, DataType::Intern)) | None) => Data::Str(s),
+            (Operand::Str(s), Some((name, _))) => {
+                return Err(format!("Column '{}' holds numbers, so it can't be compared with the text \"{}\".", name, s));
+            }
+        };
+        Ok(Term::Value(value))
+    }
+}
+
+impl Compiled<'_> {
+    fn test(&self, row: &Row) -> bool {
+        match self {
+            Compiled::And(a, b) => a.test(row) && b.test(row),
+            Compiled::Or(a, b) => a.test(row) || b.test(row),
+            Compiled::Not(a) => !a.test(row),
+            Compiled::Compare(lhs, op, rhs) => op.test(&lhs.get(row), &rhs.get(row)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dataframe::{Data, DataFrameBuilder, DataFrameView, DataType};
+
+    use crate::Progress;
+    use super::parse;
+
+    fn frame() -> DataFrameView {
+        let mut builder = DataFrameBuilder::new();
+        builder.add_column("id", DataType::Integer);
+        builder.add_column("sensor", DataType::Intern);
+        builder.add_column("raw value", DataType::Float);
+        let mut df = builder.build();
+        df.add_row(&[Data::Integer(1), Data::Str("IMU"), Data::Float(1.5)]);
+        df.add_row(&[Data::Integer(2), Data::Str("GPS"), Data::Null]);
+        df.add_row(&[Data::Integer(3), Data::Str("IMU"), Data::Float(3.0)]);
+        df.add_row(&[Data::Integer(4), Data::Str("42"), Data::Float(0.5)]);
+        DataFrameView::from_dataframe(df)
+    }
+
+    /// The ids of the rows `query` keeps.
+    fn matching(query: &str) -> Vec<String> {
+        let mut df = frame();
+        parse(query).unwrap().unwrap().filter(&mut df, &Progress::detached()).unwrap();
+        df.col(0).iter().map(|data| data.to_string()).collect()
+    }
+
+    /// Why `query` can't be read or run.
+    fn error(query: &str) -> String {
+        match parse(query) {
+            Ok(expr) => expr.unwrap().check(&frame()).unwrap_err(),
+            Err(e) => e
+        }
+    }
+
+    #[test]
+    fn binds_not_then_and_then_or() {
+        assert_eq!(matching("id == 1 OR id == 2 AND sensor == \"IMU\""), ["1"]);
+        assert_eq!(matching("(id == 1 OR id == 2) AND sensor == \"IMU\""), ["1"]);
+        assert_eq!(matching("NOT id == 1 AND sensor == 'IMU'"), ["3"]);
+        assert_eq!(matching("NOT (id == 1 OR sensor == 'IMU')"), ["2", "4"]);
+        assert_eq!(matching("id < 2 || id >= 4 && sensor != 'GPS'"), ["1", "4"]);
+        assert_eq!(matching("id==1&&sensor=='IMU'"), ["1"]);
+        assert_eq!(matching("id==2||sensor=='42'"), ["2", "4"]);
+    }
+
+    #[test]
+    fn reads_column_names_in_backticks() {
+        assert_eq!(matching("`raw value` > 1"), ["1", "3"]);
+        assert_eq!(matching("`id` <= 2"), ["1", "2"]);
+    }
+
+    #[test]
+    fn compares_with_null() {
+        assert_eq!(matching("`raw value` == null"), ["2"]);
+        assert_eq!(matching("`raw value` != NULL"), ["1", "3", "4"]);
+        assert_eq!(matching("`raw value` > null"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn compares_a_number_with_a_text_column_as_text() {
+        assert_eq!(matching("sensor == 42"), ["4"]);
+        assert_eq!(matching("id == 2.0"), ["2"]);
+    }
+
+    #[test]
+    fn explains_each_mistake() {
+        assert_eq!(error("id ! 1"), "'!' must be followed by '=', use NOT to negate.");
+        assert_eq!(error("id == 1 & sensor == 'IMU'"), "'&' must be doubled, or use AND.");
+        assert_eq!(error("sensor == \"IMU"), "The text starting at character 11 is missing its closing \".");
+        assert_eq!(error("1st > 2"), "'1st' is not a number. Put column names that start with a digit in backticks.");
+        assert_eq!(error("(id == 1"), "Expected ')' but found the end of the query.");
+        assert_eq!(error("id 1"), "Expected a comparison such as '==' or '>' but found 1.");
+        assert_eq!(error("id == AND"), "Expected a column or value but found AND.");
+        assert_eq!(error("id == 1 sensor == 'IMU'"), "Expected AND or OR but found 'sensor'.");
+        assert_eq!(error("speed > 1"), "There is no column named 'speed'.");
+        assert_eq!(error("id == 'one'"), "Column 'id' holds numbers, so it can't be compared with the text \"one\".");
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(unix)]
+use std::fs;
+use std::process::Command;
+
+// The conversion test runs a shell script in place of Python, so it and its fixtures are Unix only.
+#[cfg(unix)]
+#[path = "../launch_file/src/test_parser.rs"]
+mod test_parser;
+
+/// The format the firmware logs with, inline so that the test doesn't depend on a checkout of
+/// MIDAS-Software.
+#[cfg(unix)]
+const HEADER: &str = r#"
+#include <cstdint>
+
+struct Baro {
+    float pressure;
+    int32_t count;
+};
+
+enum class ReadingDiscriminant {
+    ID_BARO = 1,
+};
+
+struct LoggedReading {
+    ReadingDiscriminant discriminant;
+    uint32_t timestamp_ms;
+    union {
+        Baro baro;
+    } data;
+};
+"#;
+
+/// What the parser writes for [`HEADER`].
+#[cfg(unix)]
+const SCHEMA: &str = r#"{
+    "<checksum>": 305419896,
+    "Baro": [1, {"type": "struct", "members": {
+        "pressure": {"type": "float", "size": 4},
+        "count": {"type": "int", "signed": true, "size": 4}
+    }}]
+}"#;
+
+/// A launch file of two `Baro` packets.
+#[cfg(unix)]
+fn launch_file() -> Vec<u8> {
+    let mut file = 305419896u32.to_le_bytes().to_vec();
+    for (timestamp, pressure, count) in [(10u32, 1013.25f32, 1i32), (20, 998.5, -2)] {
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&timestamp.to_le_bytes());
+        file.extend_from_slice(&pressure.to_le_bytes());
+        file.extend_from_slice(&count.to_le_bytes());
+    }
+    file
+}
+
+#[cfg(unix)]
+#[test]
+fn converts_a_launch_file_to_csv() {
+    let dir = std::env::temp_dir().join(format!("midas-launch-{}-cli", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let header = dir.join("log_format.h");
+    let parser = dir.join("parser.sh");
+    let input = dir.join("data.launch");
+    let out = dir.join("data.csv");
+    fs::write(&header, HEADER).unwrap();
+    test_parser::write_test_parser(&parser, SCHEMA);
+    fs::write(&input, launch_file()).unwrap();
+
+    // The scripts and the cached schema go in `dir` too, rather than in the user's directories.
+    let output = Command::new(env!("CARGO_BIN_EXE_midas-cli"))
+        .env("XDG_DATA_HOME", &dir)
+        .env("XDG_CACHE_HOME", &dir)
+        .arg("--format").arg(&header)
+        .arg("--python").arg(&parser)
+        .arg("--out").arg(&out)
+        .arg(&input)
+        .output()
+        .unwrap();
+    let csv = fs::read_to_string(&out);
+
+    let _ = fs::remove_dir_all(&dir);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Loaded format 0x12345678"));
+    assert_eq!(csv.unwrap(), "sensor,timestamp,Baro.pressure,Baro.count\nBaro,10,1013.25,1\nBaro,20,998.5,-2\n");
+}
+
+#[test]
+fn fails_without_an_input_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_midas-cli"))
+        .arg("--format").arg("log_format.h")
+        .arg("--out").arg("data.csv")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("No input files."));
+}